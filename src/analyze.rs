@@ -0,0 +1,121 @@
+//! Batch analysis of FEN files.
+//!
+//! Reads one FEN per line, searches each position with the library `Engine`
+//! and writes one tab-separated row per position:
+//! `fen  bestmove  score  depth  nodes`. Blank lines and `#` comments are
+//! skipped; a line that is not a valid FEN still gets a row, with `error` in
+//! place of the move, so rows can be matched back to the input.
+//!
+//! Every position starts from fresh tables (`Searcher::new_game`), so a row
+//! does not depend on what was searched before it.
+
+use crate::engine::Engine;
+use crate::search::SearchLimits;
+use crate::types::Board;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+/// Search every FEN read from `input` and write a TSV row per position to `out`.
+///
+/// Returns the number of rows written.
+pub fn analyze_fens<R: BufRead, W: Write>(
+    engine: &mut Engine,
+    input: R,
+    limits: &SearchLimits,
+    out: &mut W,
+) -> io::Result<usize> {
+    let mut rows = 0;
+    for line in input.lines() {
+        let line = line?;
+        let fen = line.trim();
+        if fen.is_empty() || fen.starts_with('#') {
+            continue;
+        }
+
+        match Board::from_fen(fen) {
+            Ok(board) => {
+                engine.searcher_mut().new_game();
+                engine.set_position(board);
+                let result = engine.search(limits.clone());
+                let best = result.best_move.map_or_else(|| "0000".to_string(), |m| m.to_uci());
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}\t{}",
+                    fen,
+                    best,
+                    result.score,
+                    result.stats.depth.raw(),
+                    result.stats.nodes
+                )?;
+            }
+            Err(_) => writeln!(out, "{}\terror\t-\t0\t0", fen)?,
+        }
+        rows += 1;
+    }
+    Ok(rows)
+}
+
+/// `analyze_fens` over the lines of the file at `path`
+pub fn analyze_file<W: Write>(
+    engine: &mut Engine,
+    path: impl AsRef<Path>,
+    limits: &SearchLimits,
+    out: &mut W,
+) -> io::Result<usize> {
+    let file = File::open(path)?;
+    analyze_fens(engine, BufReader::new(file), limits, out)
+}
+
+/// Limits from the command-line form `depth <n>` or `movetime <ms>`
+/// (a bare number is a depth)
+pub fn parse_limits(args: &[&str]) -> Option<SearchLimits> {
+    match args {
+        ["depth", n] | [n] => Some(SearchLimits::depth(n.parse().ok()?)),
+        ["movetime", ms] => Some(SearchLimits {
+            movetime: Some(ms.parse().ok()?),
+            ..SearchLimits::new()
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_fens_two_rows() {
+        let path = std::env::temp_dir().join(format!("porcupine-analyze-{}.fen", std::process::id()));
+        std::fs::write(
+            &path,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\n\
+             6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\n",
+        )
+        .unwrap();
+
+        let mut engine = Engine::new();
+        let mut out = Vec::new();
+        let rows = analyze_file(&mut engine, &path, &SearchLimits::depth(2), &mut out);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(rows.unwrap(), 2);
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<Vec<&str>> = text.lines().map(|l| l.split('\t').collect()).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|cols| cols.len() == 5));
+        assert_eq!(lines[1][0], "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        assert_eq!(lines[1][1], "a1a8");
+        assert_eq!(lines[1][2], "mate 1");
+        assert!(lines[1][4].parse::<u64>().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        assert_eq!(parse_limits(&["depth", "6"]).unwrap().depth.unwrap().raw(), 6);
+        assert_eq!(parse_limits(&["6"]).unwrap().depth.unwrap().raw(), 6);
+        assert_eq!(parse_limits(&["movetime", "250"]).unwrap().movetime, Some(250));
+        assert!(parse_limits(&["nodes", "100"]).is_none());
+        assert!(parse_limits(&["depth", "x"]).is_none());
+    }
+}
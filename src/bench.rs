@@ -0,0 +1,358 @@
+//! Fixed-position search benchmark and NNUE incremental-update check.
+//!
+//! Runs the full `Searcher` on a fixed set of positions to a fixed depth and
+//! reports the total node count and NPS. With single-threaded search and fresh
+//! tables for every position, the node total is a stable signature: any change
+//! to it means the search tree changed, and NPS tracks search speed (not just eval).
+//!
+//! `run_incremental_check` plays random games and compares the incrementally
+//! updated NNUE evaluation against a from-scratch evaluation after every move.
+//!
+//! `run_endgame_eval_bench` times the endgame eval dispatch on endgame-heavy
+//! positions: the single-pass `endgame::probe` against the old two-pass
+//! `should_use_endgame` + `evaluate`, which counted material twice.
+//!
+//! `run_tt_atomic_bench` runs the single-threaded search against the shared
+//! atomic table and against a plain copy of it, to show Threads=1 pays
+//! nothing for the atomics.
+
+use crate::types::{Board, Depth, Hash, Move, NodeCount, Ply, Score};
+use crate::search::tt::{replacement_slot, BoundType, TTEntry, TranspositionTable, TtProbe, TtStore, CLUSTER_SIZE};
+use crate::search::{search_with_tt, Root, Searcher, SearchLimits};
+use crate::eval::{endgame, nnue, NnueEvaluator, SearchEvaluator};
+use std::cell::Cell;
+use std::time::Instant;
+
+/// Default depth for the search benchmark
+pub const BENCH_DEPTH: i32 = 8;
+
+/// Bench positions (mix of opening, middlegame and endgame)
+pub const BENCH_POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP2BPPP/R2QKB1R w KQ - 0 8",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+    "8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1",
+];
+
+/// Result of a benchmark run
+#[derive(Debug, Clone, Default)]
+pub struct BenchResult {
+    /// Total nodes searched over all positions
+    pub nodes: NodeCount,
+    /// Total wall-clock time in milliseconds
+    pub time_ms: u64,
+    /// Nodes per second over the whole run
+    pub nps: u64,
+}
+
+/// Search every bench position to `depth` and collect node/NPS totals.
+///
+/// Each position gets a fresh single-threaded `Searcher` so results do not
+/// depend on what was searched before.
+pub fn run_search_bench(model: Option<nnue::Model>, depth: i32) -> BenchResult {
+    let mut nodes: NodeCount = 0;
+    let start = Instant::now();
+
+    for fen in BENCH_POSITIONS {
+        let board = match Board::from_fen(fen) {
+            Ok(b) => b,
+            Err(_) => continue,
+        };
+
+        let mut searcher = Searcher::new();
+        searcher.set_nnue(model.clone());
+        searcher.set_position(board);
+        let result = searcher.search(SearchLimits::depth(depth));
+        nodes += result.stats.nodes;
+    }
+
+    let elapsed = start.elapsed();
+    let nanos = elapsed.as_nanos().max(1);
+
+    BenchResult {
+        nodes,
+        time_ms: elapsed.as_millis() as u64,
+        nps: (nodes as u128 * 1_000_000_000 / nanos) as u64,
+    }
+}
+
+/// Result of the incremental-vs-scratch NNUE comparison
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalResult {
+    /// Number of positions compared
+    pub positions: u64,
+    /// Time spent in incremental update + evaluation (ns)
+    pub incremental_ns: u64,
+    /// Time spent in from-scratch evaluation (ns)
+    pub scratch_ns: u64,
+}
+
+impl IncrementalResult {
+    /// Speedup of incremental evaluation over from-scratch evaluation
+    pub fn speedup(&self) -> f64 {
+        self.scratch_ns as f64 / self.incremental_ns.max(1) as f64
+    }
+}
+
+/// Small xorshift PRNG so random games are reproducible from a seed
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Play `games` random games of up to `plies` moves and check after every move
+/// that `NnueEvaluator::update_move` agrees with `evaluate_scratch`.
+///
+/// Panics with the offending FEN and move on the first divergence.
+pub fn run_incremental_check(model: &nnue::Model, seed: u64, games: usize, plies: usize) -> IncrementalResult {
+    let mut rng = Rng::new(seed);
+    let mut result = IncrementalResult::default();
+
+    for _ in 0..games {
+        let mut board = Board::default();
+        let mut evaluator = NnueEvaluator::new(model, &board);
+
+        for _ in 0..plies {
+            let moves = board.generate_moves();
+            if moves.is_empty() {
+                break;
+            }
+            let m: Move = moves.iter().nth(rng.below(moves.len())).unwrap();
+            let new_board = board.make_move_new(m);
+
+            let t = Instant::now();
+            if !evaluator.update_move(&board, m) {
+                evaluator.refresh(&new_board);
+            }
+            let incremental = evaluator.evaluate(&new_board);
+            result.incremental_ns += t.elapsed().as_nanos() as u64;
+
+            let t = Instant::now();
+            let scratch = nnue::evaluate_scratch(model, &new_board);
+            result.scratch_ns += t.elapsed().as_nanos() as u64;
+
+            assert_eq!(
+                incremental, scratch,
+                "NNUE incremental/scratch mismatch after {} in {}",
+                m, board.to_fen()
+            );
+
+            result.positions += 1;
+            board = new_board;
+        }
+    }
+
+    result
+}
+
+/// Endgames for `run_endgame_eval_bench` (won, drawn and balanced)
+pub const ENDGAME_BENCH_POSITIONS: &[&str] = &[
+    "8/8/8/3k4/8/8/8/3QK3 w - - 0 1",
+    "8/8/8/8/3k4/8/8/R3K3 w - - 0 1",
+    "8/8/4k3/8/8/2N5/8/2N1K3 w - - 0 1",
+    "4k3/8/8/3PP3/8/8/8/4K3 w - - 0 1",
+    "8/5k2/8/8/2B5/8/4K3/8 b - - 0 1",
+    "8/8/2k5/8/8/5R2/5P2/6K1 b - - 0 1",
+    "8/4k3/8/8/3p4/8/3PK3/8 w - - 0 1",
+];
+
+/// Result of the endgame eval dispatch benchmark
+#[derive(Debug, Clone, Default)]
+pub struct EndgameEvalResult {
+    /// Positions evaluated per round
+    pub positions: u64,
+    /// Time spent in `endgame::probe` (ns)
+    pub probe_ns: u64,
+    /// Time spent in `should_use_endgame` + `evaluate` (ns)
+    pub two_pass_ns: u64,
+}
+
+impl EndgameEvalResult {
+    /// Speedup of the single-pass probe over the two-pass dispatch
+    pub fn speedup(&self) -> f64 {
+        self.two_pass_ns as f64 / self.probe_ns.max(1) as f64
+    }
+}
+
+/// Time the endgame eval dispatch over the bench endgames and every position
+/// one move away from them, `rounds` times.
+///
+/// Panics if the two dispatch paths ever disagree.
+pub fn run_endgame_eval_bench(rounds: usize) -> EndgameEvalResult {
+    let mut boards = Vec::new();
+    for fen in ENDGAME_BENCH_POSITIONS {
+        let board = match Board::from_fen(fen) {
+            Ok(b) => b,
+            Err(_) => continue,
+        };
+        boards.extend(board.generate_moves().iter().map(|m| board.make_move_new(m)));
+        boards.push(board);
+    }
+
+    let config = endgame::EndgameConfig::default();
+    let mut result = EndgameEvalResult { positions: boards.len() as u64, ..Default::default() };
+    for _ in 0..rounds {
+        let t = Instant::now();
+        let probed: Vec<_> = boards.iter().map(|b| endgame::probe(b, &config)).collect();
+        result.probe_ns += t.elapsed().as_nanos() as u64;
+
+        let t = Instant::now();
+        let two_pass: Vec<_> = boards.iter()
+            .map(|b| endgame::should_use_endgame(b, &config).then(|| endgame::evaluate(b)))
+            .collect();
+        result.two_pass_ns += t.elapsed().as_nanos() as u64;
+
+        assert_eq!(probed, two_pass, "endgame probe disagrees with should_use_endgame + evaluate");
+    }
+
+    result
+}
+
+/// `TranspositionTable` without the atomics: same size, clusters, packing and
+/// replacement, in plain cells. Single-threaded only.
+struct PlainTable {
+    entries: Vec<Cell<u64>>,
+}
+
+impl PlainTable {
+    fn new(len: usize) -> Self {
+        Self { entries: (0..len).map(|_| Cell::new(0)).collect() }
+    }
+
+    fn cluster(&self, hash: Hash) -> &[Cell<u64>] {
+        let base = ((hash as usize) & (self.entries.len() / CLUSTER_SIZE - 1)) * CLUSTER_SIZE;
+        &self.entries[base..base + CLUSTER_SIZE]
+    }
+
+    fn load(&self, hash: Hash) -> [TTEntry; CLUSTER_SIZE] {
+        let mut entries = [TTEntry::default(); CLUSTER_SIZE];
+        for (entry, slot) in entries.iter_mut().zip(self.cluster(hash)) {
+            *entry = TTEntry::from_u64(slot.get());
+        }
+        entries
+    }
+}
+
+impl TtProbe for PlainTable {
+    fn probe(&self, hash: Hash) -> Option<TTEntry> {
+        self.load(hash).into_iter().find(|entry| entry.matches(hash) && !entry.is_empty())
+    }
+}
+
+impl TtStore for PlainTable {
+    fn store(&self, hash: Hash, best_move: Option<Move>, score: Score, depth: Depth, bound: BoundType) {
+        if let Some(i) = replacement_slot(&self.load(hash), hash, depth, 0) {
+            self.cluster(hash)[i].set(TTEntry::new(hash, best_move, score, depth, bound, 0).to_u64());
+        }
+    }
+}
+
+/// Result of the atomic vs plain table comparison
+#[derive(Debug, Clone, Default)]
+pub struct TtAtomicResult {
+    /// Nodes searched with each table (the same tree, so the same count)
+    pub nodes: NodeCount,
+    /// Search time against the shared atomic table (ns)
+    pub atomic_ns: u64,
+    /// Search time against the plain table (ns)
+    pub plain_ns: u64,
+}
+
+impl TtAtomicResult {
+    /// Atomic time over plain time (1.0 = no overhead)
+    pub fn overhead(&self) -> f64 {
+        self.atomic_ns as f64 / self.plain_ns.max(1) as f64
+    }
+}
+
+/// Search every bench position to `depth` on one thread, once with a 16 MB
+/// `TranspositionTable` and once with a `PlainTable` of the same size.
+///
+/// Panics if the two searches visit different node counts: the tables must
+/// behave identically for the timings to be comparable.
+pub fn run_tt_atomic_bench(depth: i32) -> TtAtomicResult {
+    fn search<T: TtProbe + TtStore>(table: &T, depth: i32) -> (NodeCount, u64) {
+        let mut nodes = 0;
+        let start = Instant::now();
+        for fen in BENCH_POSITIONS {
+            let Ok(board) = Board::from_fen(fen) else { continue };
+            let mut searcher = Searcher::new();
+            searcher.set_position(board);
+            for d in 1..=depth {
+                let mut evaluator = SearchEvaluator::new(None, None, &board);
+                search_with_tt::<Root, T>(
+                    &mut searcher,
+                    table,
+                    &mut evaluator,
+                    &board,
+                    Depth::new(d),
+                    Ply::ZERO,
+                    Score::neg_infinity(),
+                    Score::infinity(),
+                    None,
+                );
+            }
+            nodes += searcher.take_stats().nodes;
+        }
+        (nodes, start.elapsed().as_nanos() as u64)
+    }
+
+    let atomic = TranspositionTable::new(16);
+    let plain = PlainTable::new(atomic.len());
+    let (nodes, atomic_ns) = search(&atomic, depth);
+    let (plain_nodes, plain_ns) = search(&plain, depth);
+    assert_eq!(nodes, plain_nodes, "plain table searched a different tree");
+
+    TtAtomicResult { nodes, atomic_ns, plain_ns }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_bench_smoke() {
+        let result = run_search_bench(None, 3);
+        assert!(result.nodes > 0);
+        assert!(result.nps > 0);
+    }
+
+    #[test]
+    fn test_tt_atomic_bench_smoke() {
+        let result = run_tt_atomic_bench(3);
+        assert!(result.nodes > 0);
+        assert!(result.overhead() > 0.0);
+    }
+
+    #[test]
+    fn test_endgame_eval_bench_smoke() {
+        let result = run_endgame_eval_bench(2);
+        assert!(result.positions > ENDGAME_BENCH_POSITIONS.len() as u64);
+    }
+
+    #[test]
+    fn test_nnue_incremental_matches_scratch() {
+        let model = nnue::load_embedded_model().expect("embedded NNUE");
+        let result = run_incremental_check(&model, 0x5EED, 16, 120);
+        assert!(result.positions > 0);
+    }
+}
@@ -0,0 +1,34 @@
+//! Batch analysis: `cargo run --release --bin analyze <file> <depth <n> | movetime <ms>>`
+//!
+//! Searches every FEN in the file (one per line) and prints
+//! `fen  bestmove  score  depth  nodes` as tab-separated rows on stdout.
+
+use porcupine::analyze::{analyze_file, parse_limits};
+use porcupine::engine::Engine;
+use porcupine::eval::nnue;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let (path, limits) = match args.split_first() {
+        Some((path, rest)) => (path, parse_limits(rest)),
+        None => (&"", None),
+    };
+    let Some(limits) = limits else {
+        eprintln!("usage: analyze <file> <depth <n> | movetime <ms>>");
+        std::process::exit(2);
+    };
+
+    let mut engine = Engine::new();
+    match nnue::load_embedded_model() {
+        Ok(model) => engine.searcher_mut().set_nnue(Some(model)),
+        Err(_) => eprintln!("NNUE load failed, analyzing with HCE"),
+    }
+
+    let mut out = std::io::stdout().lock();
+    if let Err(e) = analyze_file(&mut engine, path, &limits, &mut out) {
+        eprintln!("analyze {}: {}", path, e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,55 @@
+//! Search benchmark: `cargo run --release --bin benchmark [depth]`
+//!
+//! Prints the total node count (a signature of the search tree) and NPS,
+//! compares the single-threaded search on the atomic and a plain TT, then
+//! times the endgame eval dispatch and checks NNUE incremental updates
+//! against from-scratch evaluation.
+
+use porcupine::bench::{
+    run_search_bench, run_incremental_check, run_endgame_eval_bench, run_tt_atomic_bench, BENCH_DEPTH, BENCH_POSITIONS,
+};
+use porcupine::eval::nnue;
+
+fn main() {
+    let depth = std::env::args()
+        .nth(1)
+        .and_then(|s| s.parse::<i32>().ok())
+        .unwrap_or(BENCH_DEPTH);
+
+    let model = nnue::load_embedded_model().ok();
+    if model.is_none() {
+        eprintln!("NNUE load failed, benchmarking with HCE");
+    }
+
+    let result = run_search_bench(model.clone(), depth);
+
+    println!("===========================");
+    println!("Positions       : {}", BENCH_POSITIONS.len());
+    println!("Depth           : {}", depth);
+    println!("Total time (ms) : {}", result.time_ms);
+    println!("Nodes searched  : {}", result.nodes);
+    println!("Nodes/second    : {}", result.nps);
+
+    let tt = run_tt_atomic_bench(depth.min(BENCH_DEPTH));
+    println!("===========================");
+    println!("Threads=1 nodes : {}", tt.nodes);
+    println!("Atomic TT (ms)  : {}", tt.atomic_ns / 1_000_000);
+    println!("Plain TT (ms)   : {}", tt.plain_ns / 1_000_000);
+    println!("Atomic overhead : {:.2}x", tt.overhead());
+
+    let endgame = run_endgame_eval_bench(2000);
+    println!("===========================");
+    println!("Endgame evals   : {}", endgame.positions * 2000);
+    println!("Probe (ns)      : {}", endgame.probe_ns);
+    println!("Two-pass (ns)   : {}", endgame.two_pass_ns);
+    println!("Speedup         : {:.2}x", endgame.speedup());
+
+    if let Some(m) = &model {
+        let inc = run_incremental_check(m, 0x5EED, 64, 200);
+        println!("===========================");
+        println!("NNUE positions  : {}", inc.positions);
+        println!("Incremental (ns): {}", inc.incremental_ns);
+        println!("Scratch (ns)    : {}", inc.scratch_ns);
+        println!("Speedup         : {:.2}x", inc.speedup());
+    }
+}
@@ -20,5 +20,5 @@
 mod polyglot;
 mod zobrist;
 
-pub use polyglot::{PolyglotBook, BookEntry};
+pub use polyglot::{PolyglotBook, BookEntry, AVOID_LEARN};
 pub use zobrist::polyglot_hash;
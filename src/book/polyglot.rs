@@ -1,7 +1,7 @@
 //! Polyglot opening book format reader.
 
 use super::zobrist::polyglot_hash;
-use crate::types::{Board, Move, Piece};
+use crate::types::{Board, BoardExt, Move, Piece};
 use movegen::{Square, File, Rank};
 use std::fs::File as FsFile;
 use std::io::{self, Read, Seek, SeekFrom};
@@ -9,6 +9,10 @@ use std::path::Path;
 
 const ENTRY_SIZE: usize = 16;
 
+/// `learn` value that marks an entry as "avoid" (any value with the sign bit set
+/// counts; books written by other tools leave `learn` at 0)
+pub const AVOID_LEARN: u32 = 0x8000_0000;
+
 #[derive(Debug, Clone, Copy)]
 pub struct BookEntry {
     pub key: u64,
@@ -45,30 +49,15 @@ impl BookEntry {
         (from, to, promotion)
     }
 
-    pub fn to_chess_move(&self, board: &Board) -> Option<Move> {
-        let (from, to, promo) = self.decode_move();
-        let actual_to = self.adjust_castling_move(board, from, to);
-        
-        for m in board.generate_moves().iter() {
-            if m.from() == from && m.to() == actual_to {
-                if promo.is_some() {
-                    if m.flag().promotion_piece() == promo { return Some(m); }
-                } else if m.flag().promotion_piece().is_none() {
-                    return Some(m);
-                }
-            }
-        }
-        None
+    /// Marked to avoid: `learn` is negative read as an `i32`
+    pub fn is_avoided(&self) -> bool {
+        (self.learn as i32) < 0
     }
 
-    fn adjust_castling_move(&self, board: &Board, from: Square, to: Square) -> Square {
-        if let Some((piece, _)) = board.piece_at(from) {
-            if piece == Piece::King && from.file() == File::E {
-                if to.file() == File::H { return Square::from_file_rank(File::G, to.rank()); }
-                if to.file() == File::A { return Square::from_file_rank(File::C, to.rank()); }
-            }
-        }
-        to
+    /// The legal move this entry encodes (castling is stored as king takes rook)
+    pub fn to_chess_move(&self, board: &Board) -> Option<Move> {
+        let (from, to, promo) = self.decode_move();
+        board.find_move(from, to, promo)
     }
 }
 
@@ -105,30 +94,68 @@ impl PolyglotBook {
         }
     }
 
+    /// In-memory book from raw entries (sorted by key here)
+    pub fn from_entries(mut entries: Vec<BookEntry>) -> Self {
+        entries.sort_by_key(|e| e.key);
+        let entry_count = entries.len();
+        Self { data: BookData::Memory(entries), entry_count, desc: String::from("<memory>") }
+    }
+
     pub fn probe(&self, board: &Board) -> Vec<BookEntry> {
         self.find_entries(polyglot_hash(board))
     }
 
+    /// Entries for `board` whose move is legal there, with their weights.
+    ///
+    /// A key collision can bring in entries from another position; their moves
+    /// don't match any legal move and are dropped. Entries marked to avoid are
+    /// dropped too, unless every legal entry is marked.
+    fn legal_moves(&self, board: &Board) -> Vec<(u16, Move)> {
+        let legal: Vec<(BookEntry, Move)> = self.probe(board).into_iter()
+            .filter_map(|e| e.to_chess_move(board).map(|m| (e, m)))
+            .collect();
+        let all_avoided = legal.iter().all(|(e, _)| e.is_avoided());
+        legal.into_iter()
+            .filter(|(e, _)| all_avoided || !e.is_avoided())
+            .map(|(e, m)| (e.weight, m))
+            .collect()
+    }
+
+    /// Mark the entries playing `m` in `board` to avoid. Only in-memory books
+    /// can be changed; returns whether any entry was marked.
+    pub fn mark_avoid(&mut self, board: &Board, m: Move) -> bool {
+        let BookData::Memory(entries) = &mut self.data else { return false };
+        let key = polyglot_hash(board);
+        let mut marked = false;
+        for e in entries.iter_mut().filter(|e| e.key == key) {
+            if e.to_chess_move(board) == Some(m) {
+                e.learn |= AVOID_LEARN;
+                marked = true;
+            }
+        }
+        marked
+    }
+
     pub fn probe_move(&self, board: &Board) -> Option<Move> {
-        let entries = self.probe(board);
-        if entries.is_empty() { return None; }
-        let total: u32 = entries.iter().map(|e| e.weight as u32).sum();
-        if total == 0 { return entries[0].to_chess_move(board); }
+        let moves = self.legal_moves(board);
+        if moves.is_empty() { return None; }
+        let total: u32 = moves.iter().map(|&(w, _)| w as u32).sum();
+        if total == 0 { return Some(moves[0].1); }
         
         let seed = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
             .map(|d| d.as_nanos() as u64).unwrap_or(12345);
         let random = seed.wrapping_mul(6364136223846793005).wrapping_add(1) % total as u64;
         
         let mut cum = 0u64;
-        for e in &entries {
-            cum += e.weight as u64;
-            if random < cum { return e.to_chess_move(board); }
+        for &(w, m) in &moves {
+            cum += w as u64;
+            if random < cum { return Some(m); }
         }
-        entries[0].to_chess_move(board)
+        Some(moves[0].1)
     }
 
     pub fn probe_best_move(&self, board: &Board) -> Option<Move> {
-        self.probe(board).iter().max_by_key(|e| e.weight).and_then(|e| e.to_chess_move(board))
+        self.legal_moves(board).into_iter().max_by_key(|&(w, _)| w).map(|(_, m)| m)
     }
 
     fn find_entries(&self, key: u64) -> Vec<BookEntry> {
@@ -173,3 +200,89 @@ impl PolyglotBook {
     pub fn len(&self) -> usize { self.entry_count }
     pub fn is_empty(&self) -> bool { self.entry_count == 0 }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Polyglot move encoding: to file/rank in bits 0-5, from file/rank in bits 6-11
+    fn raw_move(from: &str, to: &str) -> u16 {
+        let sq = |s: &str| {
+            let b = s.as_bytes();
+            ((b[1] - b'1') as u16) << 3 | (b[0] - b'a') as u16
+        };
+        sq(from) << 6 | sq(to)
+    }
+
+    fn entry(board: &Board, from: &str, to: &str, weight: u16) -> BookEntry {
+        BookEntry { key: polyglot_hash(board), raw_move: raw_move(from, to), weight, learn: 0 }
+    }
+
+    #[test]
+    fn test_illegal_book_move_is_not_played() {
+        let board = Board::default();
+        // e2e5 can't be played from the start position
+        let book = PolyglotBook::from_entries(vec![entry(&board, "e2", "e5", 100)]);
+        assert_eq!(book.probe(&board).len(), 1);
+        assert!(book.probe_move(&board).is_none());
+        assert!(book.probe_best_move(&board).is_none());
+    }
+
+    #[test]
+    fn test_illegal_entry_skipped_in_weighted_pick() {
+        let board = Board::default();
+        let book = PolyglotBook::from_entries(vec![
+            entry(&board, "e2", "e5", 60000),
+            entry(&board, "d2", "d4", 1),
+        ]);
+        let expected = crate::uci::parse_move(&board, "d2d4");
+        for _ in 0..16 {
+            assert_eq!(book.probe_move(&board), expected);
+        }
+        assert_eq!(book.probe_best_move(&board), expected);
+    }
+
+    #[test]
+    fn test_castling_agrees_with_uci_and_stays_legal() {
+        use crate::uci::parse_move;
+
+        // The f2 rook covers f1: O-O would pass through check, O-O-O is fine
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/5r2/R3K2R w KQkq - 0 1").unwrap();
+        assert!(parse_move(&board, "e1g1").is_none());
+        assert!(entry(&board, "e1", "h1", 1).to_chess_move(&board).is_none());
+
+        let long = parse_move(&board, "e1c1").expect("queenside castling is legal");
+        assert_eq!(entry(&board, "e1", "a1", 1).to_chess_move(&board), Some(long));
+        assert!(board.generate_moves().iter().any(|m| m == long));
+
+        // In check: no castling either way
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/4r3/R3K2R w KQkq - 0 1").unwrap();
+        for (uci, from, to) in [("e1g1", "e1", "h1"), ("e1c1", "e1", "a1")] {
+            assert!(parse_move(&board, uci).is_none());
+            assert!(entry(&board, from, to, 1).to_chess_move(&board).is_none());
+        }
+    }
+
+    #[test]
+    fn test_avoided_entry_not_selected() {
+        let board = Board::default();
+        let mut e4 = entry(&board, "e2", "e4", 60000);
+        e4.learn = AVOID_LEARN;
+        let book = PolyglotBook::from_entries(vec![e4, entry(&board, "d2", "d4", 1)]);
+        let d4 = crate::uci::parse_move(&board, "d2d4");
+        for _ in 0..16 {
+            assert_eq!(book.probe_move(&board), d4);
+        }
+        assert_eq!(book.probe_best_move(&board), d4);
+
+        // Everything avoided: still a book move rather than none
+        let book = PolyglotBook::from_entries(vec![e4]);
+        assert_eq!(book.probe_move(&board), crate::uci::parse_move(&board, "e2e4"));
+
+        // Marking from code does the same as the learn field
+        let mut book = PolyglotBook::from_entries(vec![entry(&board, "e2", "e4", 60000), entry(&board, "d2", "d4", 1)]);
+        let e4_move = crate::uci::parse_move(&board, "e2e4").unwrap();
+        assert!(book.mark_avoid(&board, e4_move));
+        assert_eq!(book.probe_best_move(&board), d4);
+    }
+}
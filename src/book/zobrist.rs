@@ -235,6 +235,9 @@ const RANDOM64: [u64; 781] = [
 
 /// Calculate Polyglot Zobrist hash for a board position.
 /// This uses the exact same random numbers as the original Polyglot implementation.
+///
+/// Only for book lookups: it is a different scheme from `Board::hash()` (used by
+/// the TT and repetition detection) and the two are never interchangeable.
 pub fn polyglot_hash(board: &Board) -> u64 {
     let mut hash: u64 = 0;
 
@@ -362,5 +365,68 @@ mod tests {
         // Expected Polyglot hash after 1.e4
         assert_eq!(hash, 0x823c9b50fd114196);
     }
+
+    #[test]
+    fn test_reference_keys() {
+        // Test vectors from the Polyglot book format specification
+        let cases = [
+            // 1.e4 e5 (e6 not capturable: no ep key)
+            ("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2", 0x0844931a6ef4b9a0),
+            // 1.e4 d5
+            ("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", 0x0756b94461c50fb0),
+            // 1.e4 d5 2.e5
+            ("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2", 0x662fafb965db29d4),
+            // 1.e4 d5 2.e5 f5 (exf6 possible: ep key included)
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", 0x22a48b5a8e47ff78),
+            // 3.Ke2 (white castling rights gone)
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3", 0x652a607ca3f242c1),
+            // 3...Kf7 (no castling rights)
+            ("rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4", 0x00fdd303c946bdd9),
+            // 1.a4 b5 2.h4 b4 3.c4
+            ("rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3", 0x3c8123ea7b067637),
+            // 3...bxc3 4.Ra3
+            ("rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4", 0x5c3f9b829b279560),
+        ];
+        for (fen, expected) in cases {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(polyglot_hash(&board), expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_polyglot_and_board_hash_differ() {
+        // Guards against one scheme being swapped in for the other
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert_ne!(polyglot_hash(&board), board.hash());
+        }
+    }
+
+    #[test]
+    fn test_board_hash_stable_under_make_unmake() {
+        let board = Board::startpos();
+        let hash = board.hash();
+
+        // Copy-make: making a move leaves the original (the unmade position) intact
+        for m in board.generate_moves().iter() {
+            let child = board.make_move_new(m);
+            assert_ne!(child.hash(), hash);
+            // Incremental hash matches a from-scratch one
+            assert_eq!(child.hash(), Board::from_fen(&child.to_fen()).unwrap().hash());
+            assert_eq!(board.hash(), hash);
+        }
+
+        // Moving the knights out and back restores the key
+        let mut b = board;
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            let m = crate::uci::parse_move(&b, uci).unwrap();
+            b = b.make_move_new(m);
+        }
+        assert_eq!(b.hash(), hash);
+        assert_eq!(polyglot_hash(&b), polyglot_hash(&board));
+    }
 }
 
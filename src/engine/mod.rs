@@ -1,2 +1,90 @@
 // Core engine logic
-pub struct Engine;
+
+use crate::eval::EvalKind;
+use crate::search::{InfoSink, NullInfoSink, SearchLimits, SearchOutcome, Searcher};
+use crate::types::Board;
+
+/// Library entry point: a position and a searcher, without UCI I/O.
+pub struct Engine {
+    searcher: Searcher,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Self { searcher: Searcher::new() }
+    }
+
+    /// Set the position to search from
+    pub fn set_position(&mut self, board: Board) {
+        self.searcher.set_position(board);
+    }
+
+    /// Evaluation function for the following searches, whatever NNUE models
+    /// are loaded (`EvalKind::Auto` restores the default choice)
+    pub fn set_eval_kind(&mut self, kind: EvalKind) {
+        self.searcher.params.eval_kind = kind;
+    }
+
+    /// Search silently and return the outcome
+    pub fn search(&mut self, limits: SearchLimits) -> SearchOutcome {
+        self.searcher.search_with_sink(limits, &mut NullInfoSink)
+    }
+
+    /// Search, sending per-iteration info to `sink`
+    pub fn search_with(&mut self, limits: SearchLimits, sink: &mut dyn InfoSink) -> SearchOutcome {
+        self.searcher.search_with_sink(limits, sink)
+    }
+
+    /// TT fill in permill (0..=1000)
+    pub fn hashfull(&self) -> u32 {
+        self.searcher.shared.tt.hashfull()
+    }
+
+    /// Underlying searcher (hash size, threads, NNUE, parameters)
+    pub fn searcher_mut(&mut self) -> &mut Searcher {
+        &mut self.searcher
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashfull_and_nps_after_search() {
+        let mut engine = Engine::new();
+        assert_eq!(engine.hashfull(), 0);
+
+        engine.set_position(Board::default());
+        let outcome = engine.search(SearchLimits::depth(6));
+        assert!(outcome.nps > 0);
+        assert!(outcome.hashfull <= 1000);
+        assert!((0..=1000).contains(&engine.hashfull()));
+    }
+
+    #[test]
+    fn test_same_search_with_different_evals() {
+        // Even material but not an even position: HCE and material count disagree
+        let board = Board::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap();
+        let search = |kind| {
+            let mut engine = Engine::new();
+            engine.set_eval_kind(kind);
+            engine.set_position(board);
+            engine.search(SearchLimits::depth(3))
+        };
+
+        let hce = search(EvalKind::Hce);
+        let material = search(EvalKind::Material);
+        for outcome in [&hce, &material] {
+            let m = outcome.best_move.expect("a move");
+            assert!(board.generate_moves().iter().any(|legal| legal == m));
+        }
+        assert_ne!(hce.score, material.score);
+    }
+}
@@ -0,0 +1,774 @@
+//! Heuristic endgame evaluation.
+//!
+//! Neither the NNUE nor the tapered HCE know how to finish off a won endgame:
+//! they see a large material edge but give no gradient towards mate. When one
+//! side is clearly winning with few pieces left, this evaluator replaces them:
+//! - General mop-up: material, passed pawns (connected, protected, blockaded),
+//!   drive the losing king to a corner and bring the winning king closer
+//! - KQvK / KRvK (and other major piece vs bare king): known-win score that
+//!   follows the mating pattern (loser on the edge, kings close), so iterative
+//!   deepening converges to a short mate
+//! - KNNvK / KBvK / KNvK: known draws despite the material edge
+//! - KBNvK: known win driving the loser to a corner of the bishop's color
+//! - KPvK: won or drawn from the rule of the square and the key squares
+//!
+//! `probe` is the hot-path entry: a single popcount rejects most positions,
+//! and the piece counts (`Material`) are taken once and shared by the
+//! threshold check and the evaluation. When the eval takes over is set by an
+//! `EndgameConfig` (hidden UCI options), defaulting to the constants below.
+
+use crate::types::{Board, Score, Color, Piece, Square, Bitboard};
+use movegen::attacks;
+use super::hce::{king_distance, center_distance, corner_distance, is_passed, EvalInfo};
+
+/// Enable the heuristic endgame evaluation
+pub const USE_ENDGAME_EVAL: bool = true;
+
+/// Maximum number of pieces on the board (kings included) for the endgame eval
+/// and the endgame net
+pub const ENDGAME_PIECE_THRESHOLD: u32 = 5;
+
+/// Minimum material advantage (centipawns) for the endgame eval
+pub const MATERIAL_ADVANTAGE_THRESHOLD: i32 = 400;
+
+/// Base score for a theoretically won endgame (well below mate scores)
+pub const KNOWN_WIN: i32 = 10000;
+
+/// Base score for a won KPvK, below any known win with a queen on the board
+/// so promoting is always progress
+const KPK_WIN: i32 = KNOWN_WIN / 2;
+
+/// Score for a drawn KPvK: a pawn up, but nothing more
+const KPK_DRAW: i32 = 10;
+
+/// Passed pawn bonus by relative rank (endgame weights)
+const PASSED_PAWN_BONUS: [i32; 8] = [0, 10, 20, 40, 70, 120, 200, 0];
+
+/// Extra bonus for a passer with another passer beside it (adjacent file, rank ±1)
+const CONNECTED_PASSER_BONUS: [i32; 8] = [0, 5, 10, 20, 35, 60, 100, 0];
+
+/// Extra bonus for a passer defended by a friendly pawn
+const PROTECTED_PASSER_BONUS: [i32; 8] = [0, 5, 10, 15, 25, 40, 60, 0];
+
+/// Bonus for direct opposition (halved for distant opposition)
+const OPPOSITION_BONUS: i32 = 20;
+
+/// Bonus per flight square taken from the losing king
+const CONFINEMENT_BONUS: i32 = 6;
+
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+
+/// Few enough pieces left to count as an endgame (also selects the endgame net)
+#[inline]
+pub fn is_endgame_phase(board: &Board) -> bool {
+    board.occupied().count() <= ENDGAME_PIECE_THRESHOLD
+}
+
+/// Non-king piece counts of a position
+#[derive(Debug, Clone, Copy)]
+pub struct Material {
+    /// [color][pawn, knight, bishop, rook, queen]
+    counts: [[i32; 5]; 2],
+}
+
+impl Material {
+    const PIECES: [Piece; 5] = [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+    const VALUES: [i32; 5] = [100, 320, 330, 500, 900];
+
+    pub fn new(board: &Board) -> Self {
+        let mut counts = [[0; 5]; 2];
+        for color in [Color::White, Color::Black] {
+            let ours = board.color_bb(color);
+            for (i, &piece) in Self::PIECES.iter().enumerate() {
+                counts[color.index()][i] = (board.piece_bb(piece) & ours).count() as i32;
+            }
+        }
+        Self { counts }
+    }
+
+    /// Number of `piece`s of `color` (0 for kings)
+    #[inline]
+    pub fn count(&self, color: Color, piece: Piece) -> i32 {
+        if piece == Piece::King {
+            return 0;
+        }
+        self.counts[color.index()][piece.index()]
+    }
+
+    /// Non-king pieces of `color`
+    #[inline]
+    pub fn pieces(&self, color: Color) -> i32 {
+        self.counts[color.index()].iter().sum()
+    }
+
+    /// Material balance in centipawns (white's perspective), same values as
+    /// `hce::material_balance`
+    #[inline]
+    pub fn balance(&self) -> i32 {
+        let [white, black] = &self.counts;
+        (0..5).map(|i| Self::VALUES[i] * (white[i] - black[i])).sum()
+    }
+
+    /// Minor pieces against a bare king that cannot force mate: KNNvK, KBvK, KNvK.
+    ///
+    /// KNNvK has enough material on paper but no forced mate, so chasing it only
+    /// burns moves towards the fifty-move rule.
+    pub fn is_known_draw(&self) -> bool {
+        let strong = match (self.pieces(Color::White) == 0, self.pieces(Color::Black) == 0) {
+            (false, true) => Color::White,
+            (true, false) => Color::Black,
+            _ => return false,
+        };
+
+        let knights = self.count(strong, Piece::Knight);
+        let bishops = self.count(strong, Piece::Bishop);
+        // Anything besides king + minors (pawns, majors) can still win
+        if self.pieces(strong) != knights + bishops {
+            return false;
+        }
+
+        matches!((knights, bishops), (1, 0) | (2, 0) | (0, 1))
+    }
+
+    /// A single pawn against a bare king (KPvK)
+    pub fn is_kpk(&self) -> bool {
+        let pieces = (self.pieces(Color::White), self.pieces(Color::Black));
+        let pawns = self.count(Color::White, Piece::Pawn) + self.count(Color::Black, Piece::Pawn);
+        matches!(pieces, (1, 0) | (0, 1)) && pawns == 1
+    }
+}
+
+/// When the heuristic endgame eval replaces NNUE/HCE.
+///
+/// Only the heuristic eval is affected: the endgame net is still selected by
+/// `ENDGAME_PIECE_THRESHOLD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndgameConfig {
+    /// Use the heuristic endgame eval at all
+    pub enabled: bool,
+    /// Maximum number of pieces on the board (kings included)
+    pub piece_threshold: u32,
+    /// Minimum material advantage (centipawns), known draws and KPvK aside
+    pub material_threshold: i32,
+}
+
+impl Default for EndgameConfig {
+    fn default() -> Self {
+        Self {
+            enabled: USE_ENDGAME_EVAL,
+            piece_threshold: ENDGAME_PIECE_THRESHOLD,
+            material_threshold: MATERIAL_ADVANTAGE_THRESHOLD,
+        }
+    }
+}
+
+impl EndgameConfig {
+    /// Set a threshold by (case-insensitive) UCI option name.
+    ///
+    /// Returns false if the name is not an endgame option.
+    pub fn set(&mut self, name: &str, value: i32) -> bool {
+        match name.to_lowercase().as_str() {
+            "endgameeval" => self.enabled = value != 0,
+            "endgamepieces" => self.piece_threshold = value.clamp(2, 32) as u32,
+            "endgamematerial" => self.material_threshold = value.max(0),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Few enough pieces for the heuristic endgame eval
+    #[inline]
+    fn in_range(&self, board: &Board) -> bool {
+        self.enabled && board.occupied().count() <= self.piece_threshold
+    }
+
+    /// Is the material lopsided (or drawn, or KPvK) enough for the heuristic eval
+    #[inline]
+    fn decides(&self, material: &Material) -> bool {
+        material.balance().abs() >= self.material_threshold || material.is_known_draw() || material.is_kpk()
+    }
+}
+
+/// Endgame score if the heuristic endgame eval applies to `board`.
+///
+/// Cheap for the common case: positions above the piece threshold are
+/// rejected with a single popcount before any material is counted.
+#[inline]
+pub fn probe(board: &Board, config: &EndgameConfig) -> Option<Score> {
+    if !config.in_range(board) {
+        return None;
+    }
+    let material = Material::new(board);
+    if !config.decides(&material) {
+        return None;
+    }
+    Some(evaluate_with(board, &material))
+}
+
+/// Check if the heuristic endgame eval should replace the main evaluation
+#[inline]
+pub fn should_use_endgame(board: &Board, config: &EndgameConfig) -> bool {
+    config.in_range(board) && config.decides(&Material::new(board))
+}
+
+/// See `Material::is_known_draw`
+pub fn is_known_draw(board: &Board) -> bool {
+    Material::new(board).is_known_draw()
+}
+
+/// Evaluate an endgame position - returns score from side-to-move perspective
+pub fn evaluate(board: &Board) -> Score {
+    evaluate_with(board, &Material::new(board))
+}
+
+/// `evaluate` with the piece counts already taken
+fn evaluate_with(board: &Board, counts: &Material) -> Score {
+    if counts.is_known_draw() {
+        return Score::draw();
+    }
+
+    let material = counts.balance();
+    let (strong, weak) = if material >= 0 {
+        (Color::White, Color::Black)
+    } else {
+        (Color::Black, Color::White)
+    };
+
+    // A bare king to move, not in check and with nowhere to go is stalemated.
+    // Quiescence only looks at captures and would score this as the win.
+    if board.turn() == weak
+        && counts.pieces(weak) == 0
+        && !board.in_check()
+        && king_escape_squares(board, strong, weak) == 0
+    {
+        return Score::draw();
+    }
+
+    let known = major_vs_bare_king(board, counts, strong, weak)
+        .or_else(|| kbnk(board, counts, strong, weak))
+        .or_else(|| kpk(board, counts, strong, weak));
+    let white_score = if let Some(s) = known {
+        if strong == Color::White { s } else { -s }
+    } else {
+        let mop_up = mop_up_bonus(board, strong, weak);
+        let info = EvalInfo::new(board);
+        material
+            + passed_pawn_bonus(board, &info, Color::White)
+            - passed_pawn_bonus(board, &info, Color::Black)
+            + if strong == Color::White { mop_up } else { -mop_up }
+    };
+
+    if board.turn() == Color::White {
+        Score::cp(white_score)
+    } else {
+        Score::cp(-white_score)
+    }
+}
+
+/// Sum of passed pawn bonuses for one side.
+///
+/// On top of the per-rank bonus, passers that are protected by a pawn score more
+/// and passers whose stop square is occupied by an enemy piece (blockade) only
+/// get half. Connected passers are found from the passer bitboard afterwards.
+pub(crate) fn passed_pawn_bonus(board: &Board, info: &EvalInfo, color: Color) -> i32 {
+    let our_pawns = board.piece_bb(Piece::Pawn) & board.color_bb(color);
+    let enemy_pawns = info.pawns[(!color).index()];
+    let enemies = board.color_bb(!color).bits();
+    let defended = info.pawn_attacks[color.index()];
+
+    let mut bonus = 0;
+    let mut passers = 0u64;
+    for sq in our_pawns {
+        if !is_passed(color, sq, enemy_pawns) {
+            continue;
+        }
+        let bit = 1u64 << sq.index();
+        let rank = relative_rank(color, sq);
+        passers |= bit;
+
+        let stop = match color {
+            Color::White => bit << 8,
+            Color::Black => bit >> 8,
+        };
+        bonus += if stop & enemies != 0 {
+            PASSED_PAWN_BONUS[rank] / 2
+        } else {
+            PASSED_PAWN_BONUS[rank]
+        };
+
+        if defended & bit != 0 {
+            bonus += PROTECTED_PASSER_BONUS[rank];
+        }
+    }
+
+    // Passers with a neighbouring passer on an adjacent file, at most a rank apart
+    let beside = ((passers & !FILE_A) >> 1) | ((passers & !FILE_H) << 1);
+    let mut connected = passers & (beside | (beside << 8) | (beside >> 8));
+    while connected != 0 {
+        let sq = Square::from_index(connected.trailing_zeros() as u8).unwrap();
+        connected &= connected - 1;
+        bonus += CONNECTED_PASSER_BONUS[relative_rank(color, sq)];
+    }
+    bonus
+}
+
+/// Corner driving and king proximity for the winning side
+fn mop_up_bonus(board: &Board, strong: Color, weak: Color) -> i32 {
+    let winner_king = board.king_square(strong);
+    let loser_king = board.king_square(weak);
+
+    let corner = (6 - corner_distance(loser_king)) * 10;
+    let proximity = (7 - king_distance(winner_king, loser_king)) * 15;
+    corner + proximity + king_pressure(board, strong, weak)
+}
+
+/// Opposition and confinement of the losing king.
+///
+/// Being close is not enough to mate: the winning king has to face the loser
+/// on a file or rank with an odd number of squares between them (opposition)
+/// and, together with its pieces, take away the loser's flight squares.
+fn king_pressure(board: &Board, strong: Color, weak: Color) -> i32 {
+    let winner_king = board.king_square(strong);
+    let loser_king = board.king_square(weak);
+
+    let same_line = winner_king.file() == loser_king.file() || winner_king.rank() == loser_king.rank();
+    let distance = king_distance(winner_king, loser_king);
+    let opposition = if same_line && distance % 2 == 0 {
+        if distance == 2 { OPPOSITION_BONUS } else { OPPOSITION_BONUS / 2 }
+    } else {
+        0
+    };
+
+    let escapes = king_escape_squares(board, strong, weak);
+    opposition + (8 - escapes) * CONFINEMENT_BONUS
+}
+
+/// Squares the losing king can step to: not occupied by its own pieces and
+/// not attacked by the winning side. Sliders see through the losing king, so
+/// stepping back along a checking line is not counted as an escape.
+fn king_escape_squares(board: &Board, strong: Color, weak: Color) -> i32 {
+    let loser_king = board.king_square(weak);
+    let occupied = board.occupied() ^ Bitboard::from_square(loser_king);
+    let ours = board.color_bb(strong);
+
+    let mut attacked = attacks::king_attacks(board.king_square(strong)).bits();
+    for sq in board.piece_bb(Piece::Pawn) & ours {
+        attacked |= attacks::pawn_attacks(strong, sq).bits();
+    }
+    for sq in board.piece_bb(Piece::Knight) & ours {
+        attacked |= attacks::knight_attacks(sq).bits();
+    }
+    let diagonal = (board.piece_bb(Piece::Bishop) | board.piece_bb(Piece::Queen)) & ours;
+    for sq in diagonal {
+        attacked |= attacks::bishop_attacks(sq, occupied).bits();
+    }
+    let straight = (board.piece_bb(Piece::Rook) | board.piece_bb(Piece::Queen)) & ours;
+    for sq in straight {
+        attacked |= attacks::rook_attacks(sq, occupied).bits();
+    }
+
+    let flight = attacks::king_attacks(loser_king).bits() & !board.color_bb(weak).bits() & !attacked;
+    flight.count_ones() as i32
+}
+
+/// KQvK, KRvK (or more major pieces) against a bare king.
+///
+/// Returns the score from the strong side's perspective. The score grows as the
+/// losing king approaches the edge (where KR/KQ mates happen), then the corner,
+/// and as the kings come closer (the winning king must help), so every step of
+/// the mating pattern is rewarded.
+fn major_vs_bare_king(board: &Board, material: &Material, strong: Color, weak: Color) -> Option<i32> {
+    // Weak side must be a bare king
+    if material.pieces(weak) != 0 {
+        return None;
+    }
+
+    let queens = material.count(strong, Piece::Queen);
+    let rooks = material.count(strong, Piece::Rook);
+    let majors = queens + rooks;
+    // Only major pieces (no pawns/minors muddying the pattern) and at least one of them
+    if majors == 0 || material.pieces(strong) != majors {
+        return None;
+    }
+
+    let winner_king = board.king_square(strong);
+    let loser_king = board.king_square(weak);
+
+    let edge = (3 - edge_distance(loser_king)) * 60;
+    let corner = (6 - corner_distance(loser_king)) * 10;
+    let center = center_distance(loser_king) * 5;
+    let proximity = (7 - king_distance(winner_king, loser_king)) * 15;
+    let pressure = king_pressure(board, strong, weak);
+
+    Some(KNOWN_WIN + queens * 900 + rooks * 500 + edge + corner + center + proximity + pressure)
+}
+
+/// KBNvK against a bare king.
+///
+/// Mate is only possible in a corner the bishop can cover, so the corner
+/// term counts the distance to the nearer corner of the bishop's color: a
+/// king in a wrong corner scores lowest, and walking it along the edge to
+/// the right one is rewarded step by step. Returns the score from the strong
+/// side's perspective.
+fn kbnk(board: &Board, material: &Material, strong: Color, weak: Color) -> Option<i32> {
+    let knights = material.count(strong, Piece::Knight);
+    let bishops = material.count(strong, Piece::Bishop);
+    if material.pieces(weak) != 0 || material.pieces(strong) != 2 || (knights, bishops) != (1, 1) {
+        return None;
+    }
+
+    let bishop = (board.piece_bb(Piece::Bishop) & board.color_bb(strong)).into_iter().next()?;
+    let dark = (bishop.file().index() + bishop.rank().index()) % 2 == 0;
+    let corners = if dark { [Square::A1, Square::H8] } else { [Square::A8, Square::H1] };
+
+    let winner_king = board.king_square(strong);
+    let loser_king = board.king_square(weak);
+    let to_corner = corners.iter().map(|&c| king_distance(loser_king, c)).min()?;
+
+    let corner = (7 - to_corner) * 40;
+    let proximity = (7 - king_distance(winner_king, loser_king)) * 15;
+    let pressure = king_pressure(board, strong, weak);
+
+    Some(KNOWN_WIN + material.balance().abs() + corner + proximity + pressure)
+}
+
+/// KPvK, classified by the rule of the square and the key squares.
+///
+/// Returns the score from the strong side's perspective: `KPK_WIN` plus the
+/// pawn's advance when won, `KPK_DRAW` when drawn, and `None` when the
+/// position is none of the patterns below (the general eval decides):
+/// - Drawn: the weak king takes an undefended pawn next to it
+/// - Won: the weak king is outside the square of the pawn
+/// - Drawn: a rook pawn with the weak king in front of it or in the corner
+/// - Won: the strong king stands on a key square
+/// - Drawn: the weak king in front of the pawn, the strong king beside or behind it
+fn kpk(board: &Board, material: &Material, strong: Color, weak: Color) -> Option<i32> {
+    if !material.is_kpk() || material.pieces(strong) != 1 {
+        return None;
+    }
+
+    let pawn = (board.piece_bb(Piece::Pawn) & board.color_bb(strong)).into_iter().next()?;
+    let strong_king = board.king_square(strong);
+    let weak_king = board.king_square(weak);
+    let strong_to_move = board.turn() == strong;
+
+    let file = pawn.file().index() as i32;
+    let rank = relative_rank(strong, pawn) as i32;
+    let queening = Square::from_index(match strong {
+        Color::White => 56 + file as u8,
+        Color::Black => file as u8,
+    })?;
+    let win = KPK_WIN + rank * 50;
+
+    if !strong_to_move && king_distance(weak_king, pawn) == 1 && king_distance(strong_king, pawn) > 1 {
+        return Some(KPK_DRAW);
+    }
+
+    // Rule of the square. A pawn on its start rank still moves two squares
+    // at once, and its own king must not stand in its way.
+    let steps = 7 - rank.max(2);
+    let tempo = if strong_to_move { 0 } else { 1 };
+    let strong_rank = relative_rank(strong, strong_king) as i32;
+    let blocked = strong_king.file() == pawn.file() && strong_rank > rank;
+    if !blocked && king_distance(weak_king, queening) > steps + tempo {
+        return Some(win);
+    }
+
+    let in_front = weak_king.file() == pawn.file() && relative_rank(strong, weak_king) as i32 > rank;
+    if file == 0 || file == 7 {
+        return (in_front || king_distance(weak_king, queening) <= 1).then_some(KPK_DRAW);
+    }
+
+    // Key squares: two ranks ahead of a pawn on its own half, one and two
+    // ranks ahead once it has crossed, on the pawn's and the adjacent files
+    let key_ranks = if rank <= 3 { (rank + 2)..=(rank + 2) } else { (rank + 1)..=(rank + 2).min(7) };
+    let beside = (strong_king.file().index() as i32 - file).abs() <= 1;
+    if beside && key_ranks.contains(&strong_rank) {
+        return Some(win);
+    }
+
+    (in_front && strong_rank <= rank).then_some(KPK_DRAW)
+}
+
+/// Distance to the nearest edge (0 = on the edge, 3 = center)
+#[inline]
+fn edge_distance(sq: Square) -> i32 {
+    let file = sq.file().index() as i32;
+    let rank = sq.rank().index() as i32;
+    file.min(7 - file).min(rank).min(7 - rank)
+}
+
+/// Rank from `color`'s point of view (0 = own back rank)
+#[inline]
+fn relative_rank(color: Color, sq: Square) -> usize {
+    let rank = sq.rank().index() as usize;
+    match color {
+        Color::White => rank,
+        Color::Black => 7 - rank,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{Searcher, SearchLimits};
+
+    /// Let the engine play both sides and check the attacker mates within `max_moves`
+    fn mates_within(fen: &str, max_moves: usize, depth: i32) -> bool {
+        let mut board = Board::from_fen(fen).unwrap();
+        let attacker = board.turn();
+        let mut searcher = Searcher::new();
+        let mut history = Vec::new();
+        let mut clock = 0;
+
+        for _ in 0..max_moves * 2 {
+            if board.generate_moves().is_empty() {
+                return board.in_check() && board.turn() != attacker;
+            }
+            searcher.set_position_with_history(board, history.clone());
+            searcher.set_halfmove_clock(clock);
+            let result = searcher.search(SearchLimits::depth(depth));
+            let m = result.best_move.unwrap();
+            history.push(board.hash());
+            clock = crate::types::next_halfmove_clock(&board, m, clock);
+            board = board.make_move_new(m);
+        }
+
+        board.generate_moves().is_empty() && board.in_check()
+    }
+
+    fn passers(board: &Board, color: Color) -> i32 {
+        passed_pawn_bonus(board, &EvalInfo::new(board), color)
+    }
+
+    #[test]
+    fn test_connected_passers_beat_isolated() {
+        let connected = Board::from_fen("4k3/8/8/3PP3/8/8/8/4K3 w - - 0 1").unwrap();
+        let isolated = Board::from_fen("4k3/8/8/1P4P1/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(passers(&connected, Color::White) > passers(&isolated, Color::White));
+
+        // Same for black, and a rank apart still counts as connected
+        let connected = Board::from_fen("4k3/8/8/8/3p4/4p3/8/4K3 b - - 0 1").unwrap();
+        let isolated = Board::from_fen("4k3/8/8/8/1p6/6p1/8/4K3 b - - 0 1").unwrap();
+        assert!(passers(&connected, Color::Black) > passers(&isolated, Color::Black));
+    }
+
+    #[test]
+    fn test_blockaded_passer_scores_less() {
+        let free = Board::from_fen("4k3/8/8/3P4/8/8/8/n3K3 w - - 0 1").unwrap();
+        let blockaded = Board::from_fen("4k3/8/3n4/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(passers(&blockaded, Color::White) < passers(&free, Color::White));
+
+        // Protected passer: the d5 passer defended from c4 earns the extra bonus
+        let protected = Board::from_fen("4k3/8/8/3P4/2P5/8/8/4K3 w - - 0 1").unwrap();
+        let apart = Board::from_fen("4k3/8/8/3P4/8/8/7P/4K3 w - - 0 1").unwrap();
+        assert!(passers(&protected, Color::White) > passers(&apart, Color::White));
+    }
+
+    #[test]
+    fn test_eval_is_deterministic() {
+        // Repetitions are avoided through draw scoring and contempt in the search,
+        // not by perturbing the eval: positions that differ only in their hash
+        // (castling rights here) must score the same, every time
+        let castles = Board::from_fen("r3k3/8/8/3P4/8/8/8/4K2R w Kq - 0 1").unwrap();
+        let plain = Board::from_fen("r3k3/8/8/3P4/8/8/8/4K2R w - - 0 1").unwrap();
+        assert_ne!(castles.hash(), plain.hash());
+        assert_eq!(evaluate(&castles), evaluate(&plain));
+        assert_eq!(evaluate(&castles), evaluate(&castles));
+    }
+
+    #[test]
+    fn test_material_counts() {
+        let board = Board::from_fen("4k3/8/8/3PP3/8/2n5/8/R3K3 w - - 0 1").unwrap();
+        let material = Material::new(&board);
+        assert_eq!(material.balance(), super::super::hce::material_balance(&board));
+        assert_eq!(material.pieces(Color::White), 3);
+        assert_eq!(material.count(Color::Black, Piece::Knight), 1);
+        assert_eq!(material.count(Color::White, Piece::King), 0);
+        let config = EndgameConfig::default();
+        assert_eq!(probe(&board, &config), None);
+
+        let krk = Board::from_fen("8/8/8/8/3k4/8/8/R3K3 w - - 0 1").unwrap();
+        assert_eq!(probe(&krk, &config), Some(evaluate(&krk)));
+    }
+
+    #[test]
+    fn test_should_use_endgame() {
+        let config = EndgameConfig::default();
+        let krk = Board::from_fen("8/8/8/4k3/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert!(should_use_endgame(&krk, &config));
+        assert!(!should_use_endgame(&Board::default(), &config));
+    }
+
+    #[test]
+    fn test_endgame_thresholds_configurable() {
+        // KRPvK: four pieces, well inside the default threshold
+        let krpk = Board::from_fen("8/8/8/4k3/8/8/P7/R3K3 w - - 0 1").unwrap();
+        // KRPPPvK: six pieces, just above it
+        let krpppk = Board::from_fen("8/8/8/4k3/8/8/PPP5/R3K3 w - - 0 1").unwrap();
+
+        let mut config = EndgameConfig::default();
+        assert!(should_use_endgame(&krpk, &config));
+        assert!(!should_use_endgame(&krpppk, &config));
+
+        assert!(config.set("EndgamePieces", 3));
+        assert!(!should_use_endgame(&krpk, &config));
+
+        assert!(config.set("EndgamePieces", 6));
+        assert!(should_use_endgame(&krpppk, &config));
+
+        // A rook up is not enough once the material bar is raised
+        assert!(config.set("EndgameMaterial", 1000));
+        assert!(!should_use_endgame(&krpk, &config));
+
+        assert!(config.set("EndgameMaterial", 400));
+        assert!(config.set("EndgameEval", 0));
+        assert!(!should_use_endgame(&krpk, &config));
+        assert_eq!(probe(&krpk, &config), None);
+        assert!(!config.set("EndgameFoo", 1));
+    }
+
+    #[test]
+    fn test_knnk_is_draw() {
+        let knnk = Board::from_fen("8/8/8/4k3/8/8/8/1N2K1N1 w - - 0 1").unwrap();
+        assert!(should_use_endgame(&knnk, &EndgameConfig::default()));
+        assert_eq!(evaluate(&knnk), Score::draw());
+
+        // Same with colors reversed and the weak side to move
+        let knnk = Board::from_fen("1n2k1n1/8/8/8/4K3/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(evaluate(&knnk), Score::draw());
+    }
+
+    #[test]
+    fn test_single_minor_is_draw() {
+        let kbk = Board::from_fen("8/8/8/4k3/8/8/8/2B1K3 w - - 0 1").unwrap();
+        let knk = Board::from_fen("8/8/8/4k3/8/8/8/1N2K3 b - - 0 1").unwrap();
+        assert_eq!(evaluate(&kbk), Score::draw());
+        assert_eq!(evaluate(&knk), Score::draw());
+
+        // Bishop pair can still mate
+        let kbbk = Board::from_fen("8/8/8/4k3/8/8/8/2B1KB2 w - - 0 1").unwrap();
+        assert!(!is_known_draw(&kbbk));
+        assert!(evaluate(&kbbk) > Score::draw());
+    }
+
+    #[test]
+    fn test_kbnk_drives_to_bishop_corner() {
+        // Dark-squared bishop: a1 is the mating corner, h1 is not. Same king
+        // distance and flight squares in both.
+        let right = Board::from_fen("8/8/8/8/8/2K5/8/k1B1N3 w - - 0 1").unwrap();
+        let wrong = Board::from_fen("8/8/8/8/8/5K2/8/2B1N2k w - - 0 1").unwrap();
+        assert!(should_use_endgame(&right, &EndgameConfig::default()));
+        assert!(evaluate(&right).raw() >= KNOWN_WIN);
+        assert!(evaluate(&wrong).raw() >= KNOWN_WIN);
+        assert!(evaluate(&right).raw() - evaluate(&wrong).raw() >= 200,
+                "{} vs {}", evaluate(&right), evaluate(&wrong));
+
+        // Light-squared bishop: the other way round
+        let right = Board::from_fen("8/8/8/8/8/5K2/8/3BN2k w - - 0 1").unwrap();
+        let wrong = Board::from_fen("8/8/8/8/8/2K5/8/k2BN3 w - - 0 1").unwrap();
+        assert!(evaluate(&right).raw() - evaluate(&wrong).raw() >= 200,
+                "{} vs {}", evaluate(&right), evaluate(&wrong));
+    }
+
+    #[test]
+    fn test_kpk_won() {
+        let config = EndgameConfig::default();
+        // Attacking king in front of its pawn, on a key square: won either side to move
+        for fen in ["4k3/8/4K3/4P3/8/8/8/8 w - - 0 1", "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"] {
+            let board = Board::from_fen(fen).unwrap();
+            assert!(should_use_endgame(&board, &config));
+            let white = if board.turn() == Color::White { evaluate(&board) } else { -evaluate(&board) };
+            assert!(white.raw() >= KPK_WIN, "{}: {}", fen, white);
+        }
+
+        // Outside the square: the pawn runs
+        let board = Board::from_fen("8/8/8/4P3/8/8/k7/7K w - - 0 1").unwrap();
+        assert!(evaluate(&board).raw() >= KPK_WIN);
+
+        // Same for Black, mirrored
+        let board = crate::types::mirror_board(&Board::from_fen("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1").unwrap());
+        assert!(evaluate(&board).raw() >= KPK_WIN);
+    }
+
+    #[test]
+    fn test_kpk_drawn() {
+        // Defending king in front of the pawn, the attacker behind it
+        let board = Board::from_fen("8/8/4k3/8/4P3/4K3/8/8 w - - 0 1").unwrap();
+        assert!(should_use_endgame(&board, &EndgameConfig::default()));
+        assert_eq!(evaluate(&board).raw(), KPK_DRAW);
+
+        // Rook pawn with the defender in the corner
+        let board = Board::from_fen("k7/8/8/8/P7/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(evaluate(&board).raw(), KPK_DRAW);
+
+        // Undefended pawn next to the weak king, which is to move
+        let board = Board::from_fen("8/8/8/3k4/4P3/8/8/K7 b - - 0 1").unwrap();
+        assert_eq!(evaluate(&board).raw(), -KPK_DRAW);
+
+        // Won positions score far above drawn ones
+        let won = Board::from_fen("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1").unwrap();
+        assert!(evaluate(&won).raw() > 10 * KPK_DRAW);
+    }
+
+    #[test]
+    fn test_krk_edge_scores_higher() {
+        // Same material, loser king on the edge vs in the center
+        let edge = Board::from_fen("4k3/8/4K3/8/8/8/8/R7 w - - 0 1").unwrap();
+        let center = Board::from_fen("8/8/8/4k3/8/8/4K3/R7 w - - 0 1").unwrap();
+        assert!(evaluate(&edge) > evaluate(&center));
+        assert!(evaluate(&center).raw() >= KNOWN_WIN);
+    }
+
+    #[test]
+    fn test_opposition_beats_mere_proximity() {
+        // Both kings two squares from the loser on e8: e6 takes the opposition
+        // and covers d7/e7/f7, g6 leaves the loser four flight squares
+        let opposition = Board::from_fen("4k3/8/4K3/8/8/8/8/R7 w - - 0 1").unwrap();
+        let close = Board::from_fen("4k3/8/6K1/8/8/8/8/R7 w - - 0 1").unwrap();
+        assert_eq!(king_escape_squares(&opposition, Color::White, Color::Black), 2);
+        assert_eq!(king_escape_squares(&close, Color::White, Color::Black), 4);
+        assert!(mop_up_bonus(&opposition, Color::White, Color::Black)
+            > mop_up_bonus(&close, Color::White, Color::Black));
+        assert!(evaluate(&opposition) > evaluate(&close));
+
+        // A rook check along the file does not leave the square behind the king free
+        let checked = Board::from_fen("8/8/8/8/4k3/8/8/4R1K1 b - - 0 1").unwrap();
+        assert_eq!(king_escape_squares(&checked, Color::White, Color::Black), 6);
+    }
+
+    #[test]
+    fn test_kqk_avoids_stalemate() {
+        // Qb6 or Qc7 would leave the cornered king without a move
+        let board = Board::from_fen("k7/8/2KQ4/8/8/8/8/8 w - - 0 1").unwrap();
+        for uci in ["d6b6", "d6c7"] {
+            let m = crate::uci::parse_move(&board, uci).unwrap();
+            let stalemate = board.make_move_new(m);
+            assert!(stalemate.generate_moves().is_empty() && !stalemate.in_check());
+            assert_eq!(evaluate(&stalemate), Score::draw());
+        }
+
+        // Even a one-ply search, whose leaves are only statically evaluated, keeps the win
+        let mut searcher = Searcher::new();
+        searcher.set_position(board);
+        let result = searcher.search(SearchLimits::depth(1));
+        let best = result.best_move.unwrap().to_uci();
+        assert!(best != "d6b6" && best != "d6c7", "{best}");
+        assert!(result.score > Score::cp(KNOWN_WIN));
+    }
+
+    #[test]
+    fn test_kqk_mate() {
+        // Theoretical bound for KQvK is 10 moves
+        assert!(mates_within("8/8/8/3k4/8/8/8/3QK3 w - - 0 1", 10, 6));
+        assert!(mates_within("8/8/2k5/8/8/5Q2/8/6K1 w - - 0 1", 10, 6));
+    }
+
+    #[test]
+    fn test_krk_mate() {
+        // Theoretical bound for KRvK is 16 moves
+        assert!(mates_within("8/8/8/8/3k4/8/8/R3K3 w - - 0 1", 16, 6));
+        assert!(mates_within("7k/8/8/8/8/8/8/R5K1 w - - 0 1", 16, 6));
+    }
+}
@@ -4,8 +4,11 @@
 //! - Packed MG/EG scores for efficient tapered evaluation
 //! - Precomputed distance tables (compile-time)
 //! - Branchless arithmetic via const generics
-//! - Cache-aligned PST arrays
+//! - Cache-aligned PST arrays, adjustable at runtime through `PstConfig`
 //! - Endgame-aware bonuses (king proximity, passed pawns, corner driving)
+//! - Pawn structure (passed, doubled, isolated and backward pawns)
+//! - Material imbalance from piece counts (Stockfish-style quadratic table)
+//! - Per-evaluation cache of pawn attacks and king zones (`EvalInfo`)
 //!
 //! Used as NNUE fallback and works seamlessly across all phases.
 
@@ -111,10 +114,12 @@ const BISHOP_PAIR: S = S::new(35, 50);
 // ============================================================================
 
 /// PST indexed by [piece][square], packed MG/EG
+#[derive(Clone)]
 #[repr(align(64))]
 struct PstTable([[S; 64]; 6]);
 
-static PST: PstTable = PstTable([
+/// Built-in PST values
+const PST_VALUES: PstTable = PstTable([
     // Pawn
     [
         S::new(0, 0), S::new(0, 0), S::new(0, 0), S::new(0, 0), S::new(0, 0), S::new(0, 0), S::new(0, 0), S::new(0, 0),
@@ -183,6 +188,84 @@ static PST: PstTable = PstTable([
     ],
 ]);
 
+/// Piece-square tables as read by the HCE, adjustable at runtime for tuning.
+///
+/// `base` holds the unscaled values (built-in values plus per-square
+/// overrides), `table` the values after `PstScaleMG` / `PstScaleEG` (percent)
+/// are applied. Squares are from White's point of view; Black uses the
+/// vertically flipped square.
+#[derive(Clone)]
+pub struct PstConfig {
+    base: PstTable,
+    table: PstTable,
+    scale_mg: i32,
+    scale_eg: i32,
+}
+
+/// The built-in tables at 100%
+pub static DEFAULT_PST: PstConfig = PstConfig::new();
+
+impl PstConfig {
+    pub const fn new() -> Self {
+        Self { base: PST_VALUES, table: PST_VALUES, scale_mg: 100, scale_eg: 100 }
+    }
+
+    /// Set a PST option by (case-insensitive) UCI name: `PstScaleMG` /
+    /// `PstScaleEG` in percent, or a single entry as `Pst<Piece><Square><Mg|Eg>`
+    /// (e.g. `PstKnightE4Mg`).
+    ///
+    /// Returns false if the name is not a PST option.
+    pub fn set(&mut self, name: &str, value: i32) -> bool {
+        let name = name.to_lowercase();
+        match name.as_str() {
+            "pstscalemg" => self.scale_mg = value.clamp(0, 1000),
+            "pstscaleeg" => self.scale_eg = value.clamp(0, 1000),
+            _ => {
+                let Some((piece, sq, mg)) = parse_pst_entry(&name) else {
+                    return false;
+                };
+                let value = value.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+                let entry = &mut self.base.0[piece][sq];
+                if mg { entry.mg = value } else { entry.eg = value }
+            }
+        }
+        self.rescale();
+        true
+    }
+
+    /// Recompute the scaled table from `base`
+    fn rescale(&mut self) {
+        let scale = |v: i16, pct: i32| (v as i32 * pct / 100).clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        for (base, table) in self.base.0.iter().zip(self.table.0.iter_mut()) {
+            for (b, t) in base.iter().zip(table.iter_mut()) {
+                *t = S::new(scale(b.mg, self.scale_mg), scale(b.eg, self.scale_eg));
+            }
+        }
+    }
+}
+
+impl Default for PstConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse `pst<piece><square><mg|eg>` (lowercase) into (piece index, square, is_mg)
+fn parse_pst_entry(name: &str) -> Option<(usize, usize, bool)> {
+    const PIECES: [&str; 6] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
+
+    let rest = name.strip_prefix("pst")?;
+    let (rest, mg) = match rest.strip_suffix("mg") {
+        Some(r) => (r, true),
+        None => (rest.strip_suffix("eg")?, false),
+    };
+    let split = rest.len().checked_sub(2)?;
+    let (piece, square) = (rest.get(..split)?, rest.get(split..)?);
+    let piece = PIECES.iter().position(|&p| p == piece)?;
+    let sq = Square::from_algebraic(square)?;
+    Some((piece, sq.index() as usize, mg))
+}
+
 // ============================================================================
 // PRECOMPUTED DISTANCE TABLES (Compile-time)
 // ============================================================================
@@ -238,6 +321,24 @@ static KING_DIST: [[i32; 64]; 64] = {
     table
 };
 
+/// Chebyshev (king) distance between two squares
+#[inline(always)]
+pub(crate) fn king_distance(a: Square, b: Square) -> i32 {
+    KING_DIST[a.index() as usize][b.index() as usize]
+}
+
+/// Distance from the center (0 = center, 6 = corner)
+#[inline(always)]
+pub(crate) fn center_distance(sq: Square) -> i32 {
+    CENTER_DIST[sq.index() as usize]
+}
+
+/// Distance to the nearest corner (0 = corner, 6 = center)
+#[inline(always)]
+pub(crate) fn corner_distance(sq: Square) -> i32 {
+    CORNER_DIST[sq.index() as usize]
+}
+
 // ============================================================================
 // PASSED PAWN MASKS (Precomputed)
 // ============================================================================
@@ -303,6 +404,16 @@ static PASSED_MASK_BLACK: [u64; 64] = {
     masks
 };
 
+/// Check if a pawn of `color` on `sq` is passed (no enemy pawns ahead on its own or adjacent files)
+#[inline(always)]
+pub(crate) fn is_passed(color: Color, sq: Square, enemy_pawns: u64) -> bool {
+    let mask = match color {
+        Color::White => PASSED_MASK_WHITE[sq.index() as usize],
+        Color::Black => PASSED_MASK_BLACK[sq.index() as usize],
+    };
+    enemy_pawns & mask == 0
+}
+
 /// Passed pawn bonus by rank advancement (index 0-7)
 static PASSED_BONUS: [S; 8] = [
     S::new(0, 0),       // Rank 1 (impossible for white)
@@ -315,15 +426,25 @@ static PASSED_BONUS: [S; 8] = [
     S::new(0, 0),       // Rank 8 (impossible)
 ];
 
+/// Penalty per pawn with another friendly pawn ahead of it on its file
+const DOUBLED_PENALTY: S = S::new(10, 25);
+
+/// Penalty for a pawn with no friendly pawns on either adjacent file
+const ISOLATED_PENALTY: S = S::new(10, 15);
+
+/// Penalty for a pawn that can no longer be defended by a neighbour and
+/// whose advance is stopped by an enemy pawn
+const BACKWARD_PENALTY: S = S::new(8, 10);
+
 // ============================================================================
 // PHASE CALCULATION
 // ============================================================================
 
 const PHASE_TOTAL: i32 = 24; // 4*1 (N) + 4*1 (B) + 4*2 (R) + 2*4 (Q)
 
-/// Calculate game phase (0 = endgame, 256 = opening)
+/// Calculate game phase (0 = opening, 256 = endgame)
 #[inline(always)]
-fn calculate_phase(board: &Board) -> i32 {
+pub(crate) fn calculate_phase(board: &Board) -> i32 {
     let n = board.piece_bb(Piece::Knight).count() as i32;
     let b = board.piece_bb(Piece::Bishop).count() as i32;
     let r = board.piece_bb(Piece::Rook).count() as i32;
@@ -336,22 +457,160 @@ fn calculate_phase(board: &Board) -> i32 {
     if phase < 0 { 0 } else if phase > PHASE_TOTAL { 256 } else { (phase * 256) / PHASE_TOTAL }
 }
 
+// ============================================================================
+// MATERIAL IMBALANCE
+// ============================================================================
+
+/// Quadratic imbalance coefficients against our own pieces.
+/// Index order: bishop pair, pawn, knight, bishop, rook, queen (lower triangle).
+/// The bishop pair diagonal is 0 because `BISHOP_PAIR` already covers it.
+const IMBALANCE_OURS: [[i32; 6]; 6] = [
+    [   0,   0,   0,   0,    0,  0],
+    [  40,  38,   0,   0,    0,  0],
+    [  32, 255, -62,   0,    0,  0],
+    [   0, 104,   4,   0,    0,  0],
+    [ -26,  -2,  47, 105, -208,  0],
+    [-189,  24, 117, 133, -134, -6],
+];
+
+/// Quadratic imbalance coefficients against the opponent's pieces
+const IMBALANCE_THEIRS: [[i32; 6]; 6] = [
+    [  0,   0,   0,   0,   0, 0],
+    [ 36,   0,   0,   0,   0, 0],
+    [  9,  63,   0,   0,   0, 0],
+    [ 59,  65,  42,   0,   0, 0],
+    [ 46,  39,  24, -24,   0, 0],
+    [ 97, 100, -42, 137, 268, 0],
+];
+
+/// Table units per centipawn (coefficients are in 1/16 of a 128-unit pawn)
+const IMBALANCE_SCALE: i32 = 16 * 128 / 100;
+
+/// Piece counts for one side in imbalance-table order
+#[inline(always)]
+fn imbalance_counts(board: &Board, color: Color) -> [i32; 6] {
+    let ours = board.color_bb(color);
+    let count = |piece| (board.piece_bb(piece) & ours).count() as i32;
+    let bishops = count(Piece::Bishop);
+    [
+        (bishops >= 2) as i32,
+        count(Piece::Pawn),
+        count(Piece::Knight),
+        bishops,
+        count(Piece::Rook),
+        count(Piece::Queen),
+    ]
+}
+
+/// Imbalance for the side owning `us` (in table units)
+#[inline(always)]
+fn imbalance_side(us: &[i32; 6], them: &[i32; 6]) -> i32 {
+    let mut bonus = 0;
+    for pt1 in 0..6 {
+        if us[pt1] == 0 {
+            continue;
+        }
+        let mut v = 0;
+        for pt2 in 0..=pt1 {
+            v += IMBALANCE_OURS[pt1][pt2] * us[pt2] + IMBALANCE_THEIRS[pt1][pt2] * them[pt2];
+        }
+        bonus += us[pt1] * v;
+    }
+    bonus
+}
+
+/// Material imbalance in centipawns (white's perspective)
+#[inline(always)]
+fn imbalance(board: &Board) -> i32 {
+    let white = imbalance_counts(board, Color::White);
+    let black = imbalance_counts(board, Color::Black);
+    (imbalance_side(&white, &black) - imbalance_side(&black, &white)) / IMBALANCE_SCALE
+}
+
 // ============================================================================
 // MAIN EVALUATION
 // ============================================================================
 
+// ============================================================================
+// EVAL INFO (computed once per evaluation, shared by all terms)
+// ============================================================================
+
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+
+/// Bitboards several eval terms need, computed once per `evaluate` call.
+///
+/// Indexed by `Color::index()`. Nothing here survives between evaluations.
+pub struct EvalInfo {
+    /// Pawns of each color
+    pub pawns: [u64; 2],
+    /// Squares attacked by each color's pawns
+    pub pawn_attacks: [u64; 2],
+    /// Squares each color's pawns can attack now or after advancing
+    pub pawn_attack_span: [u64; 2],
+    /// King square plus the squares around it
+    pub king_zone: [u64; 2],
+}
+
+impl EvalInfo {
+    pub fn new(board: &Board) -> Self {
+        let pawns_of = |c: Color| (board.piece_bb(Piece::Pawn) & board.color_bb(c)).bits();
+        let pawns = [pawns_of(Color::White), pawns_of(Color::Black)];
+
+        let white_attacks = ((pawns[0] & !FILE_A) << 7) | ((pawns[0] & !FILE_H) << 9);
+        let black_attacks = ((pawns[1] & !FILE_A) >> 9) | ((pawns[1] & !FILE_H) >> 7);
+
+        let king_zone = |c: Color| {
+            let ksq = board.king_square(c);
+            movegen::attacks::king_attacks(ksq).bits() | (1u64 << ksq.index())
+        };
+
+        Self {
+            pawns,
+            pawn_attacks: [white_attacks, black_attacks],
+            pawn_attack_span: [fill_north(white_attacks), fill_south(black_attacks)],
+            king_zone: [king_zone(Color::White), king_zone(Color::Black)],
+        }
+    }
+}
+
+#[inline(always)]
+const fn fill_north(mut bb: u64) -> u64 {
+    bb |= bb << 8;
+    bb |= bb << 16;
+    bb | (bb << 32)
+}
+
+#[inline(always)]
+const fn fill_south(mut bb: u64) -> u64 {
+    bb |= bb >> 8;
+    bb |= bb >> 16;
+    bb | (bb >> 32)
+}
+
 /// Main evaluation function - returns score from side-to-move perspective
 #[inline]
 pub fn evaluate(board: &Board) -> Score {
+    evaluate_with(board, &DEFAULT_PST)
+}
+
+/// `evaluate` with runtime-adjusted piece-square tables
+#[inline]
+pub fn evaluate_with(board: &Board, pst: &PstConfig) -> Score {
     let phase = calculate_phase(board);
+    let info = EvalInfo::new(board);
     
     // Evaluate both sides
-    let white_score = eval_side::<true>(board);
-    let black_score = eval_side::<false>(board);
+    let white_score = eval_side::<true>(board, &info, pst);
+    let black_score = eval_side::<false>(board, &info, pst);
     
     // Net score from white's perspective
     let mut score = white_score - black_score;
     
+    // Piece-count imbalance applies equally to both phases
+    let imb = imbalance(board) as i16;
+    score += S::new(imb, imb);
+    
     // Add endgame-specific bonuses (scaled by phase)
     if phase > 128 {
         score = score + endgame_bonuses(board, phase);
@@ -372,7 +631,7 @@ pub fn evaluate(board: &Board) -> Score {
 
 /// Evaluate one side using const generic for branchless color handling
 #[inline(always)]
-fn eval_side<const IS_WHITE: bool>(board: &Board) -> S {
+fn eval_side<const IS_WHITE: bool>(board: &Board, info: &EvalInfo, pst: &PstConfig) -> S {
     let color = if IS_WHITE { Color::White } else { Color::Black };
     let mut score = S::default();
     
@@ -395,7 +654,7 @@ fn eval_side<const IS_WHITE: bool>(board: &Board) -> S {
             }
             
             // PST bonus
-            score += PST.0[piece_idx][sq_idx];
+            score += pst.table.0[piece_idx][sq_idx];
         }
     }
     
@@ -405,24 +664,27 @@ fn eval_side<const IS_WHITE: bool>(board: &Board) -> S {
         score += BISHOP_PAIR;
     }
     
-    // Passed pawn evaluation
-    score = score + eval_passed_pawns::<IS_WHITE>(board);
+    // Passed, doubled, isolated and backward pawns
+    score += eval_pawn_structure::<IS_WHITE>(info);
     
     score
 }
 
-/// Evaluate passed pawns for one side
+/// Pawn structure for one side: passed pawn bonuses plus doubled,
+/// isolated and backward pawn penalties
 #[inline(always)]
-fn eval_passed_pawns<const IS_WHITE: bool>(board: &Board) -> S {
-    let color = if IS_WHITE { Color::White } else { Color::Black };
-    let our_pawns = board.piece_bb(Piece::Pawn) & board.color_bb(color);
-    let enemy_pawns = board.piece_bb(Piece::Pawn) & board.color_bb(!color);
-    let enemy_pawns_bb = enemy_pawns.bits();
+fn eval_pawn_structure<const IS_WHITE: bool>(info: &EvalInfo) -> S {
+    let (us, them) = if IS_WHITE { (0, 1) } else { (1, 0) };
+    let own_pawns_bb = info.pawns[us];
+    let enemy_pawns_bb = info.pawns[them];
     
-    let mut bonus = S::default();
+    let mut score = S::default();
     
-    for sq in our_pawns {
-        let sq_idx = sq.index() as usize;
+    let mut our_pawns = own_pawns_bb;
+    while our_pawns != 0 {
+        let sq_idx = our_pawns.trailing_zeros() as usize;
+        our_pawns &= our_pawns - 1;
+        let bit = 1u64 << sq_idx;
         
         // Get appropriate passed pawn mask
         let mask = if IS_WHITE {
@@ -440,11 +702,31 @@ fn eval_passed_pawns<const IS_WHITE: bool>(board: &Board) -> S {
                 7 - (sq_idx / 8)
             };
             
-            bonus += PASSED_BONUS[rank];
+            score += PASSED_BONUS[rank];
+        }
+        
+        // Doubled: another of our pawns further up the same file
+        let front_span = if IS_WHITE { fill_north(bit << 8) } else { fill_south(bit >> 8) };
+        if own_pawns_bb & front_span != 0 {
+            score -= DOUBLED_PENALTY;
+        }
+        
+        let file = FILE_A << (sq_idx % 8);
+        let adjacent = ((file & !FILE_A) >> 1) | ((file & !FILE_H) << 1);
+        if own_pawns_bb & adjacent == 0 {
+            score -= ISOLATED_PENALTY;
+            continue;
+        }
+        
+        // Backward: the stop square is covered by an enemy pawn and no pawn
+        // of ours can ever advance to defend it
+        let stop = if IS_WHITE { bit << 8 } else { bit >> 8 };
+        if stop & info.pawn_attacks[them] & !info.pawn_attack_span[us] != 0 {
+            score -= BACKWARD_PENALTY;
         }
     }
     
-    bonus
+    score
 }
 
 /// Endgame-specific bonuses (king proximity, corner driving)
@@ -496,7 +778,7 @@ fn endgame_bonuses(board: &Board, phase: i32) -> S {
 
 /// Quick material balance (positive = white ahead)
 #[inline(always)]
-fn material_balance(board: &Board) -> i32 {
+pub(crate) fn material_balance(board: &Board) -> i32 {
     const VALUES: [i32; 5] = [100, 320, 330, 500, 900];
     let pieces = [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
     
@@ -517,6 +799,49 @@ fn material_balance(board: &Board) -> i32 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_eval_info_pawn_attacks() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/P6p/8/8/8/8/p6P/4K3 w - - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let info = EvalInfo::new(&board);
+
+            for color in [Color::White, Color::Black] {
+                let pawns = board.piece_bb(Piece::Pawn) & board.color_bb(color);
+                let mut fresh = 0u64;
+                let mut span = 0u64;
+                for sq in pawns {
+                    fresh |= movegen::attacks::pawn_attacks(color, sq).bits();
+                    // Span: attacks from every square ahead on the file
+                    let mut ahead = sq;
+                    loop {
+                        span |= movegen::attacks::pawn_attacks(color, ahead).bits();
+                        let next = match color {
+                            Color::White => ahead.index() + 8,
+                            Color::Black => ahead.index().wrapping_sub(8),
+                        };
+                        match Square::from_index(next) {
+                            Some(s) if next < 64 => ahead = s,
+                            _ => break,
+                        }
+                    }
+                }
+                assert_eq!(info.pawns[color.index()], pawns.bits(), "{}", fen);
+                assert_eq!(info.pawn_attacks[color.index()], fresh, "{}", fen);
+                assert_eq!(info.pawn_attack_span[color.index()], span, "{}", fen);
+
+                let ksq = board.king_square(color);
+                assert!(info.king_zone[color.index()] & (1u64 << ksq.index()) != 0);
+                assert_eq!(info.king_zone[color.index()].count_ones(),
+                           movegen::attacks::king_attacks(ksq).count() + 1);
+            }
+        }
+    }
+
     #[test]
     fn test_packed_score() {
         let s = S::new(100, -50);
@@ -560,6 +885,66 @@ mod tests {
         assert!(eg_phase > 200, "Endgame phase: {}", eg_phase);
     }
 
+    #[test]
+    fn test_imbalance() {
+        // Symmetric material has no imbalance
+        assert_eq!(imbalance(&Board::default()), 0);
+
+        // Bishop pair + knight vs rook + extra pawn: the table values the
+        // minor-piece side noticeably differently than raw material does
+        let board = Board::from_fen("r3k3/pppp4/8/8/8/8/PPP5/2BNKB2 w - - 0 1").unwrap();
+        let mirrored = Board::from_fen("2bnkb2/ppp5/8/8/8/8/PPPP4/R3K3 b - - 0 1").unwrap();
+        let imb = imbalance(&board);
+        assert_ne!(imb, 0);
+        assert_eq!(imbalance(&mirrored), -imb);
+
+        // And the full eval differs from the eval without the imbalance term
+        let phase = calculate_phase(&board);
+        let info = EvalInfo::new(&board);
+        let mut raw = eval_side::<true>(&board, &info, &DEFAULT_PST) - eval_side::<false>(&board, &info, &DEFAULT_PST);
+        if phase > 128 {
+            raw += endgame_bonuses(&board, phase);
+        }
+        let raw_tapered = (raw.mg() * (256 - phase) + raw.eg() * phase) / 256;
+        assert_ne!(evaluate(&board).raw(), raw_tapered);
+    }
+
+    #[test]
+    fn test_pst_scale_is_proportional() {
+        // Full material: phase 0, so only the MG half of the tables counts
+        let board = Board::from_fen("rnbqkb1r/pppppppp/5n2/8/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 2").unwrap();
+        let at = |scale: i32| {
+            let mut pst = PstConfig::new();
+            assert!(pst.set("PstScaleMG", scale));
+            evaluate_with(&board, &pst).raw()
+        };
+
+        assert_eq!(at(100), evaluate(&board).raw());
+        let pst_part = at(100) - at(0);
+        assert_ne!(pst_part, 0);
+        assert_eq!(at(200) - at(0), 2 * pst_part);
+        // Per-entry rounding: at most one centipawn per piece
+        assert!((at(110) - at(0) - pst_part * 11 / 10).abs() <= 32);
+
+        // EG scaling does not matter at phase 0
+        let mut pst = PstConfig::new();
+        assert!(pst.set("PstScaleEG", 0));
+        assert_eq!(evaluate_with(&board, &pst), evaluate(&board));
+    }
+
+    #[test]
+    fn test_pst_entry_override() {
+        let board = Board::from_fen("rnbqkb1r/pppppppp/5n2/8/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 2").unwrap();
+        let mut pst = PstConfig::new();
+        // The c3 knight entry (+10 MG by default) raised by 40
+        assert!(pst.set("PstKnightC3Mg", 50));
+        assert_eq!(evaluate_with(&board, &pst).raw(), evaluate(&board).raw() - 40);
+
+        assert!(!pst.set("PstKnightZ9Mg", 50));
+        assert!(!pst.set("PstDragonC3Mg", 50));
+        assert!(!pst.set("PstKnightC3", 50));
+    }
+
     #[test]
     fn test_passed_pawn() {
         // White has a passed pawn on d5
@@ -568,4 +953,51 @@ mod tests {
         // Should have bonus for passed pawn
         assert!(score.raw() > 100, "Passed pawn score: {}", score.raw());
     }
+
+    #[test]
+    fn test_doubled_pawns_score_lower() {
+        // g2+g3 (doubled, both isolated) against g2+h3 on the same ranks
+        let doubled = Board::from_fen("4k3/8/8/8/8/6P1/6P1/4K3 w - - 0 1").unwrap();
+        let clean = Board::from_fen("4k3/8/8/8/8/7P/6P1/4K3 w - - 0 1").unwrap();
+        assert!(evaluate(&doubled).raw() < evaluate(&clean).raw(),
+                "doubled {} vs clean {}", evaluate(&doubled).raw(), evaluate(&clean).raw());
+
+        let info = EvalInfo::new(&doubled);
+        let structure = eval_pawn_structure::<true>(&info);
+        let passers = PASSED_BONUS[1] + PASSED_BONUS[2];
+        let expected = passers - DOUBLED_PENALTY - ISOLATED_PENALTY - ISOLATED_PENALTY;
+        assert_eq!((structure.mg(), structure.eg()), (expected.mg(), expected.eg()));
+    }
+
+    #[test]
+    fn test_advanced_passer_is_eg_weighted() {
+        // Same lone (isolated) d-pawn on the 3rd and the 6th rank; only the
+        // passed pawn bonus differs
+        let term = |fen: &str| {
+            let board = Board::from_fen(fen).unwrap();
+            eval_pawn_structure::<true>(&EvalInfo::new(&board))
+        };
+        let back = term("4k3/p7/8/8/8/3P4/8/4K3 w - - 0 1");
+        let advanced = term("4k3/p7/3P4/8/8/8/8/4K3 w - - 0 1");
+        let gain = advanced - back;
+        assert!(gain.mg() > 0);
+        assert!(gain.eg() > gain.mg(), "mg {} eg {}", gain.mg(), gain.eg());
+
+        // Black's pawn structure mirrors White's
+        let board = Board::from_fen("4k3/8/3p4/8/8/8/8/4K3 b - - 0 1").unwrap();
+        let black = eval_pawn_structure::<false>(&EvalInfo::new(&board));
+        let board = Board::from_fen("4k3/8/8/8/8/3P4/8/4K3 w - - 0 1").unwrap();
+        let white = eval_pawn_structure::<true>(&EvalInfo::new(&board));
+        assert_eq!((black.mg(), black.eg()), (white.mg(), white.eg()));
+    }
+
+    #[test]
+    fn test_backward_pawn() {
+        // d3 cannot be supported (the c-pawn is already on c4) and e5 guards d4
+        let board = Board::from_fen("4k3/8/8/4p3/2P5/3P4/8/4K3 w - - 0 1").unwrap();
+        let info = EvalInfo::new(&board);
+        let structure = eval_pawn_structure::<true>(&info);
+        let expected = PASSED_BONUS[3] - BACKWARD_PENALTY;
+        assert_eq!((structure.mg(), structure.eg()), (expected.mg(), expected.eg()));
+    }
 }
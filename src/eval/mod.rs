@@ -2,65 +2,294 @@
 //!
 //! Uses NNUE if available, otherwise falls back to optimized HCE.
 //! The HCE handles all game phases with tapered evaluation.
+//!
+//! With opposite-colored bishops the NNUE/HCE score is scaled towards a
+//! draw (`scale_opposite_bishops`).
+//!
+//! As the fifty-move counter climbs, `SearchEvaluator` scores shrink towards a
+//! draw (`scale_fifty_move`), so moves that make progress are preferred.
+//!
+//! `EvalKind` pins the search to one evaluation function instead, so eval
+//! functions can be compared on otherwise identical searches.
 
-use crate::types::{Board, Score, Color, Piece, piece_value, Value, Move};
+use crate::types::{Board, Score, Color, Piece, piece_value, Value, Move, DARK_SQUARES};
 
 pub mod nnue;
 pub mod hce;
+pub mod endgame;
 
 // Re-export the evaluator for use in search
 pub use nnue::NnueEvaluator;
+pub use endgame::EndgameConfig;
+
+/// Scaled NNUE output is kept within this many centipawns, far from mate scores
+const SCALED_EVAL_LIMIT: i32 = 20_000;
+
+/// Halfmove clock at which the eval would be scaled down to nothing: at the
+/// fifty-move limit (100 plies) half of it is left
+const FIFTY_MOVE_SCALE_PLIES: u32 = 200;
+
+/// Percent of the eval kept with opposite-colored bishops and no pawns for
+/// the stronger side
+const OCB_SCALE_BASE: i32 = 25;
+
+/// Percent of the eval kept per pawn of the stronger side (100 at most)
+const OCB_SCALE_PER_PAWN: i32 = 12;
+
+/// Which evaluation function a `SearchEvaluator` uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvalKind {
+    /// NNUE if a model is loaded, otherwise HCE, with the heuristic endgame
+    /// eval taking over where its `EndgameConfig` says so
+    #[default]
+    Auto,
+    /// NNUE only (HCE if no model is loaded)
+    Nnue,
+    /// The hand-crafted eval only
+    Hce,
+    /// The heuristic endgame eval, in every position
+    Endgame,
+    /// Material count only
+    Material,
+}
+
+/// Evaluator wrapper that handles NNUE or HCE evaluation, with the heuristic
+/// endgame eval taking over where its `EndgameConfig` says so
+#[derive(Clone)]
+pub struct SearchEvaluator<'a> {
+    backend: Backend<'a>,
+    endgame: EndgameConfig,
+    /// Consult the heuristic endgame eval first (`EvalKind::Auto` only)
+    probe_endgame: bool,
+    /// NNUE output scale in percent (`EvalScale`)
+    scale: i32,
+    /// NNUE output scale by piece count (`OutputScaling`, off: `None`)
+    output_scale: Option<&'a nnue::OutputScale>,
+    /// Halfmove clock of the position being evaluated (`set_halfmove_clock`)
+    halfmove_clock: u32,
+}
 
-/// Evaluator wrapper that handles NNUE or HCE evaluation
 #[derive(Clone)]
-pub enum SearchEvaluator<'a> {
+enum Backend<'a> {
     Nnue(NnueEvaluator<'a>),
-    Hce,
+    /// Main and endgame nets, switched by piece count
+    DualNnue {
+        main: &'a nnue::Model,
+        endgame: &'a nnue::Model,
+        eval: NnueEvaluator<'a>,
+    },
+    Hce(&'a hce::PstConfig),
+    Endgame,
+    Material,
 }
 
 impl<'a> SearchEvaluator<'a> {
-    pub fn new(model: Option<&'a nnue::Model>, board: &Board) -> Self {
-        match model {
-            Some(m) => Self::Nnue(NnueEvaluator::new(&**m, board)),
-            None => Self::Hce,
+    /// Create an evaluator for `board` with the default endgame thresholds.
+    ///
+    /// With both nets the endgame net is used once `endgame::is_endgame_phase` holds;
+    /// with only one of them that net is used everywhere.
+    pub fn new(model: Option<&'a nnue::Model>, endgame_model: Option<&'a nnue::Model>, board: &Board) -> Self {
+        Self::with_kind(EvalKind::Auto, model, endgame_model, board)
+    }
+
+    /// Create an evaluator for `board` using the evaluation function `kind`,
+    /// whichever models are loaded
+    pub fn with_kind(
+        kind: EvalKind,
+        model: Option<&'a nnue::Model>,
+        endgame_model: Option<&'a nnue::Model>,
+        board: &Board,
+    ) -> Self {
+        let backend = match (kind, model, endgame_model) {
+            (EvalKind::Hce, ..) => Backend::Hce(&hce::DEFAULT_PST),
+            (EvalKind::Endgame, ..) => Backend::Endgame,
+            (EvalKind::Material, ..) => Backend::Material,
+            (_, Some(main), Some(endgame)) => {
+                let active = if endgame::is_endgame_phase(board) { endgame } else { main };
+                Backend::DualNnue { main, endgame, eval: NnueEvaluator::new(active, board) }
+            }
+            (_, Some(m), None) | (_, None, Some(m)) => Backend::Nnue(NnueEvaluator::new(m, board)),
+            (_, None, None) => Backend::Hce(&hce::DEFAULT_PST),
+        };
+        Self { backend, endgame: EndgameConfig::default(), probe_endgame: kind == EvalKind::Auto, scale: 100, output_scale: None, halfmove_clock: 0 }
+    }
+
+    /// Use `config` to decide when the heuristic endgame eval takes over
+    pub fn with_endgame(mut self, config: EndgameConfig) -> Self {
+        self.endgame = config;
+        self
+    }
+
+    /// Use `pst` for the HCE (no effect with NNUE)
+    pub fn with_pst(mut self, pst: &'a hce::PstConfig) -> Self {
+        if let Backend::Hce(current) = &mut self.backend {
+            *current = pst;
+        }
+        self
+    }
+
+    /// Scale the NNUE output by `percent` (the HCE and endgame evals are unaffected)
+    pub fn with_scale(mut self, percent: i32) -> Self {
+        self.scale = percent;
+        self
+    }
+
+    /// Scale the NNUE output by piece count with `table` (`None`: off)
+    pub fn with_output_scale(mut self, table: Option<&'a nnue::OutputScale>) -> Self {
+        self.output_scale = table;
+        self
+    }
+
+    /// Halfmove clock of the positions evaluated next: the search sets it at
+    /// every node, since the board does not carry it
+    #[inline]
+    pub fn set_halfmove_clock(&mut self, clock: u32) {
+        self.halfmove_clock = clock;
+    }
+
+    /// Is the endgame net currently active
+    pub fn uses_endgame_net(&self) -> bool {
+        match &self.backend {
+            Backend::DualNnue { endgame, eval, .. } => core::ptr::eq(eval.model(), &***endgame),
+            _ => false,
         }
     }
 
+    /// Score of `board` for the side to move, scaled down by the halfmove clock
     #[inline]
     pub fn evaluate(&mut self, board: &Board) -> Score {
-        match self {
-            Self::Nnue(e) => e.evaluate(board.turn()),
-            Self::Hce => hce::evaluate(board),
+        let score = self.evaluate_unscaled(board);
+        scale_fifty_move(score, self.halfmove_clock)
+    }
+
+    #[inline]
+    fn evaluate_unscaled(&mut self, board: &Board) -> Score {
+        if self.probe_endgame {
+            if let Some(score) = endgame::probe(board, &self.endgame) {
+                return score;
+            }
+        }
+        let scale_nnue = |score| scale_eval(scale_output(board, score, self.output_scale), self.scale);
+        match &mut self.backend {
+            Backend::Nnue(e) => scale_opposite_bishops(board, scale_nnue(e.evaluate(board))),
+            Backend::DualNnue { eval, .. } => scale_opposite_bishops(board, scale_nnue(eval.evaluate(board))),
+            Backend::Hce(pst) => scale_opposite_bishops(board, hce::evaluate_with(board, pst)),
+            Backend::Endgame => endgame::evaluate(board),
+            Backend::Material => material_eval_wrapper(board),
         }
     }
 
+    /// Update for a move, returns false if refresh needed.
+    ///
+    /// With two nets, a capture that crosses the endgame threshold also asks for a
+    /// refresh, since the accumulator has to be rebuilt for the other net.
     #[inline]
     pub fn update_move(&mut self, board: &Board, m: Move) -> bool {
-        match self {
-            Self::Nnue(e) => e.update_move(board, m),
-            Self::Hce => true, // HCE is stateless
+        let crosses_phase = self.crosses_phase(board, m);
+        match &mut self.backend {
+            Backend::Nnue(e) => e.update_move(board, m),
+            Backend::DualNnue { eval, .. } => !crosses_phase && eval.update_move(board, m),
+            // Stateless
+            Backend::Hce(_) | Backend::Endgame | Backend::Material => true,
         }
     }
 
     #[inline]
     pub fn refresh(&mut self, board: &Board) {
-        if let Self::Nnue(e) = self {
-            e.refresh(board);
+        match &mut self.backend {
+            Backend::Nnue(e) => e.refresh(board),
+            Backend::DualNnue { main, endgame, eval } => {
+                let active = if endgame::is_endgame_phase(board) { *endgame } else { *main };
+                *eval = NnueEvaluator::new(active, board);
+            }
+            Backend::Hce(_) | Backend::Endgame | Backend::Material => {}
+        }
+    }
+
+    /// Does `m` move the position across the main/endgame net boundary
+    #[inline]
+    fn crosses_phase(&self, board: &Board, m: Move) -> bool {
+        if !matches!(self.backend, Backend::DualNnue { .. }) || !m.is_capture() {
+            return false;
         }
+        let after = board.occupied().count() - 1;
+        (after <= endgame::ENDGAME_PIECE_THRESHOLD) != self.uses_endgame_net()
+    }
+}
+
+/// Apply `EvalScale` to an NNUE score, staying clear of the mate range
+#[inline]
+fn scale_eval(score: Score, percent: i32) -> Score {
+    if percent == 100 {
+        return score;
+    }
+    Score::cp((score.raw() * percent / 100).clamp(-SCALED_EVAL_LIMIT, SCALED_EVAL_LIMIT))
+}
+
+/// Apply `OutputScaling` to an NNUE score (the bucket is only counted when on)
+#[inline]
+fn scale_output(board: &Board, score: Score, table: Option<&nnue::OutputScale>) -> Score {
+    match table {
+        Some(table) => Score::cp(nnue::scale_output(score.raw(), nnue::output_bucket(board), table)),
+        None => score,
+    }
+}
+
+/// Shrink `score` by `(200 - clock) / 200`: the closer the fifty-move rule, the
+/// less a winning eval is worth. Mate scores are left alone.
+#[inline]
+fn scale_fifty_move(score: Score, clock: u32) -> Score {
+    if clock == 0 || score.is_mate_score() {
+        return score;
     }
+    let left = FIFTY_MOVE_SCALE_PLIES.saturating_sub(clock) as i32;
+    Score::cp(score.raw() * left / FIFTY_MOVE_SCALE_PLIES as i32)
+}
+
+/// Scale `score` (side to move) towards a draw with opposite-colored bishops.
+///
+/// Applies when each side has exactly one bishop, on squares of different
+/// colors, and the other pieces match. The fewer pawns the stronger side
+/// has, the more of its edge is taken off; with six or more it keeps all.
+#[inline]
+fn scale_opposite_bishops(board: &Board, score: Score) -> Score {
+    let bishops = board.piece_bb(Piece::Bishop);
+    if bishops.count() != 2 || (bishops & board.color_bb(Color::White)).count() != 1 {
+        return score;
+    }
+    // One bishop each: on opposite colors if exactly one is on a dark square
+    if (bishops.bits() & DARK_SQUARES).count_ones() != 1 {
+        return score;
+    }
+    for piece in [Piece::Knight, Piece::Rook, Piece::Queen] {
+        let bb = board.piece_bb(piece);
+        if (bb & board.color_bb(Color::White)).count() != (bb & board.color_bb(Color::Black)).count() {
+            return score;
+        }
+    }
+
+    let stronger = if score.raw() > 0 { board.turn() } else { !board.turn() };
+    let pawns = (board.piece_bb(Piece::Pawn) & board.color_bb(stronger)).count() as i32;
+    let percent = (OCB_SCALE_BASE + pawns * OCB_SCALE_PER_PAWN).min(100);
+    Score::cp(score.raw() * percent / 100)
 }
 
 /// Evaluate the position.
 ///
-/// Uses NNUE if a model is provided, otherwise HCE fallback.
+/// Uses the endgame eval for clearly won endgames (default thresholds),
+/// otherwise NNUE if a model is provided, otherwise HCE fallback.
 pub fn evaluate(board: &Board, model: Option<&nnue::Model>) -> Score {
-    if let Some(m) = model {
+    if let Some(score) = endgame::probe(board, &EndgameConfig::default()) {
+        return score;
+    }
+    let score = if let Some(m) = model {
         // Use NNUE evaluation
         nnue::evaluate_scratch(&**m, board)
     } else {
         // Fallback to HCE
         hce::evaluate(board)
-    }
+    };
+    scale_opposite_bishops(board, score)
 }
 
 /// Wrapper for material eval that returns Score
@@ -101,6 +330,161 @@ mod tests {
         assert!(score.raw().abs() < 50);
     }
     
+    #[test]
+    fn test_endgame_net_selected_at_threshold() {
+        // Two distinct Arcs so the active net can be told apart
+        let main = nnue::load_embedded_model().expect("embedded NNUE");
+        let endgame_net = nnue::load_embedded_model().expect("embedded NNUE");
+
+        // Six pieces: main net. Rxd5 leaves five and crosses the threshold.
+        let board = Board::from_fen("4k3/p7/8/3p4/8/8/3R4/3QK3 w - - 0 1").unwrap();
+        let mut eval = SearchEvaluator::new(Some(&main), Some(&endgame_net), &board);
+        assert!(!eval.uses_endgame_net());
+
+        let m = crate::uci::parse_move(&board, "d2d5").unwrap();
+        let new_board = board.make_move_new(m);
+        assert!(!eval.update_move(&board, m));
+        eval.refresh(&new_board);
+        assert!(eval.uses_endgame_net());
+
+        // Only one net: always that one
+        let single = SearchEvaluator::new(Some(&main), None, &new_board);
+        assert!(!single.uses_endgame_net());
+    }
+
+    #[test]
+    fn test_opposite_bishops_scale_towards_draw() {
+        // KBPvKB, White's bishop on c1 (dark): c8 is light, d8 dark
+        let opposite = Board::from_fen("2b1k3/8/8/8/P7/8/8/2B1K3 w - - 0 1").unwrap();
+        let same = Board::from_fen("3bk3/8/8/8/P7/8/8/2B1K3 w - - 0 1").unwrap();
+
+        let ocb = SearchEvaluator::new(None, None, &opposite).evaluate(&opposite);
+        let plain = SearchEvaluator::new(None, None, &same).evaluate(&same);
+        assert!(plain.raw() > 50, "{}", plain);
+        assert!(ocb.raw() > 0 && ocb.raw() < plain.raw() / 2, "{} vs {}", ocb, plain);
+        assert_eq!(evaluate(&opposite, None), ocb);
+
+        // One pawn for the stronger side keeps 37%, whoever is to move
+        assert_eq!(scale_opposite_bishops(&opposite, Score::cp(200)), Score::cp(74));
+        let black = Board::from_fen("2b1k3/8/8/8/P7/8/8/2B1K3 b - - 0 1").unwrap();
+        assert_eq!(scale_opposite_bishops(&black, Score::cp(-200)), Score::cp(-74));
+
+        // Same-colored bishops or an extra piece: untouched
+        assert_eq!(scale_opposite_bishops(&same, Score::cp(200)), Score::cp(200));
+        let knight = Board::from_fen("2b1k3/8/8/8/P7/8/8/1NB1K3 w - - 0 1").unwrap();
+        assert_eq!(scale_opposite_bishops(&knight, Score::cp(200)), Score::cp(200));
+    }
+
+    #[test]
+    fn test_fifty_move_scaling() {
+        // A rook up: worth less the longer nothing has happened
+        let board = Board::from_fen("4k3/8/8/8/8/8/PPP5/R3K3 w - - 0 1").unwrap();
+        let mut evaluator = SearchEvaluator::with_kind(EvalKind::Hce, None, None, &board);
+        let fresh = evaluator.evaluate(&board);
+        evaluator.set_halfmove_clock(80);
+        let stale = evaluator.evaluate(&board);
+        assert!(fresh.raw() > 500, "{}", fresh);
+        assert!(stale < fresh, "{} vs {}", stale, fresh);
+        assert_eq!(stale, Score::cp(fresh.raw() * 120 / 200));
+
+        // Mate scores are exact, whatever the clock
+        assert_eq!(scale_fifty_move(Score::mate_in(5), 80), Score::mate_in(5));
+        assert_eq!(scale_fifty_move(Score::cp(-300), 100), Score::cp(-150));
+        assert_eq!(scale_fifty_move(Score::cp(300), 250), Score::cp(0));
+    }
+
+    #[test]
+    fn test_eval_scale() {
+        let model = nnue::load_embedded_model().expect("embedded NNUE");
+        let board = Board::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap();
+        let plain = SearchEvaluator::new(Some(&model), None, &board).evaluate(&board);
+        let scaled = SearchEvaluator::new(Some(&model), None, &board).with_scale(150).evaluate(&board);
+        assert_eq!(scaled, Score::cp(plain.raw() * 150 / 100));
+
+        // Never pushed into mate scores, however large the scale
+        assert_eq!(scale_eval(Score::cp(15_000), 400), Score::cp(SCALED_EVAL_LIMIT));
+        assert!(!scale_eval(Score::cp(-15_000), 400).is_mate_score());
+
+        // The HCE is not an NNUE output: unscaled
+        let hce = SearchEvaluator::new(None, None, &board);
+        assert_eq!(hce.with_scale(150).evaluate(&board), SearchEvaluator::new(None, None, &board).evaluate(&board));
+    }
+
+    #[test]
+    fn test_output_scaling() {
+        let model = nnue::load_embedded_model().expect("embedded NNUE");
+        let board = Board::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap();
+        let plain = SearchEvaluator::new(Some(&model), None, &board).evaluate(&board);
+        let half = [64; 31];
+        let scaled = SearchEvaluator::new(Some(&model), None, &board).with_output_scale(Some(&half)).evaluate(&board);
+        assert_eq!(scaled, Score::cp(plain.raw() * 64 / 128));
+
+        // Off by default, and the default table leaves a full board alone
+        let params = crate::search::SearchParamsTunable::default();
+        assert!(!params.output_scaling);
+        let mut table = SearchEvaluator::new(Some(&model), None, &board).with_output_scale(Some(&params.output_scale));
+        assert_eq!(table.evaluate(&board), plain);
+
+        // Tuning this position's bucket (30 pieces besides the kings) changes it
+        let mut params = params;
+        assert!(params.set("OutputScale30", 96));
+        let mut tuned = SearchEvaluator::new(Some(&model), None, &board).with_output_scale(Some(&params.output_scale));
+        assert_eq!(tuned.evaluate(&board), Score::cp(plain.raw() * 96 / 128));
+    }
+
+    /// Varied positions for the symmetry checks: openings, middlegames with
+    /// castling and en passant rights, pawn structures and endgames
+    const SYMMETRY_POSITIONS: [&str; 14] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w KQ - 0 8",
+        "2r2rk1/1b2qppp/p3pn2/1p6/3P4/P1NB1Q2/1P3PPP/2R2RK1 b - - 3 18",
+        "r4rk1/pp3ppp/2p5/3pP3/3P1Pb1/2P5/P5PP/R1B2RK1 w - d6 0 16",
+        "8/5pk1/6p1/3P3p/1p5P/1P4P1/5PK1/8 w - - 0 40",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "6k1/5ppp/8/8/8/8/5PPP/3R2K1 b - - 0 30",
+        "8/8/4k3/8/2B5/8/3NK3/8 w - - 0 60",
+        "8/8/8/4k3/8/8/8/3QK3 b - - 0 70",
+        "8/8/3k4/8/8/3K4/3P4/8 w - - 0 50",
+        "4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1",
+    ];
+
+    /// Check `eval` scores every position and its mirror alike from the side to move
+    fn assert_symmetric<T: PartialEq + core::fmt::Debug>(name: &str, eval: impl Fn(&Board) -> T) {
+        for fen in SYMMETRY_POSITIONS {
+            let board = Board::from_fen(fen).unwrap();
+            let mirrored = crate::types::mirror_board(&board);
+            assert_eq!(eval(&board), eval(&mirrored), "{name} asymmetric on {fen}");
+        }
+    }
+
+    #[test]
+    fn test_eval_symmetry() {
+        assert_symmetric("hce", hce::evaluate);
+        let config = EndgameConfig::default();
+        assert_symmetric("endgame", |board| endgame::probe(board, &config));
+        assert_symmetric("search evaluator", |board| SearchEvaluator::new(None, None, board).evaluate(board));
+    }
+
+    #[test]
+    fn test_eval_kind_overrides_model() {
+        let model = nnue::load_embedded_model().expect("embedded NNUE");
+        let board = Board::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap();
+        let eval = |kind| SearchEvaluator::with_kind(kind, Some(&model), None, &board).evaluate(&board);
+
+        assert_eq!(eval(EvalKind::Hce), hce::evaluate(&board));
+        assert_eq!(eval(EvalKind::Material), material_eval_wrapper(&board));
+        assert_eq!(eval(EvalKind::Endgame), endgame::evaluate(&board));
+        assert_eq!(eval(EvalKind::Nnue), eval(EvalKind::Auto));
+
+        // Forced HCE is not overridden by the heuristic endgame eval either
+        let won = Board::from_fen("8/8/4k3/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let forced = SearchEvaluator::with_kind(EvalKind::Hce, None, None, &won).evaluate(&won);
+        assert_eq!(forced, hce::evaluate(&won));
+    }
+
     #[test]
     fn test_hce_fallback() {
         // Evaluate without NNUE should use HCE
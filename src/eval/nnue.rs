@@ -1,55 +1,68 @@
 //! NNUE wrapper for HalfKP NNUE with incremental update support.
 //!
 //! Uses ferrum-nnue with Stockfish HalfKP architecture (40960→256×2→32→32→1).
+//!
+//! Debug builds can check every incremental update against a full refresh
+//! (`NnueEvaluator::with_validation`).
 
-use crate::types::{Board, Score, ToNnue, Move, Piece, Color, MoveFlag};
+use crate::types::{castling_rook_square, Board, BoardExt, Score, ToNnue, Move, Piece, Color, MoveFlag};
 use nnue::stockfish::halfkp::{SfHalfKpFullModel, SfHalfKpModel, SfHalfKpState, scale_nn_to_centipawns};
 use binread::BinRead;
-use std::sync::Arc;
-use std::fs::File;
-use std::io::{BufReader, Cursor};
+use alloc::sync::Arc;
+use binread::io::Cursor;
 use movegen::Square;
 
 /// Embedded NNUE network file (compiled into the binary)
 const EMBEDDED_NNUE: &[u8] = include_bytes!("../../network.nnue");
 
+/// Largest difference in raw network output tolerated between an incremental
+/// update and a full refresh (`NnueEvaluator::with_validation`)
+#[cfg(debug_assertions)]
+const VALIDATION_EPSILON: i32 = 1;
+
 /// Global type for shared thread-safe model
 pub type Model = Arc<SfHalfKpModel>;
 
+/// Why a network could not be loaded: an I/O error with `std`, the parse
+/// error itself without
+#[cfg(feature = "std")]
+pub type LoadError = std::io::Error;
+#[cfg(not(feature = "std"))]
+pub type LoadError = binread::Error;
+
 /// Load NNUE model from embedded bytes (no external file needed)
-pub fn load_embedded_model() -> std::io::Result<Model> {
-    let mut cursor = Cursor::new(EMBEDDED_NNUE);
-    
-    match SfHalfKpFullModel::read(&mut cursor) {
-        Ok(full_model) => Ok(Arc::new(full_model.model)),
-        Err(e) => Err(std::io::Error::new(
-            std::io::ErrorKind::InvalidData,
-            format!("Failed to parse embedded NNUE: {:?}", e)
-        )),
-    }
+pub fn load_embedded_model() -> Result<Model, LoadError> {
+    load_model_from_bytes(EMBEDDED_NNUE)
+}
+
+/// Load NNUE model from a network file's contents in memory (e.g. a net
+/// compiled in with `include_bytes!`)
+pub fn load_model_from_bytes(bytes: &[u8]) -> Result<Model, LoadError> {
+    let mut cursor = Cursor::new(bytes);
+    let full_model = SfHalfKpFullModel::read(&mut cursor);
+    #[cfg(feature = "std")]
+    let full_model = full_model.map_err(|e| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Failed to parse NNUE: {:?}", e))
+    });
+    full_model.map(|full_model| Arc::new(full_model.model))
 }
 
 /// Load NNUE model from file (for custom networks)
-pub fn load_model(path: &str) -> std::io::Result<Model> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-    
-    match SfHalfKpFullModel::read(&mut reader) {
-        Ok(full_model) => Ok(Arc::new(full_model.model)),
-        Err(e) => Err(std::io::Error::new(
-            std::io::ErrorKind::InvalidData,
-            format!("Failed to parse NNUE file: {:?}", e)
-        )),
-    }
+#[cfg(feature = "std")]
+pub fn load_model(path: &str) -> Result<Model, LoadError> {
+    load_model_from_bytes(&std::fs::read(path)?)
 }
 
 /// Create a fresh NNUE state from a board position
 pub fn create_state<'m>(model: &'m SfHalfKpModel, board: &Board) -> SfHalfKpState<'m> {
     // Find king positions
-    let white_king_sq = find_king_square(board, Color::White);
-    let black_king_sq = find_king_square(board, Color::Black);
-    
-    let mut state = model.new_state(white_king_sq.to_nnue(), black_king_sq.to_nnue());
+    let kings = [find_king_square(board, Color::White), find_king_square(board, Color::Black)];
+    create_state_with_kings(model, board, kings)
+}
+
+/// `create_state` with the king squares (`[white, black]`) already known
+pub fn create_state_with_kings<'m>(model: &'m SfHalfKpModel, board: &Board, kings: [Square; 2]) -> SfHalfKpState<'m> {
+    let mut state = model.new_state(kings[0].to_nnue(), kings[1].to_nnue());
 
     // Add all NON-KING pieces (HalfKP does not include kings as features)
     for &piece in &[Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
@@ -70,6 +83,20 @@ pub fn create_state<'m>(model: &'m SfHalfKpModel, board: &Board) -> SfHalfKpStat
     state
 }
 
+/// Color and landing square of the king if `mv` is a king move (castling
+/// included: the king ends on the G or C file whatever the move's encoding)
+fn king_destination(board: &Board, mv: Move) -> Option<(Color, Square)> {
+    let Some((Piece::King, color)) = board.piece_at(mv.from()) else {
+        return None;
+    };
+    let file = match mv.flag() {
+        MoveFlag::KingCastle => movegen::File::G,
+        MoveFlag::QueenCastle => movegen::File::C,
+        _ => return Some((color, mv.to())),
+    };
+    Some((color, Square::from_file_rank(file, mv.from().rank())))
+}
+
 /// Find king square for a color
 fn find_king_square(board: &Board, color: Color) -> Square {
     let king_bb = board.piece_bb(Piece::King) & board.color_bb(color);
@@ -81,12 +108,28 @@ fn find_king_square(board: &Board, color: Color) -> Square {
     Square::E1
 }
 
+/// Output scale factors (in 1/128ths) indexed by non-king piece count (0-30+),
+/// see `SearchParamsTunable::output_scale`
+pub type OutputScale = [i32; 31];
+
+/// Apply a piece-count output scale to a centipawn score
+#[inline]
+pub fn scale_output(cp: i32, piece_count: u32, table: &OutputScale) -> i32 {
+    let idx = (piece_count as usize).min(table.len() - 1);
+    cp * table[idx] / 128
+}
+
+/// Number of non-king pieces on the board (output scaling bucket)
+#[inline]
+pub fn output_bucket(board: &Board) -> u32 {
+    board.occupied().count() - 2
+}
+
 /// Evaluate using a pre-built state (fast - just runs network)
 #[inline]
 pub fn evaluate_state(state: &mut SfHalfKpState<'_>, side_to_move: Color) -> Score {
     let raw = state.activate(side_to_move.to_nnue());
-    let cp = scale_nn_to_centipawns(raw[0]);
-    Score::cp(cp)
+    Score::cp(scale_nn_to_centipawns(raw[0]))
 }
 
 /// Evaluate from scratch (creates new state)
@@ -110,7 +153,7 @@ pub fn update_state_for_move(
         Some((p, c)) => (p, c),
         None => return false,
     };
-    let captured = board.piece_at(to).map(|(p, _)| p);
+    let captured = board.piece_on(to);
 
     // If king moves, we need full refresh (king position changes all feature indices)
     if moving_piece == Piece::King {
@@ -160,31 +203,25 @@ pub fn update_state_for_move(
     state.add(nnue::Color::White, final_piece, nnue_color, to_sq);
     state.add(nnue::Color::Black, final_piece, nnue_color, to_sq);
 
-    // Handle castling: rook also moves (king move was handled above with full refresh)
-    let mv_flag = mv.flag();
-    let is_castling = mv_flag == MoveFlag::KingCastle || mv_flag == MoveFlag::QueenCastle;
-    
-    if is_castling {
-        let nnue_rook_color = moving_color.to_nnue();
-        let (rook_from, rook_to) = if mv_flag == MoveFlag::KingCastle {
-            // King-side castling
-            let rank = from.rank();
-            (
-                Square::from_file_rank(movegen::File::H, rank),
-                Square::from_file_rank(movegen::File::F, rank)
-            )
-        } else {
-            // Queen-side castling
-            let rank = from.rank();
-            (
-                Square::from_file_rank(movegen::File::A, rank),
-                Square::from_file_rank(movegen::File::D, rank)
-            )
+    // Handle castling: rook also moves (king move was handled above with full refresh).
+    // The rook comes from its actual square (Chess960), and lands next to the king.
+    let kingside = match mv.flag() {
+        MoveFlag::KingCastle => Some(true),
+        MoveFlag::QueenCastle => Some(false),
+        _ => None,
+    };
+
+    if let Some(kingside) = kingside {
+        let Some(rook_from) = castling_rook_square(board, moving_color, kingside) else {
+            return false;
         };
-        
+        let rook_file = if kingside { movegen::File::F } else { movegen::File::D };
+        let rook_to = Square::from_file_rank(rook_file, from.rank());
+        let nnue_rook_color = moving_color.to_nnue();
+
         let rook_from_nnue = rook_from.to_nnue();
         let rook_to_nnue = rook_to.to_nnue();
-        
+
         state.sub(nnue::Color::White, nnue::Piece::Rook, nnue_rook_color, rook_from_nnue);
         state.sub(nnue::Color::Black, nnue::Piece::Rook, nnue_rook_color, rook_from_nnue);
         state.add(nnue::Color::White, nnue::Piece::Rook, nnue_rook_color, rook_to_nnue);
@@ -206,33 +243,96 @@ pub fn refresh_state<'m>(state: &mut SfHalfKpState<'m>, model: &'m SfHalfKpModel
 pub struct NnueEvaluator<'m> {
     model: &'m SfHalfKpModel,
     state: SfHalfKpState<'m>,
+    /// King squares of the position the state is for (`[white, black]`),
+    /// moved along with king moves so a refresh doesn't scan for them
+    kings: [Square; 2],
+    /// Check every incremental update against a full refresh
+    #[cfg(debug_assertions)]
+    validate: bool,
 }
 
 impl<'m> NnueEvaluator<'m> {
     /// Create a new evaluator for a position
     pub fn new(model: &'m SfHalfKpModel, board: &Board) -> Self {
+        let kings = [find_king_square(board, Color::White), find_king_square(board, Color::Black)];
         Self {
             model,
-            state: create_state(model, board),
+            state: create_state_with_kings(model, board, kings),
+            kings,
+            #[cfg(debug_assertions)]
+            validate: false,
         }
     }
 
+    /// Panic as soon as an incremental update disagrees with a full refresh
+    /// of the position after the move (debug builds only; clones inherit it)
+    #[cfg(debug_assertions)]
+    pub fn with_validation(mut self) -> Self {
+        self.validate = true;
+        self
+    }
+
     /// Evaluate current position
     #[inline]
-    pub fn evaluate(&mut self, side_to_move: Color) -> Score {
-        evaluate_state(&mut self.state, side_to_move)
+    pub fn evaluate(&mut self, board: &Board) -> Score {
+        evaluate_state(&mut self.state, board.turn())
     }
 
-    /// Update for a move, returns false if refresh needed
+    /// Update for a move, returns false if refresh needed.
+    ///
+    /// King moves always need one; they only move the cached king square.
     #[inline]
     pub fn update_move(&mut self, board: &Board, mv: Move) -> bool {
-        update_state_for_move(&mut self.state, board, mv)
+        if let Some((color, to)) = king_destination(board, mv) {
+            self.kings[color.index()] = to;
+            return false;
+        }
+        let updated = update_state_for_move(&mut self.state, board, mv);
+        #[cfg(debug_assertions)]
+        if updated && self.validate {
+            self.validate_against_refresh(&board.make_move_new(mv), mv);
+        }
+        updated
+    }
+
+    /// Compare the network output of the incrementally updated state with a
+    /// state built from scratch for `after`, from both sides' point of view
+    #[cfg(debug_assertions)]
+    fn validate_against_refresh(&self, after: &Board, mv: Move) {
+        let mut incremental = self.state.clone();
+        let mut fresh = create_state(self.model, after);
+        for side in [Color::White, Color::Black] {
+            let got = incremental.activate(side.to_nnue())[0];
+            let want = fresh.activate(side.to_nnue())[0];
+            assert!(
+                (got - want).abs() <= VALIDATION_EPSILON,
+                "NNUE update for {} diverged from a refresh: {} vs {} ({})",
+                mv.to_uci(), got, want, after.to_fen()
+            );
+        }
     }
 
-    /// Refresh state for a new position
+    /// Refresh state for the position after the last `update_move`
     #[inline]
     pub fn refresh(&mut self, board: &Board) {
-        self.state = create_state(self.model, board);
+        debug_assert!(
+            self.kings == [board.king_square(Color::White), board.king_square(Color::Black)],
+            "cached king squares out of date for {}",
+            board.to_fen()
+        );
+        self.state = create_state_with_kings(self.model, board, self.kings);
+    }
+
+    /// Square of `color`'s king in the position the state is for
+    #[inline]
+    pub fn king_square(&self, color: Color) -> Square {
+        self.kings[color.index()]
+    }
+
+    /// Network this evaluator runs
+    #[inline]
+    pub fn model(&self) -> &'m SfHalfKpModel {
+        self.model
     }
 
     /// Clone the current state (for search recursion)
@@ -253,6 +353,127 @@ impl<'m> Clone for NnueEvaluator<'m> {
         Self {
             model: self.model,
             state: self.state.clone(),
+            kings: self.kings,
+            #[cfg(debug_assertions)]
+            validate: self.validate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_model_from_bytes() {
+        let board = Board::default();
+        let from_bytes = load_model_from_bytes(EMBEDDED_NNUE).expect("embedded NNUE");
+        let expected = evaluate_scratch(&from_bytes, &board);
+        assert_eq!(evaluate_scratch(&load_embedded_model().unwrap(), &board), expected);
+
+        // The same net read back from a file
+        #[cfg(feature = "std")]
+        {
+            let path = std::env::temp_dir().join(format!("porcupine-test-{}.nnue", std::process::id()));
+            std::fs::write(&path, EMBEDDED_NNUE).unwrap();
+            let from_file = load_model(path.to_str().unwrap());
+            std::fs::remove_file(&path).ok();
+            assert_eq!(evaluate_scratch(&from_file.unwrap(), &board), expected);
+        }
+
+        // Truncated data is an error, not a panic
+        let err = load_model_from_bytes(&EMBEDDED_NNUE[..64]).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_incremental_updates_validated() {
+        let model = load_embedded_model().expect("embedded NNUE");
+        let mut board = Board::from_fen("r3k2r/8/8/3pP3/8/8/1p4P1/R3K2R w KQkq d6 0 1").unwrap();
+        let mut evaluator = NnueEvaluator::new(&model, &board).with_validation();
+
+        // En passant, capturing underpromotion, castling both ways, a double
+        // push, captures, a capturing promotion and a king capture
+        let line = [
+            "e5d6", "b2a1n", "e1g1", "e8c8", "d6d7", "d8d7", "g2g4", "a1c2", "g4g5",
+            "c2e3", "g5g6", "e3f1", "g6g7", "c8b8", "g7h8q", "b8b7", "g1f1",
+        ];
+        for uci in line {
+            let m = crate::uci::parse_move(&board, uci).unwrap_or_else(|| panic!("{} is illegal", uci));
+            let next = board.make_move_new(m);
+            if !evaluator.update_move(&board, m) {
+                evaluator.refresh(&next);
+            }
+            assert_eq!(evaluator.evaluate(&next), evaluate_scratch(&model, &next), "{}", uci);
+            board = next;
+        }
+    }
+
+    #[test]
+    fn test_cached_king_squares_follow_moves() {
+        let model = load_embedded_model().expect("embedded NNUE");
+        let mut board = Board::from_fen("r3k2r/pppq1ppp/8/8/8/8/PPPQ1PPP/R3K2R w KQkq - 0 1").unwrap();
+        let mut evaluator = NnueEvaluator::new(&model, &board);
+
+        // Castling both ways, plain king moves and non-king moves in between
+        for uci in ["e1c1", "e8g8", "d2d7", "g8h8", "c1b1", "a8d8", "d7d8", "h8g8"] {
+            let m = crate::uci::parse_move(&board, uci).unwrap_or_else(|| panic!("{} is illegal", uci));
+            let next = board.make_move_new(m);
+            if !evaluator.update_move(&board, m) {
+                evaluator.refresh(&next);
+            }
+            for color in [Color::White, Color::Black] {
+                assert_eq!(evaluator.king_square(color), next.king_square(color), "{}", uci);
+            }
+            assert_eq!(evaluator.evaluate(&next), evaluate_scratch(&model, &next), "{}", uci);
+            board = next;
+        }
+        assert_eq!(evaluator.king_square(Color::White), Square::B1);
+        assert_eq!(evaluator.king_square(Color::Black), Square::G8);
+    }
+
+    #[test]
+    fn test_output_scaling_low_material() {
+        let model = load_embedded_model().expect("embedded NNUE");
+        let board = Board::from_fen("8/8/4k3/3p4/8/3PK3/8/8 w - - 0 1").unwrap();
+
+        let table = crate::search::SearchParamsTunable::default().output_scale;
+        let unscaled = evaluate_scratch(&model, &board).raw();
+        let scaled = scale_output(unscaled, output_bucket(&board), &table);
+
+        // Two pawns left: dampened toward zero, never flips sign
+        assert!(scaled.abs() <= unscaled.abs());
+        assert!(scaled.signum() == unscaled.signum() || scaled == 0);
+        assert_eq!(scale_output(1000, 2, &table), 1000 * table[2] / 128);
+
+        // Full board is untouched
+        assert_eq!(scale_output(unscaled, 30, &table), unscaled);
+    }
+
+    #[test]
+    fn test_chess960_castling_matches_refresh() {
+        let model = load_embedded_model().expect("embedded NNUE");
+        // Chess960 start (bishops and queen shuffled), kingside knights gone
+        let board = Board::from_fen("rbbqk2r/pppppppp/8/8/8/8/PPPPPPPP/RBBQK2R w KQkq - 0 1").unwrap();
+        let castle = board
+            .generate_moves()
+            .iter()
+            .find(|m| m.flag() == MoveFlag::KingCastle)
+            .expect("O-O is legal");
+        let after = board.make_move_new(castle);
+
+        // Incremental where possible, refreshed where the update asks for it
+        let mut evaluator = NnueEvaluator::new(&model, &board);
+        if !evaluator.update_move(&board, castle) {
+            evaluator.refresh(&after);
         }
+        assert_eq!(evaluator.evaluate(&after), NnueEvaluator::new(&model, &after).evaluate(&after));
+
+        // A rook move right after castling stays incremental
+        let rook_move = crate::uci::parse_move(&after, "f1e1").unwrap();
+        let next = after.make_move_new(rook_move);
+        assert!(evaluator.update_move(&after, rook_move));
+        assert_eq!(evaluator.evaluate(&next), NnueEvaluator::new(&model, &next).evaluate(&next));
     }
 }
@@ -1,6 +1,20 @@
+//! Without the `std` feature the search core builds `no_std` (with `alloc`).
+
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+extern crate alloc;
+
 pub mod types;
 pub mod engine;
 pub mod search;
 pub mod eval;
+#[cfg(feature = "std")]
 pub mod uci;
+#[cfg(feature = "std")]
 pub mod book;
+#[cfg(feature = "std")]
+pub mod bench;
+#[cfg(feature = "std")]
+pub mod selfplay;
+#[cfg(feature = "std")]
+pub mod analyze;
@@ -0,0 +1,174 @@
+//! Aspiration windows for the root search.
+//!
+//! Each iteration starts with a narrow window around the previous score. On a
+//! fail only the failing bound moves (by a growing delta), the other side is
+//! pulled towards the failed score so the re-search stays narrow. Once the
+//! delta gets large, or the failed score is a mate score, the window is
+//! opened fully.
+
+use crate::types::Score;
+
+/// Initial half-width of the window (centipawns)
+pub const INITIAL_WINDOW: i32 = 25;
+
+/// Past this delta the window is opened to (-inf, +inf)
+const MAX_DELTA: i32 = 500;
+
+/// Outcome of a root search against the current window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowResult {
+    /// Score inside the window: exact, accept it
+    Exact,
+    /// Score at or below alpha
+    FailLow,
+    /// Score at or above beta
+    FailHigh,
+}
+
+/// Aspiration window state for one iteration
+#[derive(Debug, Clone, Copy)]
+pub struct AspirationWindow {
+    pub alpha: Score,
+    pub beta: Score,
+    delta: i32,
+    /// Number of fail-lows so far this iteration
+    pub fail_lows: u32,
+    /// Number of fail-highs so far this iteration
+    pub fail_highs: u32,
+}
+
+impl AspirationWindow {
+    /// Window around the previous iteration's score (full window when there is
+    /// no usable previous score)
+    pub fn new(prev_score: Score, depth: i32) -> Self {
+        Self::with_width(prev_score, depth, INITIAL_WINDOW)
+    }
+
+    /// Same as `new` with a custom initial half-width
+    pub fn with_width(prev_score: Score, depth: i32, width: i32) -> Self {
+        let (alpha, beta) = if depth > 1 && !prev_score.is_mate_score() {
+            (prev_score.sub_saturating(width), prev_score.add_saturating(width))
+        } else {
+            (Score::neg_infinity(), Score::infinity())
+        };
+
+        Self {
+            alpha,
+            beta,
+            delta: width,
+            fail_lows: 0,
+            fail_highs: 0,
+        }
+    }
+
+    /// Classify `score` against the window and widen it on a fail
+    pub fn update(&mut self, score: Score) -> WindowResult {
+        let result = if score <= self.alpha && self.alpha > Score::neg_infinity() {
+            // Fail-low: pull beta down, push alpha below the score
+            self.fail_lows += 1;
+            self.beta = Score::cp((self.alpha.raw() + self.beta.raw()) / 2);
+            self.alpha = score.sub_saturating(self.delta);
+            WindowResult::FailLow
+        } else if score >= self.beta && self.beta < Score::infinity() {
+            // Fail-high: only beta moves
+            self.fail_highs += 1;
+            self.beta = score.add_saturating(self.delta);
+            WindowResult::FailHigh
+        } else {
+            return WindowResult::Exact;
+        };
+
+        // A mate score won't be bracketed by a centipawn delta
+        self.delta += self.delta / 2;
+        if self.delta > MAX_DELTA || score.is_mate_score() {
+            self.alpha = Score::neg_infinity();
+            self.beta = Score::infinity();
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_swing_widens_then_exact() {
+        let mut window = AspirationWindow::new(Score::cp(10), 5);
+        assert_eq!(window.alpha, Score::cp(-15));
+        assert_eq!(window.beta, Score::cp(35));
+
+        // Score jumps to +300: fail high until the window contains it
+        let swing = Score::cp(300);
+        let mut widenings = 0;
+        while window.update(swing) == WindowResult::FailHigh {
+            widenings += 1;
+            // Only beta moves on a fail-high
+            assert_eq!(window.alpha, Score::cp(-15));
+        }
+        assert!(widenings >= 1);
+        assert_eq!(window.fail_highs, widenings);
+        assert!(window.alpha < swing && swing < window.beta);
+        assert_eq!(window.update(swing), WindowResult::Exact);
+    }
+
+    #[test]
+    fn test_fail_low_opens_fully() {
+        let mut window = AspirationWindow::new(Score::cp(0), 5);
+
+        // One drop: beta is pulled down, alpha goes below the score
+        let drop = Score::cp(-200);
+        assert_eq!(window.update(drop), WindowResult::FailLow);
+        assert!(window.beta < Score::cp(INITIAL_WINDOW));
+        assert!(window.alpha < drop);
+
+        // A score that keeps collapsing eventually opens the window fully
+        let mut fails = 1;
+        while window.alpha > Score::neg_infinity() {
+            let score = window.alpha - Score::cp(1);
+            assert_eq!(window.update(score), WindowResult::FailLow);
+            fails += 1;
+            assert!(fails < 20);
+        }
+        assert_eq!(window.fail_lows, fails);
+        assert_eq!(window.beta, Score::infinity());
+    }
+
+    #[test]
+    fn test_score_inside_window_single_pass() {
+        let mut window = AspirationWindow::new(Score::cp(40), 6);
+        assert_eq!(window.update(Score::cp(52)), WindowResult::Exact);
+        assert_eq!(window.fail_lows + window.fail_highs, 0);
+        assert_eq!(window.alpha, Score::cp(15));
+        assert_eq!(window.beta, Score::cp(65));
+    }
+
+    #[test]
+    fn test_fail_low_widens_until_exact() {
+        let mut window = AspirationWindow::new(Score::cp(30), 6);
+        let drop = Score::cp(-90);
+
+        let mut passes = 1;
+        while window.update(drop) == WindowResult::FailLow {
+            passes += 1;
+            assert!(passes < 10);
+        }
+        assert!(window.fail_lows >= 1);
+        // Resolved without having to open the window fully
+        assert!(window.alpha > Score::neg_infinity());
+        assert!(window.alpha < drop && drop < window.beta);
+    }
+
+    #[test]
+    fn test_mate_scores_use_full_window() {
+        let window = AspirationWindow::new(Score::mate_in(5), 6);
+        assert_eq!(window.alpha, Score::neg_infinity());
+        assert_eq!(window.beta, Score::infinity());
+
+        // A fail that finds a mate opens the window instead of creeping up
+        let mut window = AspirationWindow::new(Score::cp(20), 6);
+        assert_eq!(window.update(Score::mate_in(3)), WindowResult::FailHigh);
+        assert_eq!(window.beta, Score::infinity());
+        assert_eq!(window.update(Score::mate_in(3)), WindowResult::Exact);
+    }
+}
@@ -0,0 +1,125 @@
+//! Quiet checking move generation.
+//!
+//! Produces the non-capturing moves that give check, for quiescence search
+//! check extensions and mate search, and answers whether a single move gives
+//! check. Direct and discovered checks are found
+//! from the attack tables against the occupancy after the move, so no
+//! position has to be made per candidate.
+
+use smallvec::SmallVec;
+
+use crate::types::{Bitboard, Board, Move, MoveFlag, Piece};
+use movegen::attacks::{bishop_attacks, knight_attacks, pawn_attacks, rook_attacks};
+
+/// Checking moves (stack-allocated, rarely more than a handful)
+pub type CheckList = SmallVec<[Move; 32]>;
+
+/// Non-capturing moves (including quiet promotions and castling) that give check.
+pub fn generate_checks(board: &Board) -> CheckList {
+    board
+        .generate_moves()
+        .iter()
+        .filter(|&m| !m.is_capture() && gives_check(board, m))
+        .collect()
+}
+
+/// Whether the legal move `m` gives check, without making it.
+pub fn gives_check(board: &Board, m: Move) -> bool {
+    let us = board.turn();
+    let king = board.king_square(!us);
+
+    match m.flag() {
+        // The rook delivers these and en passant removes a second piece; rare
+        // enough to just play them
+        MoveFlag::KingCastle | MoveFlag::QueenCastle | MoveFlag::EnPassant => {
+            board.make_move_new(m).in_check()
+        }
+        flag => {
+            let from = Bitboard::from_square(m.from());
+            let to = Bitboard::from_square(m.to());
+            let occupied = board.occupied() ^ from | to;
+            let Some((moved, _)) = board.piece_at(m.from()) else { return false };
+            let piece = flag.promotion_piece().unwrap_or(moved);
+
+            // Direct check from the destination square
+            let target = Bitboard::from_square(king);
+            let direct = match piece {
+                Piece::Pawn => (pawn_attacks(us, m.to()) & target).any(),
+                Piece::Knight => (knight_attacks(m.to()) & target).any(),
+                Piece::Bishop => (bishop_attacks(m.to(), occupied) & target).any(),
+                Piece::Rook => (rook_attacks(m.to(), occupied) & target).any(),
+                Piece::Queen => {
+                    ((bishop_attacks(m.to(), occupied) | rook_attacks(m.to(), occupied)) & target).any()
+                }
+                Piece::King => false,
+            };
+
+            // Discovered check: a slider behind the vacated square now sees the king
+            let ours = board.color_bb(us);
+            let ours = ours ^ (ours & from);
+            let queens = board.piece_bb(Piece::Queen);
+            let diagonal = ours & (board.piece_bb(Piece::Bishop) | queens);
+            let straight = ours & (board.piece_bb(Piece::Rook) | queens);
+            let discovered =
+                (bishop_attacks(king, occupied) & diagonal) | (rook_attacks(king, occupied) & straight);
+
+            direct || discovered.any()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uci_sorted(moves: &[Move]) -> Vec<String> {
+        let mut names: Vec<String> = moves.iter().map(|m| m.to_uci()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_quiet_checks_listed_exactly() {
+        // Knight moves off the e-file discover the rook, Bb5 and f7 check directly
+        let board = Board::from_fen("4k3/8/5P2/8/4N3/3B4/K7/4R3 w - - 0 1").unwrap();
+        let checks = generate_checks(&board);
+        assert_eq!(
+            uci_sorted(&checks),
+            ["d3b5", "e4c3", "e4c5", "e4d2", "e4d6", "e4f2", "e4g3", "e4g5", "f6f7"]
+        );
+    }
+
+    #[test]
+    fn test_checks_agree_with_make_move() {
+        for fen in [
+            "r3k2r/pppq1ppp/2n2n2/3pp3/1b1PP1b1/2N2N2/PPPQBPPP/R3K2R w KQkq - 0 1",
+            "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+            "3k4/1P6/8/8/8/8/6B1/4K3 w - - 0 1",
+            "4k3/8/8/8/2b5/3n4/1q6/4K3 b - - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let expected: Vec<Move> = board
+                .generate_moves()
+                .iter()
+                .filter(|m| !m.is_capture() && board.make_move_new(*m).in_check())
+                .collect();
+            assert_eq!(uci_sorted(&generate_checks(&board)), uci_sorted(&expected), "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_gives_check_matches_make_move() {
+        // Captures, en passant, promotions and castling included
+        for fen in [
+            "r3k2r/pppq1ppp/2n2n2/3pp3/1b1PP1b1/2N2N2/PPPQBPPP/R3K2R w KQkq - 0 1",
+            "4k3/8/8/2pP4/8/8/8/B3K3 w - c6 0 1",
+            "1r1k4/2P5/8/8/8/8/8/4K3 w - - 0 1",
+            "r2k3r/8/8/4N3/8/8/8/4K2R w K - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            for m in board.generate_moves().iter() {
+                assert_eq!(gives_check(&board, m), board.make_move_new(m).in_check(), "{fen} {}", m.to_uci());
+            }
+        }
+    }
+}
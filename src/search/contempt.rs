@@ -0,0 +1,170 @@
+//! Contempt for draw scores.
+//!
+//! Contempt makes the engine avoid draws when it expects to be better and seek
+//! them when it expects to be worse. The offset scales with remaining material:
+//! with a full board a draw is usually avoidable, while in sparse endgames draws
+//! are often forced and shouldn't be penalized much.
+//!
+//! Draw kinds get their own extra contempt on top: shuffling into a repetition
+//! is avoided more readily than a draw the material on the board forces.
+//!
+//! In endgames a small dynamic contempt follows the winning attempts: the side
+//! with the better passed pawns and the more active king scores a draw a bit
+//! lower, whatever the window, so it keeps playing instead of repeating.
+//!
+//! The `Contempt` UCI option comes on top of all that, from the engine's point
+//! of view rather than the window's (`Searcher::contempt_draw`): a draw costs
+//! the side to move at the root that many centipawns, whoever reaches it.
+
+use crate::types::{Board, Score};
+use crate::eval::endgame::passed_pawn_bonus;
+use crate::eval::hce::{calculate_phase, center_distance, EvalInfo};
+
+/// Contempt with all material on the board (centipawns)
+pub const CONTEMPT: i32 = 10;
+
+/// Largest dynamic endgame contempt (centipawns)
+pub const DYNAMIC_CONTEMPT_MAX: i32 = 20;
+
+/// Dynamic contempt per centipawn of passed pawn advantage, in percent
+const PASSER_WEIGHT: i32 = 25;
+
+/// Dynamic contempt per step the enemy king is further from the center
+const KING_ACTIVITY_WEIGHT: i32 = 4;
+
+/// How a drawn position came about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawKind {
+    /// Position repeated in the game or search path
+    Repetition,
+    /// Fifty moves without a capture or pawn move
+    FiftyMove,
+    /// Side to move has no legal moves and is not in check
+    Stalemate,
+}
+
+impl DrawKind {
+    /// Extra contempt (centipawns) added on top of the scaled contempt
+    #[inline]
+    pub const fn extra_contempt(self) -> i32 {
+        match self {
+            DrawKind::Repetition => 8,
+            DrawKind::FiftyMove => 4,
+            DrawKind::Stalemate => 0,
+        }
+    }
+}
+
+/// Contempt scaled by game phase (full at the opening, zero with bare kings)
+#[inline]
+pub fn scaled_contempt(board: &Board) -> i32 {
+    let phase = calculate_phase(board);
+    CONTEMPT * (256 - phase) / 256
+}
+
+/// Winning attempts of the side to move over the opponent in an endgame:
+/// positive when it has the better passers and the more central king.
+///
+/// Zero until the phase is past the middle, then phased in and capped at
+/// `DYNAMIC_CONTEMPT_MAX` either way.
+pub fn dynamic_contempt(board: &Board) -> i32 {
+    let phase = calculate_phase(board);
+    if phase <= 128 {
+        return 0;
+    }
+
+    let us = board.turn();
+    let them = !us;
+    let info = EvalInfo::new(board);
+    let passers = passed_pawn_bonus(board, &info, us) - passed_pawn_bonus(board, &info, them);
+    let king = center_distance(board.king_square(them)) - center_distance(board.king_square(us));
+    let attempts = passers * PASSER_WEIGHT / 100 + king * KING_ACTIVITY_WEIGHT;
+
+    (attempts * (phase - 128) / 128).clamp(-DYNAMIC_CONTEMPT_MAX, DYNAMIC_CONTEMPT_MAX)
+}
+
+/// Draw score for the side to move given the current search window.
+///
+/// If alpha is above the contempt (we expect to be winning), the draw is
+/// penalized; if beta is below it (we expect to be losing), it is rewarded.
+/// The dynamic endgame contempt is applied on top, except to stalemates.
+#[inline]
+pub fn draw_score(kind: DrawKind, board: &Board, alpha: Score, beta: Score) -> Score {
+    let contempt = scaled_contempt(board) + kind.extra_contempt();
+    // A stalemate is not a draw anyone chose to play for
+    let dynamic = if kind == DrawKind::Stalemate { 0 } else { dynamic_contempt(board) };
+
+    if alpha.raw() > contempt {
+        Score::cp(-contempt - dynamic)
+    } else if beta.raw() < -contempt {
+        Score::cp(contempt - dynamic)
+    } else {
+        Score::cp(-dynamic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contempt_scales_with_material() {
+        let start = Board::default();
+        let sparse = Board::from_fen("8/5k2/8/3p4/3P4/8/5K2/8 w - - 0 1").unwrap();
+
+        assert_eq!(scaled_contempt(&start), CONTEMPT);
+        assert!(scaled_contempt(&start) > scaled_contempt(&sparse));
+
+        // Winning side avoids the draw less in the sparse position
+        let alpha = Score::cp(200);
+        let beta = Score::cp(300);
+        assert!(
+            draw_score(DrawKind::Stalemate, &start, alpha, beta)
+                < draw_score(DrawKind::Stalemate, &sparse, alpha, beta)
+        );
+    }
+
+    #[test]
+    fn test_passed_pawn_declines_repetition() {
+        // White has the passer and the centralized king. The repetition is
+        // scored at the node after White's move, with Black to move.
+        let black_to_move = Board::from_fen("8/8/1k6/8/3KP3/8/8/8 b - - 0 60").unwrap();
+        let white_to_move = Board::from_fen("8/8/1k6/8/3KP3/8/8/8 w - - 0 60").unwrap();
+        let (alpha, beta) = (Score::cp(-5), Score::cp(5));
+
+        // Without dynamic contempt an even window scores the repetition 0:
+        // White would accept it. Now it is worth less than an even move.
+        let for_white = -draw_score(DrawKind::Repetition, &black_to_move, alpha, beta);
+        assert!(for_white < Score::draw(), "{for_white}");
+        assert!(draw_score(DrawKind::Repetition, &white_to_move, alpha, beta) < Score::draw());
+        assert!(dynamic_contempt(&white_to_move) <= DYNAMIC_CONTEMPT_MAX);
+
+        // Symmetric pawns and kings: no winning attempts either way
+        let even = Board::from_fen("8/4k3/8/3p4/3P4/8/4K3/8 w - - 0 60").unwrap();
+        assert_eq!(dynamic_contempt(&even), 0);
+        assert_eq!(draw_score(DrawKind::Repetition, &even, alpha, beta), Score::draw());
+
+        // Middlegames are left to the static contempt
+        assert_eq!(dynamic_contempt(&Board::default()), 0);
+    }
+
+    #[test]
+    fn test_draw_kinds_distinct() {
+        let board = Board::default();
+        let alpha = Score::cp(200);
+        let beta = Score::cp(300);
+
+        let rep = draw_score(DrawKind::Repetition, &board, alpha, beta);
+        let fifty = draw_score(DrawKind::FiftyMove, &board, alpha, beta);
+        let stale = draw_score(DrawKind::Stalemate, &board, alpha, beta);
+
+        assert_eq!(rep, Score::cp(-(CONTEMPT + DrawKind::Repetition.extra_contempt())));
+        assert_eq!(fifty, Score::cp(-(CONTEMPT + DrawKind::FiftyMove.extra_contempt())));
+        assert!(rep < fifty && fifty < stale);
+
+        // Losing side seeks the repetition the most
+        let (alpha, beta) = (Score::cp(-300), Score::cp(-200));
+        assert!(draw_score(DrawKind::Repetition, &board, alpha, beta)
+            > draw_score(DrawKind::FiftyMove, &board, alpha, beta));
+    }
+}
@@ -1,13 +1,14 @@
 //! Correction history for evaluation adjustment.
 //!
 //! Tracks the difference between static evaluation and search scores
-//! for different pawn structures. Uses this to correct future evaluations.
+//! for different pawn structures and material configurations. Uses this to
+//! correct future evaluations before they drive pruning decisions.
 //!
 //! When static eval is consistently higher than search score for a pawn structure,
 //! the correction becomes negative (reduces eval). When static eval is consistently
 //! lower, the correction becomes positive (increases eval).
 
-use crate::types::Color;
+use crate::types::{Board, Color, Piece};
 
 /// Size of the correction history table (power of 2 for fast modulo)
 const CORRECTION_SIZE: usize = 16384;
@@ -15,13 +16,30 @@ const CORRECTION_SIZE: usize = 16384;
 /// Maximum correction value (prevents overcorrection)
 const CORRECTION_MAX: i32 = 1024;
 
+/// Material key: piece counts per color and type packed into a hash.
+///
+/// Positions with the same material (e.g. all R+P vs R endings) share a slot.
+#[inline]
+pub fn material_key(board: &Board) -> u64 {
+    let mut key = 0u64;
+    for &piece in &[Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+        for &color in &[Color::White, Color::Black] {
+            let count = (board.piece_bb(piece) & board.color_bb(color)).count() as u64;
+            key = (key << 4) | count.min(15);
+        }
+    }
+    // Spread the packed counts over the table
+    key.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32
+}
+
 /// Correction history table.
 ///
 /// Indexed by [color][pawn_hash % SIZE] to store correction values for
-/// similar pawn structures.
+/// similar pawn structures, plus a second table keyed by material.
 #[derive(Clone)]
 pub struct CorrectionHistoryTable {
     table: [[i16; CORRECTION_SIZE]; 2],
+    material: [[i16; CORRECTION_SIZE]; 2],
 }
 
 impl CorrectionHistoryTable {
@@ -29,12 +47,45 @@ impl CorrectionHistoryTable {
     pub fn new() -> Self {
         Self {
             table: [[0; CORRECTION_SIZE]; 2],
+            material: [[0; CORRECTION_SIZE]; 2],
         }
     }
 
     /// Clear all correction values.
     pub fn clear(&mut self) {
         self.table = [[0; CORRECTION_SIZE]; 2];
+        self.material = [[0; CORRECTION_SIZE]; 2];
+    }
+
+    /// Combined correction (centipawns) to add to a static eval.
+    ///
+    /// The pawn table carries full weight, the coarser material table half.
+    #[inline]
+    pub fn correction(&self, color: Color, pawn_hash: u64, material_key: u64) -> i32 {
+        let pawn = self.get(color, pawn_hash);
+        let material = self.get_material(color, material_key);
+        (pawn + material / 2) / 4
+    }
+
+    /// Update both tables from one node's result.
+    #[inline]
+    pub fn update_all(&mut self, color: Color, pawn_hash: u64, material_key: u64, depth: i32, diff: i32) {
+        self.update(color, pawn_hash, depth, diff);
+        self.update_material(color, material_key, depth, diff);
+    }
+
+    /// Get the material-table correction for a material key.
+    #[inline]
+    pub fn get_material(&self, color: Color, material_key: u64) -> i32 {
+        let idx = (material_key as usize) % CORRECTION_SIZE;
+        i32::from(self.material[color.index()][idx])
+    }
+
+    /// Update the material-table correction (same gravity formula as the pawn table).
+    #[inline]
+    pub fn update_material(&mut self, color: Color, material_key: u64, depth: i32, diff: i32) {
+        let idx = (material_key as usize) % CORRECTION_SIZE;
+        Self::apply(&mut self.material[color.index()][idx], depth, diff);
     }
 
     /// Get the correction value for a pawn hash.
@@ -56,20 +107,24 @@ impl CorrectionHistoryTable {
     pub fn update(&mut self, color: Color, pawn_hash: u64, depth: i32, diff: i32) {
         let c = color.index();
         let idx = (pawn_hash as usize) % CORRECTION_SIZE;
-        
+        Self::apply(&mut self.table[c][idx], depth, diff);
+    }
+
+    #[inline]
+    fn apply(entry: &mut i16, depth: i32, diff: i32) {
         // Bonus scaled by depth (higher depth = more reliable)
         let bonus = (diff * depth).clamp(-CORRECTION_MAX / 4, CORRECTION_MAX / 4);
         
         // Apply gravity update (same as history table)
-        let old = i32::from(self.table[c][idx]);
+        let old = i32::from(*entry);
         let new = old + bonus - old * bonus.abs() / CORRECTION_MAX;
-        self.table[c][idx] = new.clamp(-CORRECTION_MAX, CORRECTION_MAX) as i16;
+        *entry = new.clamp(-CORRECTION_MAX, CORRECTION_MAX) as i16;
     }
 
     /// Age correction values (divide by 2).
     /// Call at start of new search to give more weight to recent data.
     pub fn age(&mut self) {
-        for color in &mut self.table {
+        for color in self.table.iter_mut().chain(self.material.iter_mut()) {
             for entry in color {
                 *entry /= 2;
             }
@@ -103,6 +158,27 @@ mod tests {
         assert_eq!(table.get(Color::Black, hash), 0);
     }
 
+    #[test]
+    fn test_material_correction() {
+        let mut table = CorrectionHistoryTable::new();
+        let rook_ending = Board::from_fen("4k3/4p3/8/8/8/8/3RP3/4K3 w - - 0 1").unwrap();
+        let same_material = Board::from_fen("3k4/8/4p3/8/2R5/4P3/8/5K2 w - - 0 1").unwrap();
+        let key = material_key(&rook_ending);
+        assert_eq!(key, material_key(&same_material));
+        assert_ne!(key, material_key(&Board::default()));
+
+        // Search keeps coming out below the static eval: correction goes negative
+        for _ in 0..4 {
+            table.update_all(Color::White, 42, key, 6, -80);
+        }
+        assert!(table.get_material(Color::White, key) < 0);
+        assert!(table.correction(Color::White, 42, key) < 0);
+
+        // Same material elsewhere picks up the material part only
+        let other = table.correction(Color::White, 7, material_key(&same_material));
+        assert!(other < 0 && other > table.correction(Color::White, 42, key));
+    }
+
     #[test]
     fn test_correction_clamping() {
         let mut table = CorrectionHistoryTable::new();
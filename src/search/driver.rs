@@ -0,0 +1,782 @@
+//! Iterative deepening driver.
+//!
+//! Runs the root search loop for one thread: iterative deepening with
+//! aspiration windows, soft/hard time checks and best-move tracking. Nothing
+//! here writes to stdout directly; per-iteration info goes to an `InfoSink`, so
+//! the UCI handler, a library caller and tests can each decide what to do with it.
+//!
+//! The stop flag is the searcher's `shared.stop`: anything holding a clone of
+//! `searcher.shared` can end the search from another thread.
+//!
+//! A root fail-high keeps its move as the best move and is reported with the
+//! best line known for it, so a search stopped during the re-search still has
+//! a move and the GUI never sees an empty PV.
+//!
+//! After each iteration the driver also refuses to walk into a repetition when
+//! the side to move is clearly better and a non-repeating move is almost as
+//! good (see `avoid_repetition`).
+//!
+//! With `MultiPV` above 1 each iteration then searches the root again for the
+//! next best lines, each time with the first moves of the lines found so far
+//! excluded, and reports all of them best first (`info ... multipv K`).
+
+use super::aspiration::{AspirationWindow, WindowResult};
+use super::contempt::{CONTEMPT, DYNAMIC_CONTEMPT_MAX, DrawKind};
+use super::tt::BoundType;
+use super::{negamax, OffPV, Root, SearchStats, Searcher, TimeManager, PV};
+use crate::eval::SearchEvaluator;
+use crate::types::{line_to_uci, next_halfmove_clock, Board, Depth, Move, NodeCount, Ply, Score, ScoreScale, MAX_DEPTH};
+use smallvec::smallvec;
+use alloc::{format, vec, string::{String, ToString}, vec::Vec};
+use core::sync::atomic::Ordering;
+
+/// The side to move counts as clearly better from this static eval on
+const REPETITION_AVOID_EVAL: i32 = 150;
+
+/// A non-repeating move this close to a drawn best move is played instead
+const REPETITION_AVOID_MARGIN: i32 = 30;
+
+/// Result of an iterative deepening run
+#[derive(Debug, Clone)]
+pub struct SearchOutcome {
+    /// Best move of the last completed iteration
+    pub best_move: Option<Move>,
+    /// Score of the best move (side to move's perspective)
+    pub score: Score,
+    /// Principal variation
+    pub pv: PV,
+    /// Statistics of the run
+    pub stats: SearchStats,
+    /// Nodes per second over the whole run, all threads (a search shorter
+    /// than a millisecond counts as one)
+    pub nps: u64,
+    /// TT fill in permill when the search ended
+    pub hashfull: u32,
+}
+
+impl SearchOutcome {
+    /// Second move of the PV, if any (the expected reply)
+    pub fn ponder_move(&self) -> Option<Move> {
+        self.pv.get(1).copied()
+    }
+
+    /// Recompute `nps` from `stats` (after the node count changed)
+    pub(crate) fn update_nps(&mut self) {
+        self.nps = self.stats.nodes * 1000 / self.stats.time_ms.max(1);
+    }
+}
+
+/// Summary of one completed iteration (or of an aspiration fail within one)
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+    pub depth: i32,
+    pub seldepth: i32,
+    /// Rank of this line among the root moves (1: the best)
+    pub multipv: usize,
+    pub score: Score,
+    /// `LowerBound` after a fail-high, `UpperBound` after a fail-low, else `Exact`
+    pub bound: BoundType,
+    /// Nodes over all threads
+    pub nodes: NodeCount,
+    pub qnodes: NodeCount,
+    pub evals: u64,
+    pub tbhits: u64,
+    pub nps: u64,
+    pub time_ms: u64,
+    pub hashfull: u32,
+    pub pv: PV,
+    /// Root position when castling in the PV is written king-takes-rook
+    /// (`UCI_Chess960`), else `None`
+    pub chess960_root: Option<Board>,
+}
+
+impl SearchInfo {
+    /// Format as a UCI `info` line
+    pub fn to_uci(&self) -> String {
+        let pv_str: String = match &self.chess960_root {
+            Some(root) => line_to_uci(root, &self.pv, true),
+            None => self.pv.iter().map(|m| m.to_string()).collect::<Vec<_>>().join(" "),
+        };
+
+        let bound = match self.bound {
+            BoundType::LowerBound => " lowerbound",
+            BoundType::UpperBound => " upperbound",
+            BoundType::Exact | BoundType::None => "",
+        };
+
+        format!(
+            "info depth {} seldepth {} multipv {} score {}{} nodes {} qnodes {} evals {} nps {} tbhits {} time {} hashfull {} pv {}",
+            self.depth,
+            self.seldepth,
+            self.multipv,
+            self.score.display(ScoreScale::Normalized),
+            bound,
+            self.nodes,
+            self.qnodes,
+            self.evals,
+            self.nps,
+            self.tbhits,
+            self.time_ms,
+            self.hashfull,
+            pv_str
+        )
+    }
+}
+
+/// Receiver for search progress
+pub trait InfoSink {
+    /// Called after every completed iteration
+    fn iteration(&mut self, info: &SearchInfo);
+
+    /// Called when the root search fails outside its aspiration window; the
+    /// score is only a bound and the iteration is searched again
+    fn bound(&mut self, _info: &SearchInfo) {}
+
+    /// Free-form diagnostics (aspiration fails, profiling)
+    fn message(&mut self, _msg: &str) {}
+}
+
+/// Prints UCI `info` lines to stdout
+#[cfg(feature = "std")]
+pub struct UciInfoSink;
+
+#[cfg(feature = "std")]
+impl InfoSink for UciInfoSink {
+    fn iteration(&mut self, info: &SearchInfo) {
+        println!("{}", info.to_uci());
+    }
+
+    fn bound(&mut self, info: &SearchInfo) {
+        println!("{}", info.to_uci());
+    }
+
+    fn message(&mut self, msg: &str) {
+        println!("info string {}", msg);
+    }
+}
+
+/// Discards all output (helper threads)
+pub struct NullInfoSink;
+
+impl InfoSink for NullInfoSink {
+    fn iteration(&mut self, _info: &SearchInfo) {}
+}
+
+/// Iterative deepening from `board` up to `max_depth`.
+///
+/// `time_manager` is installed on the searcher and decides when to stop
+/// starting iterations (soft limit) and when to abort one (hard limit).
+/// Tables (TT, history, killers) are used as they are; resetting them between
+/// searches is up to the caller (`Searcher::search` does it).
+pub fn iterative_deepening(
+    searcher: &mut Searcher,
+    board: &Board,
+    max_depth: Depth,
+    time_manager: TimeManager,
+    sink: &mut dyn InfoSink,
+) -> SearchOutcome {
+    searcher.board = *board;
+    searcher.time_manager = time_manager;
+    searcher.next_time_check.set(searcher.stats.nodes);
+    searcher.iterations.clear();
+
+    let mut best_score = Score::neg_infinity();
+    let mut reported_nodes: NodeCount = 0;
+
+    // Initialize evaluator at root
+    let local_nnue = searcher.nnue.clone();
+    let local_nnue_endgame = searcher.nnue_endgame.clone();
+    let local_pst = searcher.params.pst.clone();
+    let local_output_scale = searcher.params.output_scaling.then_some(searcher.params.output_scale);
+    let mut root_evaluator =
+        SearchEvaluator::with_kind(searcher.params.eval_kind, local_nnue.as_ref(), local_nnue_endgame.as_ref(), board)
+        .with_endgame(searcher.params.endgame)
+        .with_pst(&local_pst)
+        .with_scale(searcher.params.eval_scale)
+        .with_output_scale(local_output_scale.as_ref());
+
+    // Never deeper than MaxDepth (nor MAX_DEPTH, well short of MAX_PLY)
+    let max_depth = max_depth.raw().min(searcher.params.max_depth).clamp(1, MAX_DEPTH);
+
+    for depth in 1..=max_depth {
+        // Up to MinDepth only an explicit `stop` ends the search, so even an
+        // exhausted clock gets a real move; past it, the time manager decides
+        searcher.min_depth_pending = depth <= searcher.params.min_depth;
+        let can_start = if searcher.min_depth_pending {
+            !searcher.shared.stop.load(Ordering::Relaxed)
+        } else {
+            searcher.can_start_new_iteration()
+        };
+        if !can_start {
+            break;
+        }
+
+        // Early termination: only stop if we found a mate within the current depth.
+        // If mate distance > depth, deeper search might find a shorter mate (or longer defense).
+        // Example: At depth 3, finding "mate in 5" should NOT stop search,
+        //          but "mate in 2" (within depth 3) can stop.
+        // Same for mated scores: "mated in 5" at depth 3 should continue to find defenses.
+        if best_score.is_mate_score() && searcher.best_move.is_some() {
+            if let Some(plies_to_mate) = best_score.mate_distance() {
+                // Only stop if the mate is achievable within remaining search depth
+                // plies_to_mate represents plies from root, so if it's <= depth, we're done
+                if plies_to_mate <= depth {
+                    break;
+                }
+            }
+        }
+
+        // Seldepth is reported per iteration (re-searches included)
+        searcher.stats.seldepth = Ply::ZERO;
+        let iteration_start = (searcher.time_manager.elapsed(), searcher.stats.nodes);
+
+        // Aspiration window: use previous score +/- delta after depth 1
+        let mut window = AspirationWindow::with_width(best_score, depth, searcher.params.aspiration_window);
+
+        // Aspiration loop: widen the failing bound until the score is exact
+        loop {
+            let result = negamax::search::<Root>(
+                searcher,
+                &mut root_evaluator,
+                board,
+                Depth::new(depth),
+                Ply::ZERO,
+                window.alpha,
+                window.beta,
+                None,  // No prev move at root
+            );
+
+            if searcher.should_stop() {
+                break;
+            }
+
+            let window_result = window.update(result.score);
+            if window_result == WindowResult::FailHigh {
+                // The move that failed high is at least as good as the last
+                // best: play it if the re-search is cut short
+                if let Some(m) = result.best_move {
+                    searcher.best_move = Some(m);
+                    searcher.pv = fail_high_pv(m, &result.pv, &searcher.pv);
+                }
+            }
+            if window_result != WindowResult::Exact {
+                // A fail-low line is unreliable: keep showing the last good one
+                let bound = if window_result == WindowResult::FailHigh {
+                    BoundType::LowerBound
+                } else {
+                    BoundType::UpperBound
+                };
+                let unreported = searcher.stats.nodes - reported_nodes;
+                sink.bound(&search_info(searcher, depth, result.score, bound, searcher.pv.clone(), unreported));
+            }
+
+            match window_result {
+                WindowResult::Exact => {
+                    // Score within window, accept result
+                    if let Some(m) = result.best_move {
+                        searcher.best_move = Some(m);
+                        best_score = result.score;
+                        searcher.pv = result.pv.clone();
+                    }
+                    break;
+                }
+                WindowResult::FailLow => {
+                    // Best move may be worse than we thought: give it more time
+                    // (once per iteration)
+                    if window.fail_lows == 1 {
+                        searcher.time_manager.extend_time(1.2);
+                    }
+                }
+                WindowResult::FailHigh => {}
+            }
+        }
+
+        if !searcher.should_stop() {
+            if let Some((m, score, pv)) = avoid_repetition(searcher, &root_evaluator, board, depth, best_score) {
+                searcher.best_move = Some(m);
+                best_score = score;
+                searcher.pv = pv;
+            }
+        }
+
+        // MultiPV: the next best lines, best first (the best line included)
+        let lines = if searcher.multi_pv > 1 && !searcher.should_stop() {
+            let lines = multi_pv_lines(searcher, &mut root_evaluator, board, depth, best_score);
+            if let Some((score, pv)) = lines.first() {
+                searcher.best_move = pv.first().copied();
+                best_score = *score;
+                searcher.pv = pv.clone();
+            }
+            lines
+        } else {
+            Vec::new()
+        };
+
+        if window.fail_lows > 0 || window.fail_highs > 0 {
+            sink.message(&format!(
+                "aspiration depth {} fail-low {} fail-high {}",
+                depth, window.fail_lows, window.fail_highs
+            ));
+        }
+
+        searcher.stats.depth = Depth::new(depth);
+        searcher.stats.hashfull = searcher.shared.tt.hashfull();
+
+        // Update time from time manager
+        searcher.stats.time_ms = searcher.time_manager.elapsed();
+
+        // Report nodes searched this iteration to the shared counter
+        // (`stats.nodes` is cumulative over the whole search)
+        searcher.shared.total_nodes.fetch_add(searcher.stats.nodes - reported_nodes, Ordering::Relaxed);
+        reported_nodes = searcher.stats.nodes;
+
+        // Track move stability for early termination
+        if searcher.best_move == searcher.last_best_move {
+            searcher.stable_move_count += 1;
+        } else {
+            searcher.stable_move_count = 0;
+            searcher.last_best_move = searcher.best_move;
+        }
+
+        // Report this depth (only if it was not cut short)
+        if !searcher.should_stop() {
+            searcher.iterations.push(
+                searcher.stats.time_ms - iteration_start.0,
+                searcher.stats.nodes - iteration_start.1,
+            );
+
+            searcher.stats.time_search = searcher.time_manager.elapsed() * 1_000_000;
+            if searcher.profiling {
+                sink.message(&searcher.stats.profiling_info());
+            }
+
+            if lines.is_empty() {
+                sink.iteration(&search_info(searcher, depth, best_score, BoundType::Exact, searcher.pv.clone(), 0));
+            }
+            for (rank, (score, pv)) in lines.iter().enumerate() {
+                let info = SearchInfo {
+                    multipv: rank + 1,
+                    ..search_info(searcher, depth, *score, BoundType::Exact, pv.clone(), 0)
+                };
+                sink.iteration(&info);
+            }
+        }
+
+        // `go mate N`: the mate asked for is found, no need to deepen
+        if let (Some(limit), Some(moves)) = (searcher.mate_limit, best_score.mate_moves()) {
+            if moves > 0 && moves as u32 <= limit {
+                break;
+            }
+        }
+    }
+
+    searcher.min_depth_pending = false;
+
+    // Stopped before depth 1 completed: any legal move beats a null move
+    if searcher.best_move.is_none() {
+        searcher.best_move = board.generate_moves().iter().next();
+    }
+
+    let mut outcome = SearchOutcome {
+        best_move: searcher.best_move,
+        score: best_score,
+        pv: searcher.pv.clone(),
+        stats: searcher.stats.clone(),
+        nps: 0,
+        hashfull: searcher.shared.tt.hashfull(),
+    };
+    outcome.update_nps();
+    outcome
+}
+
+/// Info for `depth` from the searcher's current statistics; `unreported` nodes
+/// of this thread are not in the shared counter yet
+fn search_info(
+    searcher: &Searcher,
+    depth: i32,
+    score: Score,
+    bound: BoundType,
+    pv: PV,
+    unreported: NodeCount,
+) -> SearchInfo {
+    SearchInfo {
+        depth,
+        seldepth: searcher.stats.seldepth.raw(),
+        multipv: 1,
+        score,
+        bound,
+        nodes: searcher.shared.total_nodes.load(Ordering::Relaxed) + unreported,
+        qnodes: searcher.stats.qnodes,
+        evals: searcher.stats.eval_calls,
+        tbhits: searcher.stats.tbhits,
+        nps: searcher.stats.nps(),
+        time_ms: searcher.time_manager.elapsed(),
+        hashfull: searcher.stats.hashfull,
+        pv,
+        chess960_root: searcher.chess960.then_some(searcher.board),
+    }
+}
+
+/// Line to report for a root fail-high on `m`: the line the search returned,
+/// else the previous PV if it starts with `m`, else `m` alone
+fn fail_high_pv(m: Move, found: &PV, last: &PV) -> PV {
+    if found.first() == Some(&m) {
+        found.clone()
+    } else if last.first() == Some(&m) {
+        last.clone()
+    } else {
+        smallvec![m]
+    }
+}
+
+/// All `MultiPV` lines for `depth`, best first, starting from the best line
+/// already found (`searcher.pv` with `best_score`).
+///
+/// Each further line is a full-window root search with the first moves of
+/// the earlier lines excluded. A search cut short by the stop flag ends the
+/// list early (the iteration is not reported then anyway).
+fn multi_pv_lines(
+    searcher: &mut Searcher,
+    evaluator: &mut SearchEvaluator,
+    board: &Board,
+    depth: i32,
+    best_score: Score,
+) -> Vec<(Score, PV)> {
+    let mut lines = vec![(best_score, searcher.pv.clone())];
+    let count = searcher.multi_pv.min(board.generate_moves().len());
+    searcher.root_excluded = searcher.best_move.into_iter().collect();
+
+    while lines.len() < count {
+        let result = negamax::search::<Root>(
+            searcher,
+            evaluator,
+            board,
+            Depth::new(depth),
+            Ply::ZERO,
+            Score::neg_infinity(),
+            Score::infinity(),
+            None,
+        );
+        let Some(m) = result.best_move.filter(|_| !searcher.should_stop()) else { break };
+        let pv = if result.pv.first() == Some(&m) { result.pv } else { smallvec![m] };
+        lines.push((result.score, pv));
+        searcher.root_excluded.push(m);
+    }
+    searcher.root_excluded.clear();
+
+    // A later line can come out above an earlier one (different windows and
+    // move orders): report them in score order
+    lines.sort_by_key(|&(score, _)| core::cmp::Reverse(score));
+    lines
+}
+
+/// Non-repeating replacement for a best move that repeats the position.
+///
+/// Only kicks in when the best move walks into a threefold repetition of a
+/// position from the game history, its score is a (contempt-adjusted) draw and the static eval says
+/// we are clearly better: a shallow search can prefer the repetition even
+/// though the position is still there to be won. Every non-repeating move is
+/// searched to `depth - 1` against a bound `REPETITION_AVOID_MARGIN` below the
+/// draw score; the best one clearing it is returned with its score and PV.
+fn avoid_repetition(
+    searcher: &mut Searcher,
+    evaluator: &SearchEvaluator,
+    board: &Board,
+    depth: i32,
+    best_score: Score,
+) -> Option<(Move, Score, PV)> {
+    let best_move = searcher.best_move?;
+    let root_clock = searcher.halfmove_at(Ply::ZERO);
+    let repeats = |searcher: &Searcher, m: Move| {
+        let clock = next_halfmove_clock(board, m, root_clock);
+        searcher.is_repetition(board.make_move_new(m).hash(), Ply::new(1), clock)
+    };
+    // Around the root's `Contempt` draw score, by as much as the search's own contempt moves it
+    let max_draw = CONTEMPT + DrawKind::Repetition.extra_contempt() + DYNAMIC_CONTEMPT_MAX;
+    let root_draw = searcher.contempt_draw(board);
+    if (best_score - root_draw).raw().abs() > max_draw || !repeats(searcher, best_move) {
+        return None;
+    }
+    if evaluator.clone().evaluate(board).raw() < REPETITION_AVOID_EVAL {
+        return None;
+    }
+
+    let floor = best_score.sub_saturating(REPETITION_AVOID_MARGIN);
+    let mut found: Option<(Move, Score, PV)> = None;
+    for m in board.generate_moves().iter() {
+        let child = board.make_move_new(m);
+        if m == best_move || repeats(searcher, m) {
+            continue;
+        }
+
+        let mut child_eval = evaluator.clone();
+        if !child_eval.update_move(board, m) {
+            child_eval.refresh(&child);
+        }
+        // Same ply-1 state the root move loop sets up, so repetition and
+        // fifty-move detection below see this move
+        searcher.set_halfmove_at(Ply::new(1), next_halfmove_clock(board, m, root_clock));
+        searcher.set_path_move(Ply::ZERO, Some(m));
+        // Anything above the floor (or the best alternative so far) is an exact score
+        let alpha = found.as_ref().map_or(floor, |f| f.1).sub_saturating(1);
+        let result = negamax::search::<OffPV>(
+            searcher,
+            &mut child_eval,
+            &child,
+            Depth::new((depth - 1).max(1)),
+            Ply::new(1),
+            -Score::infinity(),
+            -alpha,
+            Some(m),
+        );
+        if searcher.should_stop() {
+            return None;
+        }
+
+        let score = -result.score;
+        if score > alpha {
+            let mut pv: PV = smallvec![m];
+            pv.extend(result.pv);
+            found = Some((m, score, pv));
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::SearchLimits;
+
+    /// Collects everything the driver emits
+    #[derive(Default)]
+    struct Capture {
+        infos: Vec<SearchInfo>,
+        bounds: Vec<SearchInfo>,
+        messages: Vec<String>,
+    }
+
+    impl InfoSink for Capture {
+        fn iteration(&mut self, info: &SearchInfo) {
+            self.infos.push(info.clone());
+        }
+
+        fn bound(&mut self, info: &SearchInfo) {
+            self.bounds.push(info.clone());
+        }
+
+        fn message(&mut self, msg: &str) {
+            self.messages.push(msg.to_string());
+        }
+    }
+
+    #[test]
+    fn test_fixed_depth_emits_every_iteration() {
+        let board = Board::default();
+        let limits = SearchLimits::depth(4);
+        let mut searcher = Searcher::new();
+        let mut capture = Capture::default();
+
+        let outcome = iterative_deepening(
+            &mut searcher,
+            &board,
+            Depth::new(4),
+            TimeManager::from_limits(&limits, board.turn()),
+            &mut capture,
+        );
+
+        let depths: Vec<i32> = capture.infos.iter().map(|i| i.depth).collect();
+        assert_eq!(depths, vec![1, 2, 3, 4]);
+
+        let last = capture.infos.last().unwrap();
+        assert_eq!(last.score, outcome.score);
+        assert_eq!(last.pv.first().copied(), outcome.best_move);
+        assert!(last.to_uci().starts_with("info depth 4 seldepth "));
+        assert!(last.to_uci().contains(" tbhits 0 "));
+        assert!(outcome.best_move.is_some());
+        assert_eq!(outcome.stats.depth, Depth::new(4));
+    }
+
+    #[test]
+    fn test_winning_side_declines_repetition() {
+        // White is a rook up; Kh1 would reach a position seen twice already in the game
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 8 30").unwrap();
+        let kh1 = crate::uci::parse_move(&board, "g1h1").unwrap();
+        let repeated = board.make_move_new(kh1).hash();
+        let mut searcher = Searcher::new();
+        searcher.set_position_with_history(board, vec![repeated, board.hash(), repeated]);
+        searcher.set_halfmove_clock(8);
+        searcher.best_move = Some(kh1);
+        let evaluator = SearchEvaluator::new(None, None, &board);
+
+        let (m, score, pv) = avoid_repetition(&mut searcher, &evaluator, &board, 3, Score::cp(-10))
+            .expect("a progressing move");
+        assert_ne!(m, kh1);
+        assert_eq!(pv.first(), Some(&m));
+        assert!(score >= Score::cp(-40));
+        assert!(!searcher.is_repetition(board.make_move_new(m).hash(), Ply::new(1), 9));
+
+        // Not a draw score: nothing to avoid
+        assert!(avoid_repetition(&mut searcher, &evaluator, &board, 3, Score::cp(300)).is_none());
+
+        // Level material: the repetition is fine
+        let level = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1").unwrap();
+        let kh1 = crate::uci::parse_move(&level, "g1h1").unwrap();
+        let repeated = level.make_move_new(kh1).hash();
+        searcher.set_position_with_history(level, vec![repeated, level.hash(), repeated]);
+        searcher.best_move = Some(kh1);
+        let evaluator = SearchEvaluator::new(None, None, &level);
+        assert!(avoid_repetition(&mut searcher, &evaluator, &level, 3, Score::draw()).is_none());
+    }
+
+    #[test]
+    fn test_contempt_still_declines_repetition() {
+        // Contempt moves the draw score the repetition comes back with
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 8 30").unwrap();
+        let kh1 = crate::uci::parse_move(&board, "g1h1").unwrap();
+        let repeated = board.make_move_new(kh1).hash();
+        let mut searcher = Searcher::new();
+        assert!(searcher.params.set("Contempt", 50));
+        searcher.set_position_with_history(board, vec![repeated, board.hash(), repeated]);
+        searcher.set_halfmove_clock(8);
+        searcher.best_move = Some(kh1);
+        let evaluator = SearchEvaluator::new(None, None, &board);
+
+        let draw = Score::cp(-50 - CONTEMPT);
+        let (m, score, _) = avoid_repetition(&mut searcher, &evaluator, &board, 3, draw)
+            .expect("a progressing move");
+        assert_ne!(m, kh1);
+        assert!(score >= draw.sub_saturating(REPETITION_AVOID_MARGIN));
+    }
+
+    #[test]
+    fn test_go_mate_stops_once_found() {
+        // Rb7 and Ra8#: mate in 2, no mate in 1
+        let board = Board::from_fen("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        searcher.set_position(board);
+        let mut capture = Capture::default();
+
+        let limits = SearchLimits { mate: Some(2), ..SearchLimits::new() };
+        let outcome = searcher.search_with_sink(limits, &mut capture);
+
+        // The iteration that proves the mate is reported, and is the last one
+        let first_mate = capture.infos.iter().position(|i| i.score.is_mate()).expect("mate found");
+        assert_eq!(first_mate, capture.infos.len() - 1);
+        assert_eq!(outcome.score.mate_moves(), Some(2));
+        assert!(outcome.stats.depth.raw() < 8);
+    }
+
+    #[test]
+    fn test_multi_pv_reports_ranked_lines() {
+        // Nc7+ wins the queen; the other lines are far behind
+        let board = Board::from_fen("q3k3/8/8/1N6/8/8/PPP5/4K2R w K - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        searcher.set_position(board);
+        let mut capture = Capture::default();
+
+        let limits = SearchLimits { multi_pv: 3, ..SearchLimits::depth(5) };
+        let outcome = searcher.search_with_sink(limits, &mut capture);
+
+        // Three lines per iteration, ranked 1..=3
+        assert_eq!(capture.infos.len(), 15);
+        for (i, info) in capture.infos.iter().enumerate() {
+            assert_eq!(info.multipv, i % 3 + 1);
+            assert_eq!(info.depth, (i / 3 + 1) as i32);
+        }
+
+        let last: Vec<&SearchInfo> = capture.infos[12..].iter().collect();
+        let firsts: Vec<Move> = last.iter().map(|i| i.pv[0]).collect();
+        assert!(firsts[0] != firsts[1] && firsts[1] != firsts[2] && firsts[0] != firsts[2]);
+        assert!(last[0].score >= last[1].score && last[1].score >= last[2].score);
+        assert!(last[1].to_uci().contains(" multipv 2 score "));
+
+        // The best line is the one played
+        assert_eq!(firsts[0].to_uci(), "b5c7");
+        assert_eq!(outcome.best_move, Some(firsts[0]));
+        assert_eq!(outcome.score, last[0].score);
+    }
+
+    #[test]
+    fn test_aspiration_fail_high_reports_lowerbound() {
+        // Two rooks up at low depth, then the mate in 2 shows up and the narrow
+        // window around the material score fails high
+        let board = Board::from_fen("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        let mut capture = Capture::default();
+
+        iterative_deepening(
+            &mut searcher,
+            &board,
+            Depth::new(4),
+            TimeManager::from_limits(&SearchLimits::depth(4), board.turn()),
+            &mut capture,
+        );
+
+        let fail_high = capture.bounds.iter().find(|i| i.bound == BoundType::LowerBound).expect("a fail-high");
+        let line = fail_high.to_uci();
+        assert!(line.contains(" lowerbound nodes "), "{line}");
+
+        // The completed iteration is exact again
+        let last = capture.infos.last().unwrap();
+        assert_eq!(last.bound, BoundType::Exact);
+        assert!(!last.to_uci().contains("bound"));
+        assert!(last.score.is_mate());
+    }
+
+    #[test]
+    fn test_fail_high_reports_pv() {
+        let board = Board::from_fen("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        let mut capture = Capture::default();
+
+        iterative_deepening(
+            &mut searcher,
+            &board,
+            Depth::new(4),
+            TimeManager::from_limits(&SearchLimits::depth(4), board.turn()),
+            &mut capture,
+        );
+
+        let fail_highs: Vec<&SearchInfo> =
+            capture.bounds.iter().filter(|i| i.bound == BoundType::LowerBound).collect();
+        assert!(!fail_highs.is_empty());
+        for info in fail_highs {
+            let first = *info.pv.first().expect("fail-high with an empty PV");
+            assert!(board.generate_moves().iter().any(|m| m == first));
+            assert!(info.to_uci().contains(" lowerbound ") && !info.to_uci().ends_with(" pv "));
+        }
+    }
+
+    #[test]
+    fn test_fail_high_pv_fallbacks() {
+        let board = Board::default();
+        let moves: Vec<Move> = board.generate_moves().iter().take(3).collect();
+        let (a, b, c) = (moves[0], moves[1], moves[2]);
+
+        assert_eq!(fail_high_pv(a, &smallvec![a, b], &smallvec![c]).as_slice(), &[a, b]);
+        assert_eq!(fail_high_pv(a, &smallvec![], &smallvec![a, c]).as_slice(), &[a, c]);
+        assert_eq!(fail_high_pv(a, &smallvec![], &smallvec![b, c]).as_slice(), &[a]);
+    }
+
+    #[test]
+    fn test_stop_flag_before_start() {
+        let board = Board::default();
+        let mut searcher = Searcher::new();
+        searcher.shared.stop.store(true, Ordering::Relaxed);
+        let mut capture = Capture::default();
+
+        let outcome = iterative_deepening(
+            &mut searcher,
+            &board,
+            Depth::new(10),
+            TimeManager::from_limits(&SearchLimits::depth(10), board.turn()),
+            &mut capture,
+        );
+
+        // Nothing was searched, but a legal move is still returned
+        assert!(capture.infos.is_empty());
+        let best = outcome.best_move.expect("a legal fallback move");
+        assert!(board.generate_moves().iter().any(|m| m == best));
+    }
+}
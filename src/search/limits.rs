@@ -5,11 +5,48 @@
 //! - Fixed time search
 //! - Time control with increment
 //! - Infinite search (until stop)
+//! - Node limits, which apply on top of any of the above (`go infinite nodes N`
+//!   ignores the clock but still stops after N nodes)
 //! - Soft/hard time limits for optimal iteration control
+//! - Predicting the next iteration's duration from the branching factor of
+//!   the last few, so an iteration that cannot finish is not started
 
-use crate::types::{Depth, Color};
+use crate::types::{Depth, Color, NodeCount};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use crate::uci::SearchParams;
-use std::time::Instant;
+#[cfg(feature = "std")]
+use std::{sync::OnceLock, time::Instant};
+
+/// Sudden death: moves the remaining time is spread over
+const SUDDEN_DEATH_MOVES: u64 = 30;
+
+/// Sudden death: percent of the increment added to each move's budget
+const SUDDEN_DEATH_INC_PERCENT: u64 = 80;
+
+/// Sudden death: a move never plans on more than 1/N of the remaining time
+const SUDDEN_DEATH_MAX_FRACTION: u64 = 5;
+
+/// Below this much time (ms) sudden death allocation drops to the minimum
+const PANIC_TIME_MS: u64 = 3000;
+
+/// Millisecond clock read by the time manager (any monotonic source, arbitrary epoch).
+///
+/// `std` builds read `Instant`. Without `std` there is no clock to read: inject
+/// one with `TimeManager::with_clock`, or the time never advances and only
+/// depth, node and stop limits end the search.
+pub type Clock = fn() -> u64;
+
+#[cfg(feature = "std")]
+fn default_clock() -> u64 {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+#[cfg(not(feature = "std"))]
+fn default_clock() -> u64 {
+    0
+}
 
 /// Search limits configuration
 #[derive(Debug, Clone, Default)]
@@ -30,10 +67,16 @@ pub struct SearchLimits {
     pub binc: Option<u64>,
     /// Moves until next time control
     pub movestogo: Option<u32>,
+    /// Stop once a mate in this many moves (or fewer) is found
+    pub mate: Option<u32>,
     /// Infinite search
     pub infinite: bool,
+    /// Root lines to search and report (`MultiPV`; 0 counts as 1)
+    pub multi_pv: usize,
     /// Move overhead (safety buffer for network/GUI delay)
     pub move_overhead: u64,
+    /// Time source (`None`: the default clock)
+    pub clock: Option<Clock>,
 }
 
 impl SearchLimits {
@@ -55,6 +98,7 @@ impl SearchLimits {
         }
     }
 
+    #[cfg(feature = "std")]
     pub fn from_params(params: &SearchParams) -> Self {
         Self {
             depth: params.depth,
@@ -65,8 +109,11 @@ impl SearchLimits {
             winc: params.winc,
             binc: params.binc,
             movestogo: params.movestogo,
+            mate: params.mate,
             infinite: params.infinite,
+            multi_pv: 1,
             move_overhead: Self::DEFAULT_MOVE_OVERHEAD,
+            clock: None,
         }
     }
     
@@ -75,6 +122,65 @@ impl SearchLimits {
         self.move_overhead = overhead;
         self
     }
+
+    /// Set the number of root lines (from UCI option)
+    pub fn with_multi_pv(mut self, lines: usize) -> Self {
+        self.multi_pv = lines;
+        self
+    }
+}
+
+/// Number of completed iterations kept for the prediction
+const ITERATION_HISTORY: usize = 4;
+
+/// Branching factor assumed at most (keeps one odd iteration from starving the search)
+const MAX_BRANCHING: f64 = 8.0;
+
+/// Duration and node count of the most recent completed iterations
+#[derive(Debug, Clone, Default)]
+pub struct IterationTimes {
+    /// (time in ms, nodes) per iteration, oldest first
+    entries: [(u64, NodeCount); ITERATION_HISTORY],
+    len: usize,
+}
+
+impl IterationTimes {
+    /// Record a completed iteration
+    pub fn push(&mut self, time_ms: u64, nodes: NodeCount) {
+        if self.len == ITERATION_HISTORY {
+            self.entries.rotate_left(1);
+            self.len -= 1;
+        }
+        self.entries[self.len] = (time_ms, nodes);
+        self.len += 1;
+    }
+
+    /// Forget all iterations (new search)
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Average ratio of node counts between consecutive iterations
+    pub fn branching_factor(&self) -> Option<f64> {
+        let recent = &self.entries[..self.len];
+        let ratios: Vec<f64> = recent.windows(2)
+            .filter(|w| w[0].1 > 0)
+            .map(|w| w[1].1 as f64 / w[0].1 as f64)
+            .collect();
+        if ratios.is_empty() {
+            return None;
+        }
+        let mean = ratios.iter().sum::<f64>() / ratios.len() as f64;
+        Some(mean.clamp(1.0, MAX_BRANCHING))
+    }
+
+    /// Expected duration of the next iteration in ms: the last one scaled by
+    /// the branching factor. None until two iterations are known.
+    pub fn predict_next_ms(&self) -> Option<u64> {
+        let branching = self.branching_factor()?;
+        let (last_ms, _) = self.entries[self.len - 1];
+        Some((last_ms as f64 * branching).ceil() as u64)
+    }
 }
 
 /// Time manager for search with soft and hard limits
@@ -86,10 +192,14 @@ pub struct TimeManager {
     hard_limit: u64,
     /// Move overhead safety buffer
     _move_overhead: u64,
-    /// Is this an infinite search?
+    /// Is this an infinite search? (no time limit; a node limit may still apply)
     infinite: bool,
-    /// Start time of search
-    start_time: Option<Instant>,
+    /// Time source
+    clock: Clock,
+    /// Clock reading at the start of the search
+    start_ms: u64,
+    /// Maximum nodes (per thread)
+    node_limit: Option<u64>,
 }
 
 impl TimeManager {
@@ -99,12 +209,26 @@ impl TimeManager {
             hard_limit: u64::MAX,
             _move_overhead: 10,
             infinite: true,
-            start_time: Some(Instant::now()),
+            clock: default_clock,
+            start_ms: default_clock(),
+            node_limit: None,
         }
     }
 
     /// Create time manager from search limits
     pub fn from_limits(limits: &SearchLimits, side: Color) -> Self {
+        let manager = Self {
+            node_limit: limits.nodes,
+            ..Self::time_limits(limits, side)
+        };
+        match limits.clock {
+            Some(clock) => manager.with_clock(clock),
+            None => manager,
+        }
+    }
+
+    /// Soft/hard time limits for `limits` (no node limit)
+    fn time_limits(limits: &SearchLimits, side: Color) -> Self {
         if limits.infinite {
             return Self::new();
         }
@@ -125,7 +249,9 @@ impl TimeManager {
                 hard_limit: hard.max(1),
                 _move_overhead: move_overhead,
                 infinite: false,
-                start_time: Some(Instant::now()),
+                clock: default_clock,
+                start_ms: default_clock(),
+                node_limit: None,
             };
         }
 
@@ -141,57 +267,47 @@ impl TimeManager {
             // Subtract overhead from available time
             let available = time.saturating_sub(move_overhead);
             
-            // Estimate moves remaining based on time situation
-            let mtg = if let Some(movestogo) = limits.movestogo {
-                // Explicit moves to go (sudden death with X moves per period)
-                movestogo as u64
-            } else {
-                // No explicit moves-to-go, estimate based on time left
-                // Use a dynamic estimate: more time = more conservative
-                // Less time = more aggressive (assume game is ending)
-                if available > 300000 {
-                    // > 5 min: assume 40 moves remaining
-                    40
-                } else if available > 120000 {
-                    // 2-5 min: assume 30 moves 
-                    30
-                } else if available > 60000 {
-                    // 1-2 min: assume 25 moves
-                    25
-                } else if available > 30000 {
-                    // 30s-1min: assume 20 moves
-                    20
-                } else if available > 10000 {
-                    // 10-30s: assume 15 moves
-                    15
-                } else {
-                    // < 10s: panic mode, 10 moves
-                    10
+            let (soft, hard) = match limits.movestogo {
+                // Explicit moves to go: spread the time over them
+                Some(movestogo) => {
+                    let base_time = available / (movestogo as u64).max(1);
+
+                    // Add most of increment to our budget (we'll get it back after moving)
+                    let inc_bonus = (inc * 85) / 100;
+
+                    // Soft limit: base + increment bonus, but cap at reasonable portion of remaining time
+                    let soft = (base_time + inc_bonus).min(available / 3);
+
+                    // Hard limit: allow up to 3x soft for critical moves, but never more than 50% of remaining
+                    let hard = (soft * 3).min(available / 2).max(soft);
+
+                    // Minimum thresholds to avoid instant moves
+                    (soft.max(100), hard.max(200))
                 }
-            }.max(1);
-            
-            // Base time allocation per move
-            let base_time = available / mtg;
-            
-            // Add most of increment to our budget (we'll get it back after moving)
-            let inc_bonus = (inc * 85) / 100;  // Use 85% of increment
-            
-            // Soft limit: base + increment bonus, but cap at reasonable portion of remaining time
-            let soft = (base_time + inc_bonus).min(available / 3);
-            
-            // Hard limit: allow up to 3x soft for critical moves, but never more than 50% of remaining
-            let hard = (soft * 3).min(available / 2).max(soft);
-            
-            // Minimum thresholds to avoid instant moves
-            let soft = soft.max(100); // At least 100ms
-            let hard = hard.max(200); // At least 200ms
+                // Sudden death, nearly flagging: keep the clock alive on the increment
+                None if available < PANIC_TIME_MS => {
+                    let soft = (available / 20 + inc / 2).min(available / 8);
+                    let hard = (soft * 2).min(available / 4).max(soft);
+                    (soft.max(1), hard.max(1))
+                }
+                // Sudden death: a fixed share of the time plus most of the increment,
+                // never more than a fraction of what is left
+                None => {
+                    let soft = (available / SUDDEN_DEATH_MOVES + inc * SUDDEN_DEATH_INC_PERCENT / 100)
+                        .min(available / SUDDEN_DEATH_MAX_FRACTION);
+                    let hard = (soft * 3).min(available / 2).max(soft);
+                    (soft.max(1), hard.max(1))
+                }
+            };
             
             return Self {
                 soft_limit: soft,
                 hard_limit: hard,
                 _move_overhead: move_overhead,
                 infinite: false,
-                start_time: Some(Instant::now()),
+                clock: default_clock,
+                start_ms: default_clock(),
+                node_limit: None,
             };
         }
 
@@ -201,20 +317,27 @@ impl TimeManager {
             hard_limit: u64::MAX,
             _move_overhead: move_overhead,
             infinite: true,
-            start_time: Some(Instant::now()),
+            clock: default_clock,
+            start_ms: default_clock(),
+            node_limit: None,
         }
     }
     
     /// Start the timer (call at search start)
     pub fn start(&mut self) {
-        self.start_time = Some(Instant::now());
+        self.start_ms = (self.clock)();
+    }
+
+    /// Read time from `clock` instead of the default (restarts the timer)
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self.start();
+        self
     }
 
     /// Get elapsed time in milliseconds
     pub fn elapsed(&self) -> u64 {
-        self.start_time
-            .map(|t| t.elapsed().as_millis() as u64)
-            .unwrap_or(0)
+        (self.clock)().saturating_sub(self.start_ms)
     }
 
     /// Check if we should stop searching (hard limit - for mid-search check)
@@ -225,14 +348,19 @@ impl TimeManager {
         self.elapsed() >= self.hard_limit
     }
 
-    /// Check if we can start a new iteration (soft limit)
-    pub fn can_start_iteration(&self) -> bool {
+    /// Check if we can start a new iteration (soft limit).
+    ///
+    /// With a prediction of the iteration's duration (`IterationTimes`), the
+    /// iteration is only started if it is expected to finish within the soft limit.
+    pub fn can_start_iteration(&self, predicted_ms: Option<u64>) -> bool {
         if self.infinite {
             return true;
         }
-        // Start new iteration if we have time remaining below soft limit
-        // and predict we can complete at least a partial iteration
-        self.elapsed() < self.soft_limit
+        let elapsed = self.elapsed();
+        if elapsed >= self.soft_limit {
+            return false;
+        }
+        predicted_ms.is_none_or(|p| p <= self.soft_limit - elapsed)
     }
 
     /// Check if we've exceeded soft limit (use between iterations)
@@ -251,9 +379,14 @@ impl TimeManager {
         self.elapsed() >= self.hard_limit
     }
     
+    /// Has the node limit (if any) been reached?
+    #[inline]
+    pub fn nodes_exceeded(&self, nodes: u64) -> bool {
+        self.node_limit.is_some_and(|limit| nodes >= limit)
+    }
+
     /// Extend time limits (when search is in trouble, e.g., score dropped)
     /// factor > 1.0 extends time, factor < 1.0 reduces time
-    #[allow(dead_code)]
     pub fn extend_time(&mut self, factor: f64) {
         if !self.infinite {
             self.soft_limit = ((self.soft_limit as f64) * factor) as u64;
@@ -320,14 +453,93 @@ mod tests {
         assert!(!tm.is_infinite());
         // 60000 - 10 = 59990 available
         // base = 59990 / 30 = ~1999
-        // inc_bonus = 1000 * 0.75 = 750
-        // soft = ~2749
+        // inc_bonus = 1000 * 0.8 = 800
+        // soft = ~2799
         assert!(tm.soft_limit_ms() > 2000);
         assert!(tm.soft_limit_ms() < 4000);
         // hard = min(3 * soft, available / 4)
         assert!(tm.hard_limit_ms() >= tm.soft_limit_ms());
     }
     
+    /// Soft and hard limits for `time` left and `inc` in a sudden-death game
+    fn sudden_death(time: u64, inc: u64) -> (u64, u64) {
+        let limits = SearchLimits { wtime: Some(time), winc: Some(inc), ..SearchLimits::new() };
+        let tm = TimeManager::from_limits(&limits, Color::White);
+        (tm.soft_limit_ms(), tm.hard_limit_ms())
+    }
+
+    #[test]
+    fn test_blitz_allocation_is_sustainable() {
+        // 1+1 over 150 moves, every move using its whole soft budget
+        let mut time = 60_000;
+        for mv in 0..150 {
+            let (soft, hard) = sudden_death(time, 1000);
+            assert!(soft <= hard && hard < time, "move {mv}: {soft}/{hard} with {time} left");
+            if mv == 0 {
+                // The opening move takes a small share, not a chunk of the clock
+                assert!(soft < time / 20);
+            }
+            time = time - soft + 1000;
+        }
+        // Settled close to living on the increment, with a reserve left
+        let (soft, _) = sudden_death(time, 1000);
+        assert!(time > 5000);
+        assert!((800..=1500).contains(&soft));
+
+        // Worst case, every move runs to its hard limit: still never flags
+        let mut time = 60_000;
+        for _ in 0..150 {
+            let (_, hard) = sudden_death(time, 1000);
+            assert!(hard < time);
+            time = time - hard + 1000;
+        }
+        assert!(time > 1000);
+    }
+
+    #[test]
+    fn test_panic_mode_allocation() {
+        // A few seconds left: small fractions only, and never the 100ms floor
+        // of the movestogo path when that would be most of the clock
+        let (soft, hard) = sudden_death(2000, 0);
+        assert!(soft <= 2000 / 8);
+        assert!(hard <= 2000 / 4);
+
+        let (soft, hard) = sudden_death(400, 0);
+        assert!(soft <= 50);
+        assert!(hard <= 100);
+
+        // An increment raises the budget, up to the same cap
+        let (soft, _) = sudden_death(2500, 1000);
+        assert!(soft > sudden_death(2500, 0).0);
+        assert!(soft <= 2500 / 8);
+    }
+
+    #[test]
+    fn test_predicted_iteration_must_fit() {
+        let limits = SearchLimits { movetime: Some(1000), move_overhead: 0, ..Default::default() };
+        let tm = TimeManager::from_limits(&limits, Color::White);
+        assert!(tm.can_start_iteration(None));
+
+        // Node counts growing 5x per iteration, the last one took 300ms:
+        // the next one should take about 1.5s, more than the whole budget
+        let mut iterations = IterationTimes::default();
+        assert_eq!(iterations.predict_next_ms(), None);
+        for (ms, nodes) in [(2, 1_000), (12, 5_000), (60, 25_000), (300, 125_000)] {
+            iterations.push(ms, nodes);
+        }
+        assert_eq!(iterations.branching_factor(), Some(5.0));
+        let predicted = iterations.predict_next_ms();
+        assert_eq!(predicted, Some(1500));
+        assert!(!tm.can_start_iteration(predicted));
+
+        // A cheap next iteration still fits
+        assert!(tm.can_start_iteration(Some(100)));
+
+        // Only the most recent iterations count
+        iterations.push(330, 137_500);
+        assert!(iterations.branching_factor().unwrap() < 5.0);
+    }
+
     #[test]
     fn test_infinite() {
         let limits = SearchLimits {
@@ -337,7 +549,40 @@ mod tests {
         let tm = TimeManager::from_limits(&limits, Color::White);
         
         assert!(tm.is_infinite());
-        assert!(tm.can_start_iteration());
+        assert!(tm.can_start_iteration(Some(u64::MAX)));
         assert!(!tm.should_stop());
+        assert!(!tm.nodes_exceeded(u64::MAX));
+
+        // Infinite ignores the clock, not the node limit
+        let limits = SearchLimits { infinite: true, nodes: Some(50_000), ..Default::default() };
+        let tm = TimeManager::from_limits(&limits, Color::White);
+        assert!(tm.is_infinite());
+        assert!(!tm.nodes_exceeded(49_999));
+        assert!(tm.nodes_exceeded(50_000));
+    }
+
+    #[test]
+    fn test_injected_clock() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static NOW: AtomicU64 = AtomicU64::new(10_000);
+        fn fake_clock() -> u64 {
+            NOW.load(Ordering::Relaxed)
+        }
+
+        let limits = SearchLimits {
+            movetime: Some(1000),
+            move_overhead: 0,
+            clock: Some(fake_clock),
+            ..Default::default()
+        };
+        let tm = TimeManager::from_limits(&limits, Color::White);
+        assert_eq!(tm.elapsed(), 0);
+
+        NOW.store(10_500, Ordering::Relaxed);
+        assert_eq!(tm.elapsed(), 500);
+        assert!(!tm.hard_limit_exceeded());
+
+        NOW.store(12_000, Ordering::Relaxed);
+        assert!(tm.hard_limit_exceeded());
     }
 }
@@ -4,13 +4,18 @@
 //!
 //! # Architecture
 //! - `Searcher`: Main search controller with state management
+//! - `driver`: Iterative deepening with aspiration windows, reporting via `InfoSink`
 //! - `negamax`: Alpha-beta search with negamax framework
 //! - `ordering`: Move ordering heuristics (MVV-LVA, killer moves, history)
 //! - `limits`: Search limits and time management
 //! - `tt`: Transposition table for caching search results
+//! - `checks`: Quiet checking move generation and `gives_check` (qsearch, mate search)
+//! - `contempt`: Material-scaled contempt for draw scores
+//! - `params`: Tunable search parameters (hidden UCI options)
 //!
 //! # Multi-threading
-//! Implements Lazy SMP with lock-free TT sharing between threads
+//! Implements Lazy SMP with lock-free TT sharing between threads (`std` builds;
+//! without `std` the search runs on the calling thread only)
 
 mod negamax;
 mod qsearch;
@@ -20,43 +25,98 @@ pub mod tt;
 mod killers;
 mod history;
 mod see;
+mod checks;
 mod countermove;
 pub mod node_types;
 mod correction;
+mod contempt;
+mod aspiration;
+mod driver;
+mod params;
 
 pub use node_types::{NodeType, Root, OnPV, OffPV};
 pub use correction::CorrectionHistoryTable;
-
-pub use limits::{SearchLimits, TimeManager};
-pub use negamax::{SearchResult, PV};
-pub use tt::TranspositionTable;
+pub use checks::{generate_checks, gives_check, CheckList};
+pub use contempt::DrawKind;
+pub use params::{SearchParamsTunable, MAX_CONTEMPT};
+pub use driver::{iterative_deepening, InfoSink, NullInfoSink, SearchInfo, SearchOutcome};
+#[cfg(feature = "std")]
+pub use driver::UciInfoSink;
+
+pub use limits::{Clock, IterationTimes, SearchLimits, TimeManager};
+pub use negamax::{search_with_tt, SearchResult, PV};
+pub use tt::{TranspositionTable, TtProbe, TtStore};
 pub use killers::KillerTable;
 pub use history::HistoryTable;
 pub use countermove::CounterMoveTable;
 pub use see::{see, see_ge, is_good_capture};
 
-use crate::types::{Board, Move, Score, Depth, Ply, NodeCount};
-use crate::eval::{nnue, SearchEvaluator};
+use crate::types::{Board, Move, Score, Depth, Ply, NodeCount, MAX_PLY};
+use crate::eval::nnue;
 use smallvec::smallvec;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use alloc::sync::Arc;
+use core::cell::Cell;
+#[cfg(feature = "std")]
 use std::thread;
 
-/// Search statistics collected during search
+/// Nodes between clock reads in `Searcher::should_stop`
+const TIME_CHECK_INTERVAL: NodeCount = 2048;
+
+/// Upper bound on search threads, whatever the hardware reports
+const MAX_THREADS: usize = 64;
+
+/// Milliseconds between `info currline` reports
+const CURRLINE_INTERVAL_MS: u64 = 1000;
+
+/// Receives `info currline` lines while `UCI_ShowCurrLine` is on
+pub type CurrLineReport = fn(&str);
+
+/// Most threads `Searcher::set_threads` accepts: the hardware threads, up to `MAX_THREADS`
+pub fn max_threads() -> usize {
+    #[cfg(feature = "std")]
+    let hardware = thread::available_parallelism().map_or(1, |n| n.get());
+    #[cfg(not(feature = "std"))]
+    let hardware = 1;
+    hardware.min(MAX_THREADS)
+}
+
+/// Search statistics collected during search.
+///
+/// Counters are always collected; the eval call count and the profiling timers
+/// are only recorded in debug builds (they cost an `Instant::now()` per call).
+/// Use `Searcher::take_stats` to read a snapshot and reset them.
 #[derive(Debug, Clone, Default)]
 pub struct SearchStats {
+    /// Nodes searched (main + quiescence)
     pub nodes: NodeCount,
+    /// Last completed iteration depth
     pub depth: Depth,
+    /// Maximum ply reached
     pub seldepth: Ply,
+    /// Elapsed time in milliseconds
     pub time_ms: u64,
+    /// TT fill in permill
     pub hashfull: u32,
+    /// Quiescence nodes
     pub qnodes: NodeCount,
+    /// TT probes that found an entry for the position (main + quiescence)
+    pub tt_hits: u64,
+    /// Static evaluations (debug builds)
     pub eval_calls: u64,
-    // Profiling stats (ns)
+    /// Successful tablebase probes (reported as UCI `tbhits`; there is no
+    /// tablebase prober yet, so this stays 0)
+    pub tbhits: u64,
+    // Profiling stats (ns, debug builds)
+    /// Time spent in move generation
     pub time_gen: u64,
+    /// Time spent in evaluation
     pub time_eval: u64,
+    /// Time spent in move ordering
     pub time_order: u64,
-    pub time_search: u64, // (Rest of time)
+    /// Total search time (ns)
+    pub time_search: u64,
 }
 
 impl SearchStats {
@@ -68,27 +128,22 @@ impl SearchStats {
         }
     }
 
-    #[cfg(debug_assertions)]
-    pub fn print_profiling(&self) {
-        let total_ns = self.time_ms * 1_000_000;
-        if total_ns > 0 {
-            let gen_pct = self.time_gen * 100 / total_ns;
-            let eval_pct = self.time_eval * 100 / total_ns;
-            let order_pct = self.time_order * 100 / total_ns;
-            let other = total_ns.saturating_sub(self.time_gen + self.time_eval + self.time_order);
-            let other_pct = other * 100 / total_ns;
-            
-            println!("profiling: gen {}% eval {}% order {}% other {}%", 
-                gen_pct, eval_pct, order_pct, other_pct);
-            
-             println!("stats: qnodes {} evals {}", self.qnodes, self.eval_calls);
-        }
-    }
-    
-    #[cfg(not(debug_assertions))]
-    #[inline]
-    pub fn print_profiling(&self) {
-        // No-op in release builds
+    /// Profiling breakdown, sent as a UCI `info string`
+    pub fn profiling_info(&self) -> String {
+        let total_ns = (self.time_ms * 1_000_000).max(1);
+        let pct = |ns: u64| ns * 100 / total_ns;
+        let other = total_ns.saturating_sub(self.time_gen + self.time_eval + self.time_order);
+
+        format!(
+            "profiling gen {}% eval {}% order {}% other {}% nodes {} qnodes {} evals {}",
+            pct(self.time_gen),
+            pct(self.time_eval),
+            pct(self.time_order),
+            pct(other),
+            self.nodes,
+            self.qnodes,
+            self.eval_calls
+        )
     }
 }
 
@@ -142,16 +197,50 @@ pub struct Searcher {
     pv: PV,
     /// NNUE Model (thread-safe reference)
     pub nnue: Option<nnue::Model>,
-    /// Position history for repetition detection (stores Zobrist hashes)
-    pub position_history: Vec<u64>,
+    /// Optional endgame NNUE, used below the endgame piece threshold
+    pub nnue_endgame: Option<nnue::Model>,
+    /// Zobrist hashes of the game positions before the root (from `position ... moves`)
+    pub game_history: Vec<u64>,
     /// Move stability counter (how many iterations best move unchanged)
     stable_move_count: u32,
     /// Last iteration's best move for stability tracking
     last_best_move: Option<Move>,
     /// Number of threads to use for search
     num_threads: usize,
-    /// Is this a helper thread (no UCI output)
-    is_helper: bool,
+    /// Tunable search parameters (margins, reductions, LMR table)
+    pub params: SearchParamsTunable,
+    /// Static eval per ply along the current search path (`Score::none()` in check)
+    eval_stack: [Score; MAX_PLY as usize],
+    /// Print profiling `info string` lines after each iteration
+    pub profiling: bool,
+    /// Null move is disabled below this ply (set during null-move verification)
+    nmp_min_ply: i32,
+    /// Node count at which `should_stop` next reads the clock
+    next_time_check: Cell<NodeCount>,
+    /// Halfmove clock per ply along the current search path (index 0 = root)
+    halfmove_stack: [u16; MAX_PLY as usize],
+    /// Zobrist hash per ply along the current search path (index 0 = root)
+    path_hashes: [u64; MAX_PLY as usize],
+    /// Move played at each ply along the current search path (`None`: null move)
+    path_moves: [Option<Move>; MAX_PLY as usize],
+    /// `UCI_ShowCurrLine`: report the line being searched every `CURRLINE_INTERVAL_MS`
+    /// (main thread only, off by default)
+    pub currline: Option<CurrLineReport>,
+    /// Node count and elapsed time of the next currline check
+    next_currline: (NodeCount, u64),
+    /// Time and nodes of the last completed iterations (next-iteration prediction)
+    iterations: IterationTimes,
+    /// `go mate N`: stop deepening once a mate within N moves is proven
+    mate_limit: Option<u32>,
+    /// Set while iterating up to `params.min_depth`: the clock cannot abort the search
+    min_depth_pending: bool,
+    /// Root lines searched and reported per iteration (`MultiPV`)
+    multi_pv: usize,
+    /// `UCI_Chess960`: info lines write castling as king-takes-rook
+    pub chess960: bool,
+    /// Root moves skipped by the root search (the lines already found this
+    /// iteration); the root neither cuts off on nor stores to the TT while set
+    root_excluded: PV,
 }
 
 impl Searcher {
@@ -168,11 +257,27 @@ impl Searcher {
             best_move: None,
             pv: smallvec![],
             nnue: None,
-            position_history: Vec::with_capacity(512),
+            nnue_endgame: None,
+            game_history: Vec::with_capacity(512),
             stable_move_count: 0,
             last_best_move: None,
             num_threads: 1,
-            is_helper: false,
+            params: SearchParamsTunable::default(),
+            profiling: false,
+            eval_stack: [Score::none(); MAX_PLY as usize],
+            nmp_min_ply: 0,
+            next_time_check: Cell::new(0),
+            halfmove_stack: [0; MAX_PLY as usize],
+            path_hashes: [0; MAX_PLY as usize],
+            path_moves: [None; MAX_PLY as usize],
+            currline: None,
+            next_currline: (0, 0),
+            iterations: IterationTimes::default(),
+            mate_limit: None,
+            min_depth_pending: false,
+            multi_pv: 1,
+            root_excluded: smallvec![],
+            chess960: false,
         }
     }
 
@@ -183,9 +288,13 @@ impl Searcher {
         s
     }
     
-    /// Set number of search threads
+    /// Set number of search threads (1..=`max_threads()`).
+    ///
+    /// One thread is the fast path: no helpers are spawned and nothing but the
+    /// main thread touches the table. Its accesses are `Relaxed` atomics, which
+    /// are plain loads and stores (see `bench::run_tt_atomic_bench`).
     pub fn set_threads(&mut self, threads: usize) {
-        self.num_threads = threads.max(1).min(64);
+        self.num_threads = threads.clamp(1, max_threads());
     }
     
     /// Set hash table size in MB (recreates the TT)
@@ -204,26 +313,95 @@ impl Searcher {
         self.nnue = model;
     }
 
-    /// Set the position to search with history for repetition detection
+    /// Set endgame NNUE model
+    pub fn set_nnue_endgame(&mut self, model: Option<nnue::Model>) {
+        self.nnue_endgame = model;
+    }
+
+    /// Set the position to search, with no game history before it
     pub fn set_position(&mut self, board: Board) {
-        self.position_history.clear();
-        self.position_history.push(board.hash());
+        self.game_history.clear();
+        self.path_hashes[0] = board.hash();
         self.board = board;
+        self.halfmove_stack[0] = 0;
+    }
+
+    /// Set the root halfmove clock (plies since the last capture or pawn move)
+    pub fn set_halfmove_clock(&mut self, clock: u32) {
+        self.halfmove_stack[0] = clock.min(u16::MAX as u32) as u16;
     }
     
-    /// Set position with move history for repetition detection
+    /// Forget everything learned in the previous game (`ucinewgame`).
+    ///
+    /// Clears the TT and the per-thread tables in place: hash size, threads,
+    /// networks and parameters stay, and nothing is reallocated. Between moves
+    /// of the same game `search_with_sink` only ages history, clears killers
+    /// and bumps the TT generation.
+    pub fn new_game(&mut self) {
+        self.shared.tt.clear();
+        self.killers.clear();
+        self.history.clear();
+        self.countermoves.clear();
+        self.correction.clear();
+        self.iterations.clear();
+        self.game_history.clear();
+        self.board = Board::default();
+        self.best_move = None;
+        self.pv.clear();
+        self.stable_move_count = 0;
+        self.last_best_move = None;
+        self.stats = SearchStats::default();
+    }
+
+    /// Set the position to search together with the hashes of the game
+    /// positions played before it (oldest first, not including `board`).
+    ///
+    /// Follow with `set_halfmove_clock`: repetitions are only looked for that
+    /// far back.
     pub fn set_position_with_history(&mut self, board: Board, history: Vec<u64>) {
-        self.position_history = history;
-        self.position_history.push(board.hash());
+        self.game_history = history;
+        self.path_hashes[0] = board.hash();
         self.board = board;
     }
-    
-    /// Check if position has repeated (for draw detection)
-    /// Check if position has repeated (for draw detection)
-    pub fn is_repetition(&self, hash: u64) -> bool {
-        // Iterate backwards to find recent repetitions (more likely)
-        // using any() to stop at first match
-        self.position_history.iter().rev().any(|&h| h == hash)
+
+    /// Whether the position `hash` reached at `ply` with halfmove clock
+    /// `halfmove` is drawn by repetition.
+    ///
+    /// Repeating anything on the search path (the root included) is a draw:
+    /// the side that allowed it could repeat again. A position from the game
+    /// before the root only draws on its third occurrence, i.e. when it was
+    /// already played twice; a single earlier occurrence is a mere two-fold.
+    ///
+    /// Nothing before the last capture or pawn move can repeat, so the scan
+    /// goes back `halfmove` plies at most, looking only at every second
+    /// position (the same side to move).
+    pub fn is_repetition(&self, hash: u64, ply: Ply, halfmove: u32) -> bool {
+        let ply = ply.as_index().min(MAX_PLY as usize);
+        let reach = halfmove as usize;
+
+        // The search path: `ply - 2`, `ply - 4`, ... back to the root
+        let on_path = (2..=reach.min(ply)).step_by(2).any(|back| self.path_hashes[ply - back] == hash);
+        if on_path {
+            return true;
+        }
+
+        // The game before the root: entry `len - k` is `ply + k` plies back
+        let first = if ply % 2 == 0 { 2 } else { 1 };
+        let game_reach = reach.saturating_sub(ply).min(self.game_history.len());
+        (first..=game_reach)
+            .step_by(2)
+            .filter(|&k| self.game_history[self.game_history.len() - k] == hash)
+            .nth(1)
+            .is_some()
+    }
+
+    /// Draw score from the `Contempt` option for the side to move in `board`:
+    /// a draw costs the engine (the side to move at the root) `contempt`
+    /// centipawns and is worth as much to its opponent
+    #[inline]
+    pub fn contempt_draw(&self, board: &Board) -> Score {
+        let contempt = self.params.contempt;
+        Score::cp(if board.turn() == self.board.turn() { -contempt } else { contempt })
     }
 
     /// Get current statistics
@@ -231,6 +409,11 @@ impl Searcher {
         &self.stats
     }
 
+    /// Return the statistics collected so far and reset them
+    pub fn take_stats(&mut self) -> SearchStats {
+        core::mem::take(&mut self.stats)
+    }
+
     /// Get best move found
     pub fn best_move(&self) -> Option<Move> {
         self.best_move
@@ -253,10 +436,27 @@ impl Searcher {
             return true;
         }
         
-        // Check time periodically (every 512 nodes for stricter timing)
-        // More frequent checks help prevent time losses in movetime mode
-        if self.stats.nodes & 511 == 0 {
+        let nodes = self.stats.nodes;
+
+        // `go nodes`: cheap enough to test on every call (infinite searches included)
+        if self.time_manager.nodes_exceeded(nodes) {
+            self.shared.stop.store(true, Ordering::Relaxed);
+            return true;
+        }
+
+        // Below MinDepth the clock cannot end the search
+        if self.min_depth_pending {
+            return false;
+        }
+
+        // Only read the clock every TIME_CHECK_INTERVAL nodes: `Instant::now()`
+        // per node is measurable in NPS
+        if nodes >= self.next_time_check.get() {
+            self.next_time_check.set(nodes + TIME_CHECK_INTERVAL);
             if self.time_manager.hard_limit_exceeded() {
+                // Latch the decision so later calls (and other threads) see it
+                // without touching the clock again
+                self.shared.stop.store(true, Ordering::Relaxed);
                 return true;
             }
         }
@@ -270,8 +470,12 @@ impl Searcher {
             return false;
         }
         
+        if self.time_manager.nodes_exceeded(self.stats.nodes) {
+            return false;
+        }
+
         // Check soft limit
-        if !self.time_manager.can_start_iteration() {
+        if !self.time_manager.can_start_iteration(self.iterations.predict_next_ms()) {
             return false;
         }
         
@@ -282,7 +486,8 @@ impl Searcher {
             let elapsed = self.time_manager.elapsed();
             let soft = self.time_manager.soft_limit_ms();
             // Only stop early if we've used at least 40% of our soft limit
-            if elapsed > (soft * 2) / 5 {
+            // (soft is u64::MAX for depth/node-limited searches)
+            if elapsed > soft.saturating_mul(2) / 5 {
                 return false;
             }
         }
@@ -291,6 +496,7 @@ impl Searcher {
     }
     
     /// Create a helper searcher that shares TT but has own tables
+    #[cfg(feature = "std")]
     fn create_helper(&self) -> Self {
         Self {
             board: self.board.clone(),
@@ -299,21 +505,44 @@ impl Searcher {
             history: HistoryTable::new(),
             countermoves: CounterMoveTable::new(),
             correction: CorrectionHistoryTable::new(),
-            time_manager: self.time_manager.clone(),
+            time_manager: TimeManager::new(),
             stats: SearchStats::default(),
             best_move: None,
             pv: smallvec![],
             nnue: self.nnue.clone(),
-            position_history: self.position_history.clone(),
+            nnue_endgame: self.nnue_endgame.clone(),
+            game_history: self.game_history.clone(),
             stable_move_count: 0,
             last_best_move: None,
             num_threads: 1,
-            is_helper: true,
+            params: self.params.clone(),
+            profiling: false,
+            eval_stack: [Score::none(); MAX_PLY as usize],
+            nmp_min_ply: 0,
+            next_time_check: Cell::new(0),
+            halfmove_stack: self.halfmove_stack,
+            path_hashes: self.path_hashes,
+            path_moves: [None; MAX_PLY as usize],
+            currline: None,
+            next_currline: (0, 0),
+            iterations: IterationTimes::default(),
+            mate_limit: self.mate_limit,
+            min_depth_pending: false,
+            multi_pv: 1,
+            root_excluded: smallvec![],
+            chess960: self.chess960,
         }
     }
 
-    /// Run the search with given limits (with Lazy SMP multi-threading)
-    pub fn search(&mut self, limits: SearchLimits) -> SearchResult {
+    /// Run the search with given limits (with Lazy SMP multi-threading),
+    /// printing UCI info to stdout
+    #[cfg(feature = "std")]
+    pub fn search(&mut self, limits: SearchLimits) -> SearchOutcome {
+        self.search_with_sink(limits, &mut UciInfoSink)
+    }
+
+    /// Run the search with given limits, sending progress to `sink`
+    pub fn search_with_sink(&mut self, limits: SearchLimits, sink: &mut dyn InfoSink) -> SearchOutcome {
         // Reset state
         self.shared.stop.store(false, Ordering::Relaxed);
         self.shared.total_nodes.store(0, Ordering::Relaxed);
@@ -322,6 +551,9 @@ impl Searcher {
         self.pv.clear();
         self.stable_move_count = 0;
         self.last_best_move = None;
+        self.mate_limit = limits.mate;
+        self.multi_pv = limits.multi_pv.max(1);
+        self.next_currline = (0, 0);
         
         // Increment TT generation for new search
         self.shared.tt.new_search();
@@ -333,178 +565,50 @@ impl Searcher {
         self.history.age();
         
         // Configure time management
-        self.time_manager = TimeManager::from_limits(&limits, self.board.turn());
+        let time_manager = TimeManager::from_limits(&limits, self.board.turn());
         
         let max_depth = limits.depth.unwrap_or(Depth::MAX);
         
         // Spawn helper threads for Lazy SMP
+        #[cfg(feature = "std")]
         let mut handles = Vec::new();
         
+        #[cfg(feature = "std")]
         if self.num_threads > 1 {
             for _ in 1..self.num_threads {
                 let mut helper = self.create_helper();
-                let limits_clone = limits.clone();
-                let max_d = max_depth;
+                let board = self.board;
+                let tm = time_manager.clone();
                 
                 let handle = thread::spawn(move || {
-                    helper.search_internal(limits_clone, max_d);
+                    driver::iterative_deepening(&mut helper, &board, max_depth, tm, &mut NullInfoSink);
                 });
                 handles.push(handle);
             }
         }
         
         // Main thread search (prints UCI output)
-        let result = self.search_internal(limits, max_depth);
+        let board = self.board;
+        let mut result = driver::iterative_deepening(self, &board, max_depth, time_manager, sink);
         
         // Signal all helpers to stop
         self.shared.stop.store(true, Ordering::Relaxed);
         
         // Wait for all helper threads
+        #[cfg(feature = "std")]
         for handle in handles {
             let _ = handle.join();
         }
         
         // Get total nodes from all threads
         self.stats.nodes = self.shared.total_nodes.load(Ordering::Relaxed);
+        result.stats.nodes = self.stats.nodes;
+        result.update_nps();
+        result.hashfull = self.shared.tt.hashfull();
         
         result
     }
     
-    /// Internal search loop (called by main and helper threads)
-    fn search_internal(&mut self, _limits: SearchLimits, max_depth: Depth) -> SearchResult {
-        let mut best_score = Score::neg_infinity();
-        const INITIAL_WINDOW: i32 = 25;
-        
-        // Initialize evaluator at root
-        let local_nnue = self.nnue.clone();
-        let mut root_evaluator = SearchEvaluator::new(local_nnue.as_ref(), &self.board);
-
-        for depth in 1..=max_depth.raw() {
-            // Check if we can start a new iteration
-            if !self.can_start_new_iteration() {
-                break;
-            }
-            
-            // Early termination: only stop if we found a mate within the current depth.
-            // If mate distance > depth, deeper search might find a shorter mate (or longer defense).
-            // Example: At depth 3, finding "mate in 5" should NOT stop search,
-            //          but "mate in 2" (within depth 3) can stop.
-            // Same for mated scores: "mated in 5" at depth 3 should continue to find defenses.
-            if best_score.is_mate_score() && self.best_move.is_some() {
-                if let Some(plies_to_mate) = best_score.mate_distance() {
-                    // Only stop if the mate is achievable within remaining search depth
-                    // plies_to_mate represents plies from root, so if it's <= depth, we're done
-                    if plies_to_mate <= depth {
-                        break;
-                    }
-                }
-            }
-
-            // Aspiration window: use previous score +/- delta after depth 1
-            let mut delta = INITIAL_WINDOW;
-            let mut alpha = if depth > 1 && !best_score.is_mate() { 
-                best_score - Score::cp(delta) 
-            } else { 
-                Score::neg_infinity() 
-            };
-            let mut beta = if depth > 1 && !best_score.is_mate() { 
-                best_score + Score::cp(delta) 
-            } else { 
-                Score::infinity() 
-            };
-
-            // Aspiration loop: widen window on fail-high/low
-            loop {
-                let result = negamax::search::<Root>(
-                    self,
-                    &mut root_evaluator,
-                    &self.board.clone(),
-                    Depth::new(depth),
-                    Ply::ZERO,
-                    alpha,
-                    beta,
-                    None,  // No prev move at root
-                );
-
-                if self.should_stop() {
-                    break;
-                }
-
-                // Check if score is within window
-                if result.score <= alpha {
-                    // Fail-low: widen alpha
-                    alpha = Score::neg_infinity();
-                } else if result.score >= beta {
-                    // Fail-high: widen beta
-                    beta = Score::infinity();
-                } else {
-                    // Score within window, accept result
-                    if let Some(m) = result.best_move {
-                        self.best_move = Some(m);
-                        best_score = result.score;
-                        self.pv = result.pv.clone();
-                    }
-                    break;
-                }
-
-                // Widen window for next attempt
-                delta *= 2;
-                if delta > 500 {
-                    alpha = Score::neg_infinity();
-                    beta = Score::infinity();
-                }
-            }
-
-            self.stats.depth = Depth::new(depth);
-            self.stats.hashfull = self.shared.tt.hashfull();
-            
-            // Update time from time manager
-            self.stats.time_ms = self.time_manager.elapsed();
-            
-            // Report nodes to shared counter
-            self.shared.total_nodes.fetch_add(self.stats.nodes, Ordering::Relaxed);
-            
-            // Track move stability for early termination
-            if self.best_move == self.last_best_move {
-                self.stable_move_count += 1;
-            } else {
-                self.stable_move_count = 0;
-                self.last_best_move = self.best_move;
-            }
-
-            // Print info for this depth (main thread only)
-            if !self.is_helper && !self.should_stop() {
-                self.stats.print_profiling();
-                self.stats.time_search = (self.time_manager.elapsed() as u64) * 1_000_000;
-                let pv_str: String = self.pv.iter()
-                    .map(|m| m.to_string())
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                    
-                println!(
-                    "info depth {} seldepth {} score {} nodes {} qnodes {} evals {} nps {} time {} hashfull {} pv {}",
-                    depth,
-                    self.stats.seldepth.raw(),
-                    best_score,
-                    self.shared.total_nodes.load(Ordering::Relaxed),
-                    self.stats.qnodes,
-                    self.stats.eval_calls,
-                    self.stats.nps(),
-                    self.stats.time_ms,
-                    self.stats.hashfull,
-                    pv_str
-                );
-            }
-        }
-
-        SearchResult {
-            best_move: self.best_move,
-            score: best_score,
-            pv: self.pv.clone(),
-            stats: self.stats.clone(),
-        }
-    }
-
     /// Increment node counter
     #[inline]
     pub fn inc_nodes(&mut self) {
@@ -534,12 +638,88 @@ impl Searcher {
         self.stats.time_order += ns;
     }
 
+    /// Record the static eval of the node at `ply`
+    #[inline]
+    pub fn set_static_eval(&mut self, ply: Ply, eval: Score) {
+        if let Some(slot) = self.eval_stack.get_mut(ply.as_index()) {
+            *slot = eval;
+        }
+    }
+
+    /// Static eval recorded at `ply` on the current path
+    #[inline]
+    pub fn static_eval_at(&self, ply: Ply) -> Score {
+        self.eval_stack.get(ply.as_index()).copied().unwrap_or(Score::none())
+    }
+
+    /// Halfmove clock of the position at `ply` on the current path
+    #[inline]
+    pub fn halfmove_at(&self, ply: Ply) -> u32 {
+        self.halfmove_stack.get(ply.as_index()).map_or(0, |&c| c as u32)
+    }
+
+    /// Record the halfmove clock of the position at `ply`
+    #[inline]
+    pub fn set_halfmove_at(&mut self, ply: Ply, clock: u32) {
+        if let Some(slot) = self.halfmove_stack.get_mut(ply.as_index()) {
+            *slot = clock.min(u16::MAX as u32) as u16;
+        }
+    }
+
+    /// Record the hash of the position at `ply` on the current path
+    #[inline]
+    pub fn set_path_hash(&mut self, ply: Ply, hash: u64) {
+        if let Some(slot) = self.path_hashes.get_mut(ply.as_index()) {
+            *slot = hash;
+        }
+    }
+
+    /// Record the move played from `ply` on the current path (`None`: null move)
+    #[inline]
+    pub fn set_path_move(&mut self, ply: Ply, m: Option<Move>) {
+        if let Some(slot) = self.path_moves.get_mut(ply.as_index()) {
+            *slot = m;
+        }
+    }
+
+    /// Send `info currline 1 <moves to ply>` if `UCI_ShowCurrLine` is on and
+    /// the interval has passed. The clock is read every `TIME_CHECK_INTERVAL`
+    /// nodes at most.
+    pub fn report_currline(&mut self, ply: Ply) {
+        let Some(report) = self.currline else { return };
+        if ply == Ply::ZERO {
+            return;
+        }
+        let (next_nodes, next_ms) = self.next_currline;
+        if self.stats.nodes < next_nodes {
+            return;
+        }
+        let elapsed = self.time_manager.elapsed();
+        self.next_currline.0 = self.stats.nodes + TIME_CHECK_INTERVAL;
+        if elapsed < next_ms {
+            return;
+        }
+        self.next_currline.1 = elapsed + CURRLINE_INTERVAL_MS;
+
+        let line: Vec<String> = self.path_moves[..ply.as_index().min(MAX_PLY as usize)]
+            .iter()
+            .map(|m| m.map_or_else(|| "0000".to_string(), |m| m.to_string()))
+            .collect();
+        report(&format!("info currline 1 {}", line.join(" ")));
+    }
+
     /// Increment qnodes counter
     #[inline]
     pub fn inc_qnodes(&mut self) {
         self.stats.qnodes += 1;
     }
 
+    /// Increment TT hit counter
+    #[inline]
+    pub fn inc_tt_hits(&mut self) {
+        self.stats.tt_hits += 1;
+    }
+
     /// Increment eval call counter
     #[inline]
     pub fn inc_eval_calls(&mut self) {
@@ -558,3 +738,221 @@ impl Default for Searcher {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_stats_resets() {
+        let mut searcher = Searcher::new();
+        searcher.set_position(Board::default());
+        let result = searcher.search(SearchLimits::depth(5));
+
+        let stats = searcher.take_stats();
+        assert!(stats.nodes > 0);
+        assert!(stats.qnodes > 0);
+        assert_eq!(stats.depth, Depth::new(5));
+        // Single thread: the shared counter matches the thread's own count
+        assert_eq!(stats.nodes, result.stats.nodes);
+
+        let after = searcher.stats();
+        assert_eq!(after.nodes, 0);
+        assert_eq!(after.qnodes, 0);
+        assert_eq!(after.time_eval, 0);
+    }
+
+    #[test]
+    fn test_repetition_scan_stops_at_irreversible_move() {
+        let (h, u, v) = (0x1111, 0x2222, 0x3333);
+        let mut searcher = Searcher::new();
+
+        // On the path: `h` at the root, reached again at ply 4
+        searcher.set_position(Board::default());
+        searcher.set_path_hash(Ply::ZERO, h);
+        searcher.set_path_hash(Ply::new(2), u);
+        assert!(searcher.is_repetition(h, Ply::new(4), 4));
+        // A capture at ply 1 or later: the root is out of reach
+        assert!(!searcher.is_repetition(h, Ply::new(4), 3));
+        // Odd distances are the other side to move and never looked at
+        assert!(!searcher.is_repetition(u, Ply::new(3), 3));
+
+        // Before the root: `h` was played two and four plies before ply 1,
+        // so reaching it there is its third occurrence
+        searcher.set_position_with_history(Board::default(), vec![u, h, v, h]);
+        assert!(searcher.is_repetition(h, Ply::new(1), 4));
+        // The older occurrence is behind the last irreversible move: two-fold only
+        assert!(!searcher.is_repetition(h, Ply::new(1), 3));
+    }
+
+    #[test]
+    fn test_seldepth_includes_qsearch() {
+        // Lots of hanging pieces: quiescence runs well past the nominal depth
+        let mut searcher = Searcher::new();
+        searcher.set_position(
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap(),
+        );
+        let result = searcher.search(SearchLimits::depth(3));
+        assert!(result.stats.seldepth.raw() > 3);
+
+        // Reset per iteration: the start position at depth 1 goes no deeper than
+        // a couple of qsearch plies
+        searcher.set_position(Board::default());
+        let result = searcher.search(SearchLimits::depth(1));
+        assert!(result.stats.seldepth.raw() >= 1);
+        assert!(result.stats.seldepth.raw() < 4);
+    }
+
+    #[test]
+    fn test_time_check_granularity() {
+        let mut searcher = Searcher::new();
+        let limits = SearchLimits { movetime: Some(1), move_overhead: 0, ..Default::default() };
+        searcher.time_manager = TimeManager::from_limits(&limits, crate::types::Color::White);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        // First call reads the clock and latches the stop flag
+        assert!(searcher.should_stop());
+        assert!(searcher.shared.stop.load(Ordering::Relaxed));
+
+        // With the flag cleared, the clock is not read again until the next interval
+        searcher.shared.stop.store(false, Ordering::Relaxed);
+        for nodes in 1..TIME_CHECK_INTERVAL {
+            searcher.stats.nodes = nodes;
+            assert!(!searcher.should_stop());
+        }
+        searcher.stats.nodes = TIME_CHECK_INTERVAL;
+        assert!(searcher.should_stop());
+
+        // The atomic flag is honoured immediately, whatever the node count
+        searcher.shared.stop.store(true, Ordering::Relaxed);
+        searcher.stats.nodes += 1;
+        assert!(searcher.should_stop());
+    }
+
+    #[test]
+    fn test_consecutive_searches_reuse_tables() {
+        use crate::types::{Color, MoveFlag, Square};
+
+        let mut searcher = Searcher::new();
+        searcher.set_position(Board::default());
+        searcher.search_with_sink(SearchLimits::depth(3), &mut NullInfoSink);
+        let generation = searcher.shared.tt.generation();
+
+        // A move the next search cannot touch, and a killer far beyond its reach
+        let h1h8 = Move::new(Square::from_index(7).unwrap(), Square::from_index(63).unwrap(), MoveFlag::Quiet);
+        searcher.history.update(Color::White, h1h8, 400);
+        let before = searcher.history.get(Color::White, h1h8);
+        searcher.killers.store(Ply::new(100), h1h8);
+
+        searcher.search_with_sink(SearchLimits::depth(3), &mut NullInfoSink);
+        assert_eq!(searcher.history.get(Color::White, h1h8), before / 2);
+        assert_eq!(searcher.killers.get(Ply::new(100)), [None, None]);
+        assert_ne!(searcher.shared.tt.generation(), generation);
+        assert!(searcher.shared.tt.hashfull() > 0);
+
+        // A new game clears them, keeping the table size
+        let size = searcher.shared.tt.size_mb();
+        searcher.new_game();
+        assert_eq!(searcher.history.get(Color::White, h1h8), 0);
+        assert_eq!(searcher.shared.tt.hashfull(), 0);
+        assert_eq!(searcher.shared.tt.size_mb(), size);
+    }
+
+    #[test]
+    fn test_equal_root_moves_break_ties_deterministically() {
+        // Bare kings: every move draws
+        let board = Board::from_fen("8/8/4k3/8/8/4K3/8/8 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        searcher.set_position(board);
+        let first = searcher.search_with_sink(SearchLimits::depth(6), &mut NullInfoSink);
+        // History and TT from the first search change the move order, not the answer
+        let second = searcher.search_with_sink(SearchLimits::depth(6), &mut NullInfoSink);
+        assert_eq!(first.score, second.score);
+        assert_eq!(first.best_move, second.best_move);
+
+        let mut fresh = Searcher::new();
+        fresh.set_position(board);
+        assert_eq!(fresh.search_with_sink(SearchLimits::depth(5), &mut NullInfoSink).best_move, first.best_move);
+    }
+
+    #[test]
+    fn test_infinite_search_honours_node_limit() {
+        let mut searcher = Searcher::new();
+        searcher.set_position(Board::default());
+        let limits = SearchLimits { infinite: true, nodes: Some(50_000), ..SearchLimits::new() };
+        let result = searcher.search_with_sink(limits, &mut NullInfoSink);
+
+        assert!(result.best_move.is_some());
+        assert!(result.stats.nodes >= 50_000);
+        assert!(result.stats.nodes < 50_000 + TIME_CHECK_INTERVAL);
+    }
+
+    #[test]
+    fn test_min_depth_does_not_override_node_limit() {
+        // MinDepth only holds off the clock: `go nodes` still ends the search
+        let mut searcher = Searcher::new();
+        assert!(searcher.params.set("MinDepth", 30));
+        searcher.set_position(Board::default());
+        let limits = SearchLimits { nodes: Some(20_000), ..SearchLimits::new() };
+        let result = searcher.search_with_sink(limits, &mut NullInfoSink);
+
+        assert!(result.best_move.is_some());
+        assert!(result.stats.nodes < 20_000 + TIME_CHECK_INTERVAL);
+        assert!(result.stats.depth.raw() < 30);
+    }
+
+    #[test]
+    fn test_currline_reported_during_timed_search() {
+        static LINES: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+        let mut searcher = Searcher::new();
+        searcher.set_position(Board::default());
+        searcher.currline = Some(|line: &str| LINES.lock().unwrap().push(line.to_string()));
+        let limits = SearchLimits { movetime: Some(200), ..SearchLimits::new() };
+        searcher.search_with_sink(limits, &mut NullInfoSink);
+
+        let lines = LINES.lock().unwrap();
+        assert!(!lines.is_empty());
+        let moves: Vec<&str> = lines[0].strip_prefix("info currline 1 ").unwrap().split(' ').collect();
+        assert!(Board::default().generate_moves().iter().any(|m| m.to_string() == moves[0]));
+
+        // Off by default
+        assert!(Searcher::new().currline.is_none());
+    }
+
+    #[test]
+    fn test_exhausted_clock_still_returns_a_move() {
+        // 1ms left and a larger move overhead: every time limit is already past
+        let mut searcher = Searcher::new();
+        searcher.set_position(Board::default());
+        let limits = SearchLimits { wtime: Some(1), btime: Some(1), ..SearchLimits::new() };
+        let result = searcher.search_with_sink(limits, &mut NullInfoSink);
+
+        let best = result.best_move.expect("a move despite the clock");
+        assert!(Board::default().generate_moves().iter().any(|m| m == best));
+        assert!(result.stats.depth.raw() >= 1);
+
+        // MaxDepth caps `go depth`
+        searcher.params.set("MaxDepth", 3);
+        let result = searcher.search_with_sink(SearchLimits::depth(10), &mut NullInfoSink);
+        assert_eq!(result.stats.depth, Depth::new(3));
+    }
+
+    /// Search `fen` and format the score the way the `info` line does
+    fn uci_score(fen: &str, depth: i32) -> String {
+        let mut searcher = Searcher::new();
+        searcher.set_position(Board::from_fen(fen).unwrap());
+        let result = searcher.search(SearchLimits::depth(depth));
+        format!("score {}", result.score.display(crate::types::ScoreScale::Normalized))
+    }
+
+    #[test]
+    fn test_uci_mate_scores() {
+        // Ra8#
+        assert_eq!(uci_score("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 4), "score mate 1");
+        // Ra7 Kg8 Rb8#
+        assert_eq!(uci_score("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", 5), "score mate 2");
+        // Kb8 forced, then Rh8#
+        assert_eq!(uci_score("k7/8/1K6/8/8/8/8/7R b - - 0 1", 4), "score mate -1");
+    }
+}
@@ -2,7 +2,8 @@
 //!
 //! This is the core search algorithm with:
 //! - Transposition table probing and storing
-//! - Alpha-beta pruning
+//! - Fail-soft alpha-beta pruning (scores outside the window are returned as-is
+//!   and stored with the matching bound)
 //! - Quiescence search for captures
 //! - Compile-time node type specialization (no runtime PV checks)
 //!
@@ -10,15 +11,46 @@
 //! See `node_types` module for `NodeType` trait and concrete types.
 
 use super::{Searcher, SearchStats, ordering, qsearch, see};
+use super::contempt::{self, DrawKind};
+use super::correction::material_key;
 use super::node_types::{NodeType, OffPV};
-use super::tt::BoundType;
-use crate::types::{Board, Move, Score, Depth, Ply, Piece, SCORE_MATE};
-use crate::eval::SearchEvaluator;
+use super::tt::{BoundType, TtProbe, TtStore};
+use crate::types::{Board, Move, Score, Depth, Ply, Piece, MAX_PLY, SCORE_MATE, is_insufficient_material, next_halfmove_clock};
+use crate::eval::{endgame, SearchEvaluator};
 use smallvec::{SmallVec, smallvec};
+use alloc::sync::Arc;
 
 /// Type alias for PV storage - stack-allocated for typical depths
 pub type PV = SmallVec<[Move; 32]>;
 
+/// Null-move cutoffs are verified when the side to move has at most this many
+/// non-pawn pieces (zugzwang gets likely once few pieces are left)
+const NMP_VERIFY_MAX_PIECES: u32 = 2;
+
+/// Knights, bishops, rooks and queens of the side to move
+#[inline]
+fn non_pawn_pieces(board: &Board) -> u32 {
+    let ours = board.color_bb(board.turn());
+    let non_pawn = (board.piece_bb(Piece::Knight)
+        | board.piece_bb(Piece::Bishop)
+        | board.piece_bb(Piece::Rook)
+        | board.piece_bb(Piece::Queen)) & ours;
+    non_pawn.count()
+}
+
+/// Should a null-move cutoff be verified with a normal search in this position
+#[inline]
+fn needs_null_verification(board: &Board) -> bool {
+    non_pawn_pieces(board) <= NMP_VERIFY_MAX_PIECES
+}
+
+/// Root tie-break: of two moves with the same score keep the one with the
+/// lower encoding, not whichever the (history-dependent) ordering tried first
+#[inline]
+fn wins_root_tie(m: Move, best: Option<Move>) -> bool {
+    best.is_some_and(|b| m.bits() < b.bits())
+}
+
 /// Result from a search
 #[derive(Debug, Clone)]
 pub struct SearchResult {
@@ -28,7 +60,8 @@ pub struct SearchResult {
     pub stats: SearchStats,
 }
 
-/// Main negamax search function with TT integration and null move pruning.
+/// Main negamax search function with TT integration and null move pruning,
+/// using the searcher's shared transposition table.
 ///
 /// Uses compile-time node type specialization via the `NodeType` trait.
 /// - `NT::PV`: true if this is a principal variation node
@@ -40,35 +73,59 @@ pub fn search<NT: NodeType>(
     board: &Board,
     depth: Depth,
     ply: Ply,
+    alpha: Score,
+    beta: Score,
+    prev_move: Option<Move>,
+) -> SearchResult {
+    let shared = Arc::clone(&searcher.shared);
+    search_with_tt::<NT, _>(searcher, &shared.tt, evaluator, board, depth, ply, alpha, beta, prev_move)
+}
+
+/// `search` against any table implementing `TtProbe` + `TtStore`.
+///
+/// The table is threaded through the recursion rather than read from
+/// `searcher.shared`, so tests can hand in a table that records what the
+/// search asks for and what it stores.
+pub fn search_with_tt<NT: NodeType, T: TtProbe + TtStore>(
+    searcher: &mut Searcher,
+    tt: &T,
+    evaluator: &mut SearchEvaluator,
+    board: &Board,
+    depth: Depth,
+    ply: Ply,
     mut alpha: Score,
     mut beta: Score,
     prev_move: Option<Move>,
 ) -> SearchResult {
     searcher.inc_nodes();
+
+    // === Ply Limit ===
+    // Extensions could otherwise recurse past every per-ply table
+    if ply.raw() >= MAX_PLY {
+        return SearchResult {
+            best_move: None,
+            score: evaluator.evaluate(board),
+            pv: smallvec![],
+            stats: searcher.stats().clone(),
+        };
+    }
+
     searcher.update_seldepth(ply);
 
     let hash = board.hash();
+    let halfmove = searcher.halfmove_at(ply);
+    evaluator.set_halfmove_clock(halfmove);
+    searcher.set_path_hash(ply, hash);
+    searcher.report_currline(ply);
 
     // === Repetition Detection with Contempt ===
-    // Check for draw by repetition (position seen before in game history)
+    // Draw by repetition: a repeat since the root, or a game position's third
+    // occurrence, since the last irreversible move (see `Searcher::is_repetition`)
     // Use contempt: avoid draws when winning, seek draws when losing
     // Skip at root node (ply == 0)
-    if !NT::ROOT && searcher.is_repetition(hash) {
-        // Contempt factor: small penalty/bonus for draws based on expected score
-        // If alpha > 0 (we expect to be winning), penalize draws to avoid them
-        // If beta < 0 (we expect to be losing), reward draws to seek them
-        const CONTEMPT: i32 = 10; // Small contempt factor (centipawns)
-        
-        let draw_score = if alpha.raw() > CONTEMPT {
-            // We're winning - penalize draws to avoid repetition
-            Score::cp(-CONTEMPT)
-        } else if beta.raw() < -CONTEMPT {
-            // We're losing - reward draws to seek repetition  
-            Score::cp(CONTEMPT)
-        } else {
-            // Close to equal - treat as pure draw
-            Score::draw()
-        };
+    if !NT::ROOT && searcher.is_repetition(hash, ply, halfmove) {
+        // Contempt shrinks as material comes off (see `contempt`)
+        let draw_score = contempt::draw_score(DrawKind::Repetition, board, alpha, beta) + searcher.contempt_draw(board);
         
         return SearchResult {
             best_move: None,
@@ -78,6 +135,29 @@ pub fn search<NT: NodeType>(
         };
     }
 
+    // === Fifty-Move Rule ===
+    // 100 plies without a capture or pawn move is a draw, unless the position
+    // is already checkmate
+    if !NT::ROOT && halfmove >= 100 && (!board.in_check() || !board.generate_moves().is_empty()) {
+        return SearchResult {
+            best_move: None,
+            score: contempt::draw_score(DrawKind::FiftyMove, board, alpha, beta) + searcher.contempt_draw(board),
+            pv: smallvec![],
+            stats: searcher.stats().clone(),
+        };
+    }
+
+    // === Insufficient Material ===
+    // Nobody can mate any more: a draw whatever is played
+    if !NT::ROOT && is_insufficient_material(board) {
+        return SearchResult {
+            best_move: None,
+            score: searcher.contempt_draw(board),
+            pv: smallvec![],
+            stats: searcher.stats().clone(),
+        };
+    }
+
     // Mate distance pruning
     let mate_score = SCORE_MATE - ply.raw() as i32;
     let mated_score = -SCORE_MATE + ply.raw() as i32;
@@ -109,8 +189,18 @@ pub fn search<NT: NodeType>(
     let orig_alpha = alpha;
     let mut tt_move: Option<Move> = None;
 
+    // MultiPV: root moves of the lines already found are skipped. The root
+    // entry in the TT is about the best line, so it neither cuts off here
+    // nor gets overwritten by a lesser one
+    let excluded = if NT::ROOT { searcher.root_excluded.clone() } else { smallvec![] };
+    let use_tt = excluded.is_empty();
+
     // === TT Probe ===
-    if let Some(entry) = searcher.shared.tt.probe(hash) {
+    let tt_entry = tt.probe(hash).filter(|_| use_tt);
+    if let Some(entry) = tt_entry {
+        searcher.inc_tt_hits();
+        // The move is used for ordering whether or not the entry can cut off
+        // (shallower depth, or a bound on the wrong side of the window)
         tt_move = entry.best_move();
         
         // Only use TT score if depth is sufficient
@@ -165,50 +255,84 @@ pub fn search<NT: NodeType>(
     }
 
     let in_check = board.in_check();
-
-    // === Reverse Futility Pruning (RFP) ===
-    // If we are way ahead, we can prune without searching
-    // Distinct from standard Futility Pruning which prunes *moves*
-    let mut static_eval = None;
     let pawn_hash = board.pawn_hash();
+    let mat_key = material_key(board);
     let color = board.turn();
-    
-    if !in_check && depth.raw() <= 7 {
+
+    // === Static Eval ===
+    // Evaluated once per node and kept on the per-ply eval stack (`Score::none()`
+    // when in check), so later plies can tell whether our eval is improving
+    let static_eval = if in_check {
+        None
+    } else {
         #[cfg(debug_assertions)]
         searcher.inc_eval_calls();
-        #[cfg(debug_assertions)]
+        #[cfg(all(debug_assertions, feature = "std"))]
         let t_eval = std::time::Instant::now();
         let raw_eval = evaluator.evaluate(board);
-        #[cfg(debug_assertions)]
+        #[cfg(all(debug_assertions, feature = "std"))]
         searcher.add_eval_time(t_eval.elapsed().as_nanos() as u64);
         
         // Apply correction history adjustment
-        let correction = searcher.correction.get(color, pawn_hash);
-        let eval = raw_eval + Score::cp(correction / 4);
-        static_eval = Some(eval);
+        let correction = searcher.correction.correction(color, pawn_hash, mat_key);
+        Some(raw_eval + Score::cp(correction))
+    };
+    searcher.set_static_eval(ply, static_eval.unwrap_or(Score::none()));
 
-        // RFP Margin: 90 * depth (tuned)
-        let margin = Score::cp(90 * depth.raw() as i32);
-        
-        if eval - margin >= beta {
-             return SearchResult {
-                best_move: None,
-                score: eval - margin, // Soft cap to avoid crazy scores
-                pv: smallvec![],
-                stats: searcher.stats().clone(),
+    // Improving: our eval is better than two plies ago (our previous move)
+    let improving = match static_eval {
+        Some(eval) if ply.raw() >= 2 => {
+            let prev = searcher.static_eval_at(Ply::new(ply.raw() - 2));
+            prev != Score::none() && eval > prev
+        }
+        _ => false,
+    };
+
+    // The TT score is a better estimate than the static eval when its bound
+    // points the right way; use it for pruning decisions
+    let prune_eval = static_eval.map(|eval| match tt_entry {
+        Some(entry) => {
+            let tt_score = entry.score().from_tt(ply.raw());
+            let usable = !tt_score.is_mate_score() && match entry.bound() {
+                BoundType::Exact => true,
+                BoundType::LowerBound => tt_score > eval,
+                BoundType::UpperBound => tt_score < eval,
+                BoundType::None => false,
             };
+            if usable { tt_score } else { eval }
+        }
+        None => eval,
+    });
+
+    // === Reverse Futility Pruning (RFP) ===
+    // If we are way ahead, we can prune without searching
+    // Distinct from standard Futility Pruning which prunes *moves*
+    // (non-PV only; there is no eval to prune with in check)
+    if let Some(eval) = prune_eval {
+        if !NT::PV && depth.raw() <= searcher.params.rfp_max_depth {
+            // RFP Margin: 90 * depth (tuned), one ply less when improving
+            let margin = searcher.params.rfp_margin * (depth.raw() - improving as i32);
+            
+            if eval.sub_saturating(margin) >= beta {
+                 return SearchResult {
+                    best_move: None,
+                    score: eval.sub_saturating(margin), // Soft cap to avoid crazy scores
+                    pv: smallvec![],
+                    stats: searcher.stats().clone(),
+                };
+            }
         }
     }
 
     // === ProbCut ===
     // Only on non-PV nodes (zero-window)
-    const PROBCUT_MARGIN: i32 = 100;
     if !NT::PV && depth.raw() >= 5 && !in_check && beta.raw().abs() < (SCORE_MATE - 1000) {
-        let probe_beta = beta + Score::cp(PROBCUT_MARGIN);
+        let probe_beta = beta.add_saturating(searcher.params.probcut_margin);
         let probe_depth = Depth::new(depth.raw() - 4);
 
-        let result = search::<OffPV>(
+        let result = search_with_tt::<OffPV, T>(
             searcher,
+            tt,
             evaluator,
             board,
             probe_depth,
@@ -221,7 +345,7 @@ pub fn search<NT: NodeType>(
         if result.score >= probe_beta {
             return SearchResult {
                  best_move: result.best_move,
-                 score: beta,
+                 score: result.score,
                  pv: smallvec![],
                  stats: searcher.stats().clone()
             };
@@ -229,28 +353,31 @@ pub fn search<NT: NodeType>(
     }
 
     // === Null Move Pruning ===
-    // Skip if: in check, depth too low, PV node, or only king+pawns
-    // Note: we don't do NMP on PV nodes or at root
-    if !NT::PV && !in_check && depth.raw() >= 3 {
-        // Don't do null move in pure pawn endgames (zugzwang risk)
-        let dominated_by_pawns = (board.piece_bb(Piece::Knight)
-            | board.piece_bb(Piece::Bishop)
-            | board.piece_bb(Piece::Rook)
-            | board.piece_bb(Piece::Queen)).is_empty();
-        
-        if !dominated_by_pawns {
+    // Skip if: in check, depth too low, PV node, or the side to move has only
+    // king+pawns (zugzwang risk). Also off where the heuristic endgame eval
+    // decides: those positions are the zugzwang-prone ones.
+    // Note: we don't do NMP on PV nodes or at root, nor inside a verification search
+    if !NT::PV && !in_check && depth.raw() >= searcher.params.nmp_min_depth && ply.raw() >= searcher.nmp_min_ply {
+        let zugzwang_prone = non_pawn_pieces(board) == 0
+            || endgame::should_use_endgame(board, &searcher.params.endgame);
+
+        if !zugzwang_prone {
             // Reduction: R=5 if depth > 6, else R=4 (aggressive)
-            let r = if depth.raw() > 6 { 5 } else { 4 };
+            let r = searcher.params.nmp_reduction + (depth.raw() > 6) as i32;
             
-            // Create a null move board (pass the turn)
+            // Create a null move board (pass the turn, clear en passant).
+            // Copy-make: `board` itself is the "unmade" position.
             let null_board = board.make_null_move();
+            searcher.set_halfmove_at(ply.next(), halfmove + 1);
+            searcher.set_path_move(ply, None);
             
-            // Clone evaluator for null move (no piece updates needed)
-            let mut null_evaluator = evaluator.clone();
-            
-            let null_result = search::<OffPV>(
+            // No pieces moved, so the accumulators are unchanged and the evaluator
+            // can be shared: only the side-to-move perspective flips, which
+            // `evaluate` takes from `null_board.turn()`. Children clone before updating.
+            let null_result = search_with_tt::<OffPV, T>(
                 searcher,
-                &mut null_evaluator,
+                tt,
+                evaluator,
                 &null_board,
                 Depth::new((depth.raw() - 1 - r).max(0)),
                 ply.next(),
@@ -261,11 +388,33 @@ pub fn search<NT: NodeType>(
             
             let null_score = -null_result.score;
             
-            if null_score >= beta {
-                // Null move cutoff
+            // With little material the pass may be the only "good" move (zugzwang):
+            // verify with a reduced search that has null move disabled for its
+            // first plies before trusting the cutoff
+            let verified = null_score >= beta && (!needs_null_verification(board) || {
+                let verify_depth = Depth::new((depth.raw() - 1 - r).max(0));
+                let saved_min_ply = searcher.nmp_min_ply;
+                searcher.nmp_min_ply = ply.raw() + 3 * verify_depth.raw() / 4 + 1;
+                let verify_result = search_with_tt::<OffPV, T>(
+                    searcher,
+                    tt,
+                    evaluator,
+                    board,
+                    verify_depth,
+                    ply,
+                    beta - Score::cp(1),
+                    beta,
+                    prev_move,
+                );
+                searcher.nmp_min_ply = saved_min_ply;
+                verify_result.score >= beta
+            });
+
+            if verified {
+                // Null move cutoff (fail-soft, but never claim an unproven mate)
                 return SearchResult {
                     best_move: None,
-                    score: beta,
+                    score: if null_score.is_mate_score() { beta } else { null_score },
                     pv: smallvec![],
                     stats: searcher.stats().clone(),
                 };
@@ -273,38 +422,29 @@ pub fn search<NT: NodeType>(
         }
     }
 
-    // === Internal Iterative Deepening (IID) ===
-    // If we are at a PV node and have no TT move, search shallower to find one
-    if NT::PV && tt_move.is_none() && depth.raw() >= 6 {
-        let iid_depth = Depth::new(depth.raw() - 2);
-        
-        let result = search::<NT>(
-            searcher,
-            evaluator,
-            board,
-            iid_depth,
-            ply,
-            alpha,
-            beta,
-            prev_move,
-        );
-        
-        tt_move = result.best_move;
-    }
+    // === Internal Iterative Reductions (IIR) ===
+    // No TT move means weak ordering (and usually a node not worth much yet):
+    // search one ply shallower instead of paying for an IID search to find a move
+    let depth = if !NT::ROOT && tt_move.is_none() && depth.raw() >= searcher.params.iir_min_depth {
+        Depth::new(depth.raw() - 1)
+    } else {
+        depth
+    };
 
     // Generate legal moves
-    #[cfg(debug_assertions)]
+    #[cfg(all(debug_assertions, feature = "std"))]
     let t_gen = std::time::Instant::now();
     let mut moves = board.generate_moves();
-    #[cfg(debug_assertions)]
+    #[cfg(all(debug_assertions, feature = "std"))]
     searcher.add_gen_time(t_gen.elapsed().as_nanos() as u64);
 
-    // Check for checkmate or stalemate
+    // Check for checkmate or stalemate.
+    // Must come from move generation, not eval: a stalemated side can be far ahead.
     if moves.is_empty() {
         let score = if board.in_check() {
             Score::mated_in(ply.raw())
         } else {
-            Score::draw()
+            contempt::draw_score(DrawKind::Stalemate, board, alpha, beta) + searcher.contempt_draw(board)
         };
         return SearchResult {
             best_move: None,
@@ -316,7 +456,7 @@ pub fn search<NT: NodeType>(
 
     // Quiescence search at depth 0
     if depth.is_qs() {
-        return qsearch::quiescence::<NT>(searcher, evaluator, board, ply, 0, alpha, beta);
+        return qsearch::quiescence_with_tt::<NT, T>(searcher, tt, evaluator, board, ply, 0, alpha, beta);
     }
 
     // Get killers for this ply
@@ -327,33 +467,21 @@ pub fn search<NT: NodeType>(
     let counter_move = prev_move.and_then(|pm| searcher.countermoves.get(pm));
 
     // Order moves (TT, killers, counter-move, and history)
-    #[cfg(debug_assertions)]
+    #[cfg(all(debug_assertions, feature = "std"))]
     let t_order = std::time::Instant::now();
     ordering::order_moves_full(board, moves.as_slice_mut(), tt_move, killers, counter_move, &searcher.history, color);
-    #[cfg(debug_assertions)]
+    #[cfg(all(debug_assertions, feature = "std"))]
     searcher.add_order_time(t_order.elapsed().as_nanos() as u64);
 
-    // Static eval is already computed for RFP if depth <= 7
-    // If not (e.g. was in check check or deeper), compute it now if needed for Razoring/Futility
-    if static_eval.is_none() && depth.raw() <= 3 && !in_check {
-        #[cfg(debug_assertions)]
-        searcher.inc_eval_calls();
-        #[cfg(debug_assertions)]
-        let t_eval = std::time::Instant::now();
-        let val = evaluator.evaluate(board);
-        #[cfg(debug_assertions)]
-        searcher.add_eval_time(t_eval.elapsed().as_nanos() as u64);
-        static_eval = Some(val);
-    }
-    
-    // Razoring - only on non-PV nodes
-    if !NT::PV && depth.raw() <= 3 && !in_check {
-        if let Some(eval) = static_eval {
-            let threshold = alpha - Score::cp(200 + depth.raw() as i32 * 60);
-            if eval < threshold {
-                let result = qsearch::quiescence::<OffPV>(searcher, evaluator, board, ply, 0, alpha, beta);
-                 if result.score < alpha {
-                    return result; 
+    // === Razoring ===
+    // Non-PV nodes at depth 1-2 whose static eval is hopelessly below alpha:
+    // drop into qsearch and trust it if it can't reach alpha either
+    if !NT::PV && depth.raw() <= searcher.params.razor_max_depth && !in_check && !alpha.is_mate_score() {
+        if let Some(eval) = prune_eval {
+            if eval.raw() + searcher.params.razor_margin * depth.raw() < alpha.raw() {
+                let result = qsearch::quiescence_with_tt::<OffPV, T>(searcher, tt, evaluator, board, ply, 0, alpha, beta);
+                if result.score < alpha {
+                    return result;
                 }
             }
         }
@@ -366,11 +494,13 @@ pub fn search<NT: NodeType>(
     let mut searched_quiets: [Move; 64] = [Move::NULL; 64];
     let mut quiets_count = 0usize;
 
-    for (move_idx, m) in moves.iter().enumerate() {
+    for (move_idx, m) in moves.iter().filter(|m| !excluded.contains(m)).enumerate() {
         let new_board = board.make_move_new(m);
+        searcher.set_halfmove_at(ply.next(), next_halfmove_clock(board, m, halfmove));
+        searcher.set_path_move(ply, Some(m));
 
         // Prefetch TT entry for next position
-        searcher.shared.tt.prefetch(new_board.hash());
+        tt.prefetch(new_board.hash());
 
         // Determine if this is a quiet move (for LMR)
         let is_capture = m.is_capture();
@@ -384,7 +514,7 @@ pub fn search<NT: NodeType>(
         // This relies on move ordering to put good moves early.
         if is_quiet && depth.raw() <= 7 && !in_check {
             // Formula: LMS = 3 + depth^2 (e.g., d1=4, d2=7, d3=12...)
-            let lmp_count = (3 + depth.raw() * depth.raw()) as usize;
+            let lmp_count = (searcher.params.lmp_base + depth.raw() * depth.raw()) as usize;
             if quiets_count > lmp_count {
                 continue;
             }
@@ -404,10 +534,8 @@ pub fn search<NT: NodeType>(
             && !gives_check
             && !is_killer
         {
-            // Logarithmic reduction formula
-            let d = (depth.raw() as f32).ln();
-            let m_idx = ((move_idx + 1) as f32).ln();
-            let reduction = ((d * m_idx) / 1.9) as i32;
+            // Logarithmic reduction formula (precomputed table)
+            let reduction = searcher.params.lmr(depth.raw(), move_idx + 1);
             let reduction = reduction.min(depth.raw() - 2).max(1);
             reduced = true;
             Depth::new((depth.raw() - 1 - reduction + extension).max(1))
@@ -419,7 +547,7 @@ pub fn search<NT: NodeType>(
         // Prune quiet moves that have historically failed significantly
         if depth.raw() < 4 && is_quiet && !in_check && !gives_check && !is_killer && move_idx > 0 {
             // Threshold: -3000 * depth (e.g. -3000 at d1, -6000 at d2)
-            let threshold = -3000 * depth.raw() as i32;
+            let threshold = -searcher.params.history_prune * depth.raw();
             if searcher.history.get(color, m) < threshold {
                  // Track for history stats if needed, or just prune
                 continue;
@@ -431,17 +559,22 @@ pub fn search<NT: NodeType>(
         if depth.raw() <= 4 && is_quiet && !in_check && !gives_check && move_idx > 0 {
              // If move loses material (at least 50cp), prune it
              // This uses SEE to see if the move is "safe"
-             if !see::see_ge(board, m, -50) {
+             if !see::see_ge(board, m, -searcher.params.see_quiet_margin) {
                  continue;
              }
         }
 
         // === Futility Pruning ===
-        // At shallow depths, skip quiet moves if eval + margin is below alpha
-        if let Some(se) = static_eval {
-            if is_quiet && !gives_check && move_idx > 0 {
+        // At frontier nodes off the PV, skip quiet moves if eval + margin is below alpha
+        if let Some(se) = prune_eval {
+            if !NT::PV
+                && depth.raw() <= searcher.params.futility_max_depth
+                && is_quiet
+                && !gives_check
+                && move_idx > 0
+            {
                 // Tuned margin: 90 * depth (was 75 * depth)
-                let margin = 90 * depth.raw();
+                let margin = searcher.params.futility_margin * depth.raw();
                 if se.raw() + margin < alpha.raw() {
                     // Track for history
                     if quiets_count < 64 {
@@ -454,19 +587,22 @@ pub fn search<NT: NodeType>(
         }
 
         // === Principal Variation Search (PVS) ===
+        // (`Pvs` off: plain alpha-beta, every move gets the full window)
         let mut result;
         let mut score;
         
-        if move_idx == 0 {
+        if move_idx == 0 || !searcher.params.pvs {
             // Incremental update for next depth
             let mut child_eval = evaluator.clone();
             if !child_eval.update_move(board, m) {
                 child_eval.refresh(&new_board);
             }
 
-            // First move: search with full window (PV search)
-            result = search::<NT::Next>(
+            // First move: search with full window (PV search). Without PVS
+            // every child of a PV node is searched this way, as a PV node.
+            result = search_with_tt::<NT::Next, T>(
                 searcher,
+                tt,
                 &mut child_eval,
                 &new_board,
                 search_depth,
@@ -484,8 +620,9 @@ pub fn search<NT: NodeType>(
             }
 
             // Later moves: null window search first (OffPV)
-            result = search::<OffPV>(
+            result = search_with_tt::<OffPV, T>(
                 searcher,
+                tt,
                 &mut child_eval,
                 &new_board,
                 search_depth,
@@ -499,8 +636,9 @@ pub fn search<NT: NodeType>(
             // Re-search with full window if fails high (only on PV nodes)
             if NT::PV && score > alpha && score < beta && !searcher.should_stop() {
                 // Re-use same child_eval since board/move didn't change
-                result = search::<NT::Next>(
+                result = search_with_tt::<NT::Next, T>(
                     searcher,
+                    tt,
                     &mut child_eval,
                     &new_board,
                     search_depth,
@@ -520,8 +658,9 @@ pub fn search<NT: NodeType>(
                 child_eval.refresh(&new_board);
             }
 
-            result = search::<NT::Next>(
+            result = search_with_tt::<NT::Next, T>(
                 searcher,
+                tt,
                 &mut child_eval,
                 &new_board,
                 Depth::new((depth.raw() - 1 + extension).max(0)),
@@ -533,11 +672,34 @@ pub fn search<NT: NodeType>(
             score = -result.score;
         }
 
+        // A root tie only counts on an exact score: the null-window and reduced
+        // searches above return an upper bound, which can sit right on the best
+        // score for a refuted move. Re-search with a window that holds it.
+        if NT::ROOT && score == best_score && wins_root_tie(m, best_move) && !searcher.should_stop() {
+            let mut child_eval = evaluator.clone();
+            if !child_eval.update_move(board, m) {
+                child_eval.refresh(&new_board);
+            }
+
+            result = search_with_tt::<NT::Next, T>(
+                searcher,
+                tt,
+                &mut child_eval,
+                &new_board,
+                Depth::new((depth.raw() - 1 + extension).max(0)),
+                ply.next(),
+                -beta,
+                -(best_score - Score::cp(1)),
+                Some(m),
+            );
+            score = -result.score;
+        }
+
         if searcher.should_stop() {
             break;
         }
 
-        if score > best_score {
+        if score > best_score || (NT::ROOT && score == best_score && wins_root_tie(m, best_move)) {
             best_score = score;
             best_move = Some(m);
 
@@ -572,16 +734,27 @@ pub fn search<NT: NodeType>(
     }
 
     // === Update Correction History ===
-    // Track difference between static eval and search score to correct future evals
-    if let Some(se) = static_eval {
-        if !best_score.is_mate_score() && !se.is_mate_score() {
+    // Track difference between static eval and search score to correct future evals.
+    // Only when the score says something about the eval: a fail-high above it or a
+    // fail-low below it are bounds on the wrong side and carry no information,
+    // and neither does a root with MultiPV lines excluded.
+    if let Some(se) = static_eval.filter(|_| use_tt) {
+        let fail_high_below = best_score >= beta && best_score <= se;
+        let fail_low_above = best_score <= orig_alpha && best_score >= se;
+        if !searcher.should_stop()
+            && !best_score.is_mate_score()
+            && !se.is_mate_score()
+            && !fail_high_below
+            && !fail_low_above
+        {
             let diff = best_score.raw() - se.raw();
-            searcher.correction.update(color, pawn_hash, depth.raw(), diff);
+            searcher.correction.update_all(color, pawn_hash, mat_key, depth.raw(), diff);
         }
     }
 
     // === TT Store ===
-    if !searcher.should_stop() {
+    // Fail-high: lower bound, fail-low: upper bound, otherwise exact
+    if !searcher.should_stop() && use_tt {
         let bound = if best_score >= beta {
             BoundType::LowerBound
         } else if best_score > orig_alpha {
@@ -590,7 +763,7 @@ pub fn search<NT: NodeType>(
             BoundType::UpperBound
         };
 
-        searcher.shared.tt.store(
+        tt.store(
             hash,
             best_move,
             best_score.to_tt(ply.raw()),
@@ -606,3 +779,606 @@ pub fn search<NT: NodeType>(
         stats: searcher.stats().clone(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{OnPV, Root};
+    use crate::search::tt::TTEntry;
+    use crate::types::Hash;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    fn search_root(fen: &str, depth: i32) -> SearchResult {
+        let board = Board::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new();
+        searcher.set_position(board);
+        let mut evaluator = SearchEvaluator::new(None, None, &board);
+        search::<Root>(
+            &mut searcher,
+            &mut evaluator,
+            &board,
+            Depth::new(depth),
+            Ply::new(0),
+            Score::neg_infinity(),
+            Score::infinity(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_stalemate_is_draw() {
+        // Black to move has no legal moves and is not in check
+        let result = search_root("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 2);
+        assert_eq!(result.score, Score::draw());
+        assert!(result.best_move.is_none());
+    }
+
+    #[test]
+    fn test_checkmate_is_mated() {
+        // Back-rank mate already on the board
+        let result = search_root("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", 2);
+        assert_eq!(result.score, Score::mated_in(0));
+
+        // And the side delivering it finds mate in one
+        let result = search_root("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2);
+        assert_eq!(result.score, Score::mate_in(1));
+    }
+
+    /// Search the start position at depth 1 with the given window and return the
+    /// result together with the TT entry stored for the root
+    fn search_window(alpha: i32, beta: i32) -> (SearchResult, crate::search::tt::TTEntry) {
+        let board = Board::default();
+        let mut searcher = Searcher::new();
+        searcher.set_position(board);
+        let mut evaluator = SearchEvaluator::new(None, None, &board);
+        let result = search::<Root>(
+            &mut searcher,
+            &mut evaluator,
+            &board,
+            Depth::new(1),
+            Ply::new(0),
+            Score::cp(alpha),
+            Score::cp(beta),
+            None,
+        );
+        let entry = searcher.shared.tt.probe(board.hash()).expect("root stored");
+        (result, entry)
+    }
+
+    /// Table that answers from a plain map and records every probe and store
+    #[derive(Default)]
+    struct RecordingTt {
+        entries: RefCell<HashMap<Hash, TTEntry>>,
+        probes: RefCell<Vec<Hash>>,
+        stores: RefCell<Vec<(Hash, Option<Move>, Score, Depth, BoundType)>>,
+    }
+
+    impl TtProbe for RecordingTt {
+        fn probe(&self, hash: Hash) -> Option<TTEntry> {
+            self.probes.borrow_mut().push(hash);
+            self.entries.borrow().get(&hash).copied()
+        }
+    }
+
+    impl TtStore for RecordingTt {
+        fn store(&self, hash: Hash, best_move: Option<Move>, score: Score, depth: Depth, bound: BoundType) {
+            self.stores.borrow_mut().push((hash, best_move, score, depth, bound));
+            self.entries.borrow_mut().insert(hash, TTEntry::new(hash, best_move, score, depth, bound, 0));
+        }
+    }
+
+    fn search_recorded(fen: &str, depth: i32, ply: i32, alpha: Score, beta: Score) -> (SearchResult, RecordingTt) {
+        let board = Board::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new();
+        searcher.set_position(board);
+        let mut evaluator = SearchEvaluator::new(None, None, &board);
+        let tt = RecordingTt::default();
+        let result = search_with_tt::<OffPV, _>(
+            &mut searcher,
+            &tt,
+            &mut evaluator,
+            &board,
+            Depth::new(depth),
+            Ply::new(ply),
+            alpha,
+            beta,
+            None,
+        );
+        (result, tt)
+    }
+
+    #[test]
+    fn test_mock_tt_fail_high_stores_lower_bound() {
+        let board = Board::default();
+        let (result, tt) = search_recorded(&board.to_fen(), 2, 0, Score::cp(-101), Score::cp(-100));
+        assert!(result.score >= Score::cp(-100));
+
+        // The node probes its own hash first and stores last
+        assert_eq!(tt.probes.borrow().first(), Some(&board.hash()));
+        let stores = tt.stores.borrow();
+        let &(hash, best_move, score, depth, bound) = stores.last().unwrap();
+        assert_eq!(hash, board.hash());
+        assert_eq!(bound, BoundType::LowerBound);
+        assert_eq!(depth, Depth::new(2));
+        assert_eq!(best_move, result.best_move);
+        assert_eq!(score, result.score);
+    }
+
+    #[test]
+    fn test_mock_tt_stores_mate_relative_to_node() {
+        // Mate in one found three plies below the root: the stored score counts
+        // from this node, the returned one from the root
+        let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        let (result, tt) = search_recorded(fen, 2, 3, Score::neg_infinity(), Score::infinity());
+        assert_eq!(result.score, Score::mate_in(4));
+
+        let hash = Board::from_fen(fen).unwrap().hash();
+        let stores = tt.stores.borrow();
+        let &(_, _, score, _, _) = stores.iter().rev().find(|s| s.0 == hash).unwrap();
+        assert_eq!(score, Score::mate_in(1));
+        assert_eq!(score.from_tt(3), result.score);
+    }
+
+    #[test]
+    fn test_root_tie_needs_exact_score() {
+        // Qxd5 wins the queen, every other move leaves it hanging
+        let board = Board::from_fen("4k3/8/8/3q4/8/8/8/3QK3 w - - 0 1").unwrap();
+        let root = |tt: &RecordingTt| {
+            let mut searcher = Searcher::new();
+            searcher.set_position(board);
+            let mut evaluator = SearchEvaluator::new(None, None, &board);
+            search_with_tt::<Root, _>(
+                &mut searcher,
+                tt,
+                &mut evaluator,
+                &board,
+                Depth::new(3),
+                Ply::new(0),
+                Score::neg_infinity(),
+                Score::infinity(),
+                None,
+            )
+        };
+        let plain = root(&RecordingTt::default());
+        let best = plain.best_move.unwrap();
+        assert_eq!(best.to_uci(), "d1d5");
+
+        // A losing move that would win the tie-break, and a (true) lower bound
+        // for its reply that makes the null-window search land on the best score
+        let refuted = board.generate_moves().iter().find(|m| m.bits() < best.bits()).expect("a lower-encoded move");
+        let child = board.make_move_new(refuted).hash();
+        let tt = RecordingTt::default();
+        tt.entries.borrow_mut().insert(
+            child,
+            TTEntry::new(child, None, -plain.score, Depth::new(10), BoundType::LowerBound, 0),
+        );
+
+        let result = root(&tt);
+        assert_eq!(result.best_move, Some(best));
+        assert_eq!(result.score, plain.score);
+    }
+
+    #[test]
+    fn test_bound_fail_high() {
+        let (result, entry) = search_window(-60, -50);
+        assert!(result.score >= Score::cp(-50));
+        assert_eq!(entry.bound(), BoundType::LowerBound);
+        assert_eq!(entry.score(), result.score);
+    }
+
+    #[test]
+    fn test_bound_fail_low() {
+        let (result, entry) = search_window(50, 60);
+        assert!(result.score <= Score::cp(50));
+        assert_eq!(entry.bound(), BoundType::UpperBound);
+        assert_eq!(entry.score(), result.score);
+    }
+
+    #[test]
+    fn test_bound_exact() {
+        let (result, entry) = search_window(-1000, 1000);
+        assert!(result.score > Score::cp(-1000) && result.score < Score::cp(1000));
+        assert_eq!(entry.bound(), BoundType::Exact);
+        assert_eq!(entry.score(), result.score);
+        assert_eq!(entry.best_move(), result.best_move);
+    }
+
+    #[test]
+    fn test_shallow_tt_move_orders_first() {
+        // Ra8# is a quiet move, so without the TT it is not searched early
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let nodes_with_tt_move = |uci: &str| {
+            let mut searcher = Searcher::new();
+            let m = crate::uci::parse_move(&board, uci).unwrap();
+            // Depth 1 upper bound: too shallow and wrong side for a cutoff at depth 4
+            searcher.shared.tt.store(board.hash(), Some(m), Score::cp(-20), Depth::new(1), BoundType::UpperBound);
+            let mut evaluator = SearchEvaluator::new(None, None, &board);
+            let result = search::<Root>(
+                &mut searcher,
+                &mut evaluator,
+                &board,
+                Depth::new(4),
+                Ply::new(0),
+                Score::neg_infinity(),
+                Score::infinity(),
+                None,
+            );
+            assert_eq!(result.score, Score::mate_in(1));
+            searcher.stats().nodes
+        };
+
+        assert!(nodes_with_tt_move("a1a8") < nodes_with_tt_move("g1f1"));
+    }
+
+    #[test]
+    fn test_fifty_move_draw_from_fen() {
+        // A rook up, but every white move is quiet: at halfmove 99 the next move draws
+        let fen = "7k/8/8/8/8/8/8/R6K w - - 99 80";
+        let score_at = |clock: u32| {
+            let board = Board::from_fen(fen).unwrap();
+            let mut searcher = Searcher::new();
+            searcher.set_position(board);
+            searcher.set_halfmove_clock(clock);
+            let mut evaluator = SearchEvaluator::new(None, None, &board);
+            search::<Root>(
+                &mut searcher,
+                &mut evaluator,
+                &board,
+                Depth::new(3),
+                Ply::new(0),
+                Score::neg_infinity(),
+                Score::infinity(),
+                None,
+            )
+            .score
+        };
+
+        let (clock, _) = crate::uci::fen_clocks(fen);
+        assert_eq!(clock, 99);
+        assert!(score_at(clock).raw().abs() <= 20);
+        assert!(score_at(0).raw() > 300);
+    }
+
+    #[test]
+    fn test_knight_shuffle_repetition_draws() {
+        // A queen down, Black can shuffle the knight back to g8 for a third time
+        let start = Board::from_fen("6nk/6pp/8/8/8/8/6PP/3Q2NK w - - 0 1").unwrap();
+        let mut board = start;
+        let mut history = Vec::new();
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1"] {
+            history.push(board.hash());
+            board = board.make_move_new(crate::uci::parse_move(&board, uci).unwrap());
+        }
+
+        let score_with = |history: Vec<u64>| {
+            let mut searcher = Searcher::new();
+            searcher.set_position_with_history(board, history);
+            searcher.set_halfmove_clock(7);
+            let mut evaluator = SearchEvaluator::new(None, None, &board);
+            search::<Root>(
+                &mut searcher,
+                &mut evaluator,
+                &board,
+                Depth::new(3),
+                Ply::new(0),
+                Score::neg_infinity(),
+                Score::infinity(),
+                None,
+            )
+            .score
+        };
+
+        assert!(score_with(history).raw().abs() <= 40);
+        assert!(score_with(Vec::new()).raw() < -300);
+    }
+
+    #[test]
+    fn test_contempt_option_biases_repetition() {
+        // The root position again, two plies in: a repetition with the root
+        // side to move, searched with a full window
+        let board = Board::default();
+        let draw_with = |contempt: i32| {
+            let mut searcher = Searcher::new();
+            assert!(searcher.params.set("Contempt", contempt));
+            searcher.set_position(board);
+            searcher.set_halfmove_at(Ply::new(2), 2);
+            let mut evaluator = SearchEvaluator::new(None, None, &board);
+            search::<OffPV>(
+                &mut searcher,
+                &mut evaluator,
+                &board,
+                Depth::new(1),
+                Ply::new(2),
+                Score::neg_infinity(),
+                Score::infinity(),
+                None,
+            )
+            .score
+        };
+        assert_eq!(draw_with(50), Score::cp(-50));
+        assert_eq!(draw_with(0), Score::draw());
+
+        // The opponent gains what the engine loses
+        let mut searcher = Searcher::new();
+        searcher.params.set("Contempt", 50);
+        searcher.set_position(board);
+        let black = board.make_move_new(crate::uci::parse_move(&board, "e2e4").unwrap());
+        assert_eq!(searcher.contempt_draw(&black), Score::cp(50));
+    }
+
+    #[test]
+    fn test_insufficient_material_is_draw() {
+        // Kxe2 leaves bare kings: exactly a draw, not the king's PST
+        let board = Board::from_fen("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        searcher.set_position(board);
+        let mut evaluator = SearchEvaluator::new(None, None, &board);
+        let result = search::<Root>(
+            &mut searcher,
+            &mut evaluator,
+            &board,
+            Depth::new(3),
+            Ply::new(0),
+            Score::neg_infinity(),
+            Score::infinity(),
+            None,
+        );
+        assert_eq!(result.score, Score::draw());
+        assert_eq!(result.best_move.map(|m| m.to_uci()).as_deref(), Some("e1e2"));
+    }
+
+    #[test]
+    fn test_eval_stack_populated() {
+        let board = Board::from_fen("r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP2BPPP/R2QKB1R w KQ - 0 8").unwrap();
+        let mut evaluator = SearchEvaluator::new(None, None, &board);
+        let expected = evaluator.evaluate(&board);
+
+        // Same position entered at ply 0 and at ply 5 fills that slot only
+        // (fresh searcher each time: no TT cutoff, no correction yet)
+        for ply in [0, 5] {
+            let mut searcher = Searcher::new();
+            search::<OffPV>(
+                &mut searcher,
+                &mut evaluator,
+                &board,
+                Depth::new(1),
+                Ply::new(ply),
+                Score::cp(-50),
+                Score::cp(50),
+                None,
+            );
+            assert_eq!(searcher.static_eval_at(Ply::new(ply)), expected);
+            assert_eq!(searcher.static_eval_at(Ply::new(3)), Score::none());
+        }
+
+        // In check: no static eval
+        let mut searcher = Searcher::new();
+        let check = Board::from_fen("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+        let mut evaluator = SearchEvaluator::new(None, None, &check);
+        search::<OffPV>(
+            &mut searcher,
+            &mut evaluator,
+            &check,
+            Depth::new(1),
+            Ply::new(1),
+            Score::cp(-50),
+            Score::cp(50),
+            None,
+        );
+        assert_eq!(searcher.static_eval_at(Ply::new(1)), Score::none());
+    }
+
+    #[test]
+    fn test_iir_reduces_nodes() {
+        let board = Board::from_fen("r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP2BPPP/R2QKB1R w KQ - 0 8").unwrap();
+        let nodes = |iir: bool| {
+            let mut searcher = Searcher::new();
+            if !iir {
+                searcher.params.iir_min_depth = i32::MAX;
+            }
+            searcher.set_position(board);
+            searcher.search(crate::search::SearchLimits::depth(7)).stats.nodes
+        };
+
+        let with_iir = nodes(true);
+        let without_iir = nodes(false);
+        assert!(with_iir < without_iir, "IIR {} vs no IIR {}", with_iir, without_iir);
+    }
+
+    #[test]
+    fn test_razoring_reduces_nodes() {
+        // Black is a queen and two rooks down: most shallow nodes are hopeless
+        let board = Board::from_fen("4k3/pppp1ppp/8/8/8/8/PPPP1PPP/R2QK2R b KQ - 0 1").unwrap();
+        let nodes = |razoring: bool| {
+            let mut searcher = Searcher::new();
+            if !razoring {
+                searcher.params.razor_max_depth = 0;
+            }
+            searcher.set_position(board);
+            searcher.search(crate::search::SearchLimits::depth(5)).stats.nodes
+        };
+
+        let with_razoring = nodes(true);
+        let without_razoring = nodes(false);
+        assert!(with_razoring < without_razoring, "razoring {} vs none {}", with_razoring, without_razoring);
+    }
+
+    #[test]
+    fn test_futility_off_pv_and_out_of_check_only() {
+        // White is a queen up: a depth-2 node with beta far below the eval
+        let ahead = Board::from_fen("4k3/pppp1ppp/8/8/8/8/PPPP1PPP/3QK3 w - - 0 1").unwrap();
+        // Same material, but White is in check
+        let checked = Board::from_fen("4k3/pppp1ppp/8/8/8/8/PPPP1PPP/3QK2r w - - 0 1").unwrap();
+        let beta = Score::cp(-500);
+
+        let nodes = |board: &Board, pv: bool| {
+            let mut searcher = Searcher::new();
+            searcher.set_position(*board);
+            let mut evaluator = SearchEvaluator::new(None, None, board);
+            let (depth, ply) = (Depth::new(2), Ply::new(1));
+            let result = if pv {
+                search::<OnPV>(&mut searcher, &mut evaluator, board, depth, ply, Score::neg_infinity(), beta, None)
+            } else {
+                search::<OffPV>(&mut searcher, &mut evaluator, board, depth, ply, beta - Score::cp(1), beta, None)
+            };
+            assert!(result.score >= beta);
+            searcher.stats().nodes
+        };
+
+        // Reverse futility answers the non-PV node from its static eval alone
+        assert_eq!(nodes(&ahead, false), 1);
+        assert!(nodes(&ahead, true) > 1);
+        assert!(nodes(&checked, false) > 1);
+    }
+
+    #[test]
+    fn test_futility_keeps_tactical_solutions() {
+        let search = |fen: &str, pruning: bool| {
+            let mut searcher = Searcher::new();
+            if !pruning {
+                searcher.params.set("RfpMaxDepth", 0);
+                searcher.params.set("FutilityMaxDepth", 0);
+            }
+            searcher.set_position(Board::from_fen(fen).unwrap());
+            searcher.search(crate::search::SearchLimits::depth(6))
+        };
+
+        for (fen, expected) in [
+            // Nc7+ forks king and queen
+            ("q3k3/8/8/1N6/8/8/PPP5/4K2R w K - 0 1", "b5c7"),
+            // Back-rank mate
+            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"),
+        ] {
+            for pruning in [true, false] {
+                let best = search(fen, pruning).best_move.map(|m| m.to_uci());
+                assert_eq!(best.as_deref(), Some(expected), "{fen} pruning {pruning}");
+            }
+        }
+
+        // Mate in two needs a quiet first move
+        let mate = "7k/8/8/8/8/8/R7/1R4K1 w - - 0 1";
+        assert_eq!(search(mate, true).score.mate_moves(), Some(2));
+        assert_eq!(search(mate, false).score.mate_moves(), Some(2));
+    }
+
+    #[test]
+    fn test_null_verification_threshold() {
+        // Full middlegame army: plain null move
+        assert!(!needs_null_verification(&Board::default()));
+        // Queen + rook only: verify
+        let board = Board::from_fen("1q1k4/2Rr4/8/2Q3K1/8/8/8/8 w - - 0 1").unwrap();
+        assert!(needs_null_verification(&board));
+    }
+
+    #[test]
+    fn test_null_move_keeps_tactical_best_move() {
+        // Nc7+ forks king and queen
+        let board = Board::from_fen("q3k3/8/8/1N6/8/8/PPP5/4K2R w K - 0 1").unwrap();
+        let best = |nmp_min_depth: i32| {
+            let mut searcher = Searcher::new();
+            searcher.params.set("NmpMinDepth", nmp_min_depth);
+            searcher.set_position(board);
+            searcher.search(crate::search::SearchLimits::depth(7)).best_move
+        };
+
+        let with_nmp = best(3);
+        let without_nmp = best(crate::types::MAX_DEPTH + 1);
+        assert_eq!(with_nmp.map(|m| m.to_uci()).as_deref(), Some("b5c7"));
+        assert_eq!(with_nmp, without_nmp);
+    }
+
+    #[test]
+    fn test_no_null_move_without_own_pieces() {
+        // White has only pawns (Black has a rook): no null move for White
+        let board = Board::from_fen("4k3/8/8/r7/8/8/PPP5/4K3 w - - 0 1").unwrap();
+        assert_eq!(non_pawn_pieces(&board), 0);
+        assert_eq!(non_pawn_pieces(&board.make_null_move()), 1);
+    }
+
+    #[test]
+    fn test_pvs_searches_fewer_nodes() {
+        let run = |fen: &str, depth: i32, pvs: bool| {
+            let mut searcher = Searcher::new();
+            searcher.params.set("Pvs", pvs as i32);
+            searcher.set_position(Board::from_fen(fen).unwrap());
+            searcher.search(crate::search::SearchLimits::depth(depth))
+        };
+
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let pvs = run(start, 5, true);
+        let plain = run(start, 5, false);
+        assert!(pvs.stats.nodes < plain.stats.nodes, "PVS {} vs alpha-beta {}", pvs.stats.nodes, plain.stats.nodes);
+
+        // A forced mate: both find the same move and the same exact score
+        let mate = "7k/8/8/8/8/8/R7/1R4K1 w - - 0 1";
+        let pvs = run(mate, 5, true);
+        let plain = run(mate, 5, false);
+        assert_eq!(pvs.best_move, plain.best_move);
+        assert_eq!(pvs.score, plain.score);
+        assert_eq!(pvs.score.mate_moves(), Some(2));
+    }
+
+    #[test]
+    fn test_zugzwang_kh6() {
+        // Classic zugzwang: only the quiet Kh6 keeps the bind and wins, a
+        // pass would be even better for White, so unverified null move misleads
+        let board = Board::from_fen("1q1k4/2Rr4/8/2Q3K1/8/8/8/8 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        searcher.set_position(board);
+        let result = searcher.search(crate::search::SearchLimits::depth(10));
+        assert_eq!(result.best_move.map(|m| m.to_uci()), Some("g5h6".to_string()));
+    }
+
+    #[test]
+    fn test_search_stops_at_max_ply() {
+        // Both queens can give check on nearly every move: with check extensions
+        // the lines starting a few plies short of MAX_PLY run into the limit
+        let board = Board::from_fen("3qk3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let mut evaluator = SearchEvaluator::new(None, None, &board);
+        let expected = evaluator.evaluate(&board);
+
+        let mut searcher = Searcher::new();
+        let result = search::<OffPV>(
+            &mut searcher,
+            &mut evaluator,
+            &board,
+            Depth::new(4),
+            Ply::new(MAX_PLY - 3),
+            Score::neg_infinity(),
+            Score::infinity(),
+            None,
+        );
+        assert!(result.best_move.is_some());
+        assert!(searcher.stats.seldepth.raw() < MAX_PLY);
+
+        // At the limit itself: static eval, no move, for both searches
+        let mut searcher = Searcher::new();
+        let result = search::<OffPV>(
+            &mut searcher,
+            &mut evaluator,
+            &board,
+            Depth::new(4),
+            Ply::MAX,
+            Score::neg_infinity(),
+            Score::infinity(),
+            None,
+        );
+        assert_eq!(result.score, expected);
+        assert!(result.best_move.is_none());
+
+        let check = Board::from_fen("3qk3/8/8/8/8/8/8/3QK2q w - - 0 1").unwrap();
+        let mut evaluator = SearchEvaluator::new(None, None, &check);
+        let expected = evaluator.evaluate(&check);
+        let result = qsearch::quiescence::<OffPV>(
+            &mut searcher,
+            &mut evaluator,
+            &check,
+            Ply::MAX,
+            20,
+            Score::neg_infinity(),
+            Score::infinity(),
+        );
+        assert_eq!(result.score, expected);
+    }
+}
@@ -3,9 +3,10 @@
 //! Good move ordering is critical for alpha-beta pruning efficiency.
 //! Uses lazy selection sort to avoid full sort overhead.
 
-use crate::types::{Board, Move, Color, piece_value};
+use crate::types::{Board, BoardExt, Move, Color, Piece, Bitboard, piece_value};
 use super::history::HistoryTable;
 use super::see;
+use movegen::attacks::knight_attacks;
 
 /// Move score constants
 const TT_MOVE_BONUS: i32 = 1_000_000;
@@ -15,12 +16,14 @@ const KILLER_0_BONUS: i32 = 40_000;
 const KILLER_1_BONUS: i32 = 35_000;
 const COUNTER_MOVE_BONUS: i32 = 30_000;
 const BAD_CAPTURE_PENALTY: i32 = -10_000;
+/// Knight promotions with check: ahead of the other underpromotions, behind the queen
+const KNIGHT_PROMO_CHECK_BONUS: i32 = 400;
 
 /// MVV-LVA scores for capture ordering
 #[inline]
 fn mvv_lva_score(board: &Board, m: Move) -> i32 {
-    let victim = board.piece_at(m.to()).map(|(p, _)| p);
-    let attacker = board.piece_at(m.from()).map(|(p, _)| p);
+    let victim = board.piece_on(m.to());
+    let attacker = board.piece_on(m.from());
 
     match (victim, attacker) {
         (Some(v), Some(a)) => {
@@ -51,13 +54,19 @@ pub fn score_move(
     // Promotions are very important
     if let Some(promo) = m.flag().promotion_piece() {
         score += piece_value(promo) + PROMOTION_BONUS;
+        if promo == Piece::Knight {
+            let king = Bitboard::from_square(board.king_square(!board.turn()));
+            if (knight_attacks(m.to()) & king).any() {
+                score += KNIGHT_PROMO_CHECK_BONUS;
+            }
+        }
     }
 
     // Captures: skip SEE for obviously good captures (victim >= attacker)
     if m.is_capture() {
         // MVV-LVA logic inlined to reuse victim for SEE
-        let victim = board.piece_at(m.to()).map(|(p, _)| p);
-        let attacker = board.piece_at(m.from()).map(|(p, _)| p);
+        let victim = board.piece_on(m.to());
+        let attacker = board.piece_on(m.from());
         
         let mvv_lva = match (victim, attacker) {
             (Some(v), Some(a)) => piece_value(v) * 10 - piece_value(a),
@@ -70,8 +79,7 @@ pub fn score_move(
         } else {
             // Potentially losing capture - use SEE to verify
             // Pass the victim we already found to avoid re-lookup
-            let see_value = see::see_captured(board, m, victim);
-            if see_value >= 0 {
+            if see::see_ge_captured(board, m, victim, 0) {
                 score += GOOD_CAPTURE_BONUS + mvv_lva;
             } else {
                 score += BAD_CAPTURE_PENALTY + mvv_lva;
@@ -94,7 +102,6 @@ pub fn score_move(
     score
 }
 
-#[allow(dead_code)]
 pub fn order_moves_full(
     board: &Board, 
     moves: &mut [Move], 
@@ -112,23 +119,7 @@ pub fn order_moves_full(
         scores[i] = score_move(board, moves[i], tt_move, killers, counter_move, history, color);
     }
     
-    // Selection sort by scores (in-place, no allocation)
-    for i in 0..count {
-        let mut best_idx = i;
-        let mut best_score = scores[i];
-        
-        for j in (i + 1)..count {
-            if scores[j] > best_score {
-                best_score = scores[j];
-                best_idx = j;
-            }
-        }
-        
-        if best_idx != i {
-            moves.swap(i, best_idx);
-            scores.swap(i, best_idx);
-        }
-    }
+    sort_by_scores(&mut moves[..count], &mut scores[..count]);
 }
 
 #[allow(dead_code)]
@@ -142,7 +133,6 @@ pub fn order_moves_with_tt_and_killers(
     order_moves_full(board, moves, tt_move, killers, None, &dummy_history, Color::White);
 }
 
-#[allow(dead_code)]
 pub fn order_captures(board: &Board, moves: &mut [Move]) {
     let mut scores: [i32; 256] = [0; 256];
     let count = moves.len().min(256);
@@ -151,20 +141,69 @@ pub fn order_captures(board: &Board, moves: &mut [Move]) {
         scores[i] = mvv_lva_score(board, moves[i]);
     }
     
-    for i in 0..count {
+    sort_by_scores(&mut moves[..count], &mut scores[..count]);
+}
+
+/// Selection sort by score, highest first (in-place, no allocation).
+///
+/// Equal scores go by the lower move encoding, so the order depends only on
+/// the set of moves, never on the order the generator produced them in.
+fn sort_by_scores(moves: &mut [Move], scores: &mut [i32]) {
+    for i in 0..moves.len() {
         let mut best_idx = i;
-        let mut best_score = scores[i];
-        
-        for j in (i + 1)..count {
-            if scores[j] > best_score {
-                best_score = scores[j];
+
+        for j in (i + 1)..moves.len() {
+            let better = scores[j] > scores[best_idx]
+                || (scores[j] == scores[best_idx] && moves[j].bits() < moves[best_idx].bits());
+            if better {
                 best_idx = j;
             }
         }
-        
+
         if best_idx != i {
             moves.swap(i, best_idx);
             scores.swap(i, best_idx);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checking_knight_promotion_order() {
+        // e8=N gives check to the king on f6
+        let board = Board::from_fen("8/4P3/5k2/8/8/8/8/K7 w - - 0 1").unwrap();
+        let history = HistoryTable::new();
+        let score = |uci: &str| {
+            let m = crate::uci::parse_move(&board, uci).unwrap();
+            score_move(&board, m, None, [None; 2], None, &history, Color::White)
+        };
+        assert!(score("e7e8q") > score("e7e8n"));
+        assert!(score("e7e8n") > score("e7e8r"));
+        assert!(score("e7e8r") > score("e7e8b"));
+    }
+
+    #[test]
+    fn test_order_independent_of_generation_order() {
+        let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let first: Vec<Move> = board.generate_moves().iter().collect();
+        let second: Vec<Move> = board.generate_moves().iter().collect();
+        assert_eq!(first, second);
+
+        // Full ordering and capture ordering agree on the forward and reversed lists
+        let history = HistoryTable::new();
+        let mut forward = first.clone();
+        let mut reversed: Vec<Move> = first.iter().rev().copied().collect();
+        order_moves_full(&board, &mut forward, None, [None; 2], None, &history, Color::White);
+        order_moves_full(&board, &mut reversed, None, [None; 2], None, &history, Color::White);
+        assert_eq!(forward, reversed);
+
+        let mut forward: Vec<Move> = board.generate_captures().iter().collect();
+        let mut reversed: Vec<Move> = forward.iter().rev().copied().collect();
+        order_captures(&board, &mut forward);
+        order_captures(&board, &mut reversed);
+        assert_eq!(forward, reversed);
+    }
+}
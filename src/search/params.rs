@@ -0,0 +1,250 @@
+//! Tunable search parameters.
+//!
+//! The pruning/reduction margins used by `negamax` and the initial aspiration
+//! window live here instead of as scattered constants, so external tuners
+//! (SPSA etc.) can drive them through hidden UCI options without recompiling. Defaults match the hand-tuned values.
+//! The thresholds for the heuristic endgame eval and the HCE piece-square
+//! tables ride along, since the searcher hands them to its evaluator.
+
+use crate::eval::{EndgameConfig, EvalKind};
+use crate::eval::hce::PstConfig;
+use crate::eval::nnue::OutputScale;
+use crate::types::{MAX_DEPTH, MAX_PLY};
+use super::aspiration::INITIAL_WINDOW;
+use alloc::sync::Arc;
+
+/// Largest `Contempt` either way (centipawns)
+pub const MAX_CONTEMPT: i32 = 1000;
+
+/// Default NNUE output scale: dampens toward a draw in low-material positions
+/// where the net is least precise
+const OUTPUT_SCALE: OutputScale = [
+     64,  72,  80,  88,  96, 102, 108, 112, 116, 119,
+    122, 124, 126, 127, 128, 128, 128, 128, 128, 128,
+    128, 128, 128, 128, 128, 128, 128, 128, 128, 128,
+    128,
+];
+
+/// Size of the LMR table in each dimension (depth, move index)
+const LMR_SIZE: usize = 64;
+
+/// Search parameters read by the `Searcher`
+#[derive(Clone)]
+pub struct SearchParamsTunable {
+    /// LMR base reduction (x100)
+    pub lmr_base: i32,
+    /// LMR divisor for ln(depth) * ln(move) (x100)
+    pub lmr_divisor: i32,
+    /// Reverse futility margin per ply
+    pub rfp_margin: i32,
+    /// Maximum depth for reverse futility pruning
+    pub rfp_max_depth: i32,
+    /// Futility margin per ply (quiet moves)
+    pub futility_margin: i32,
+    /// Maximum depth for futility pruning (frontier nodes)
+    pub futility_max_depth: i32,
+    /// Late move pruning: quiet move budget is `lmp_base + depth^2`
+    pub lmp_base: i32,
+    /// Razoring margin per ply
+    pub razor_margin: i32,
+    /// Maximum depth for razoring (0 disables it)
+    pub razor_max_depth: i32,
+    /// ProbCut margin above beta
+    pub probcut_margin: i32,
+    /// Null move base reduction (one more above depth 6)
+    pub nmp_reduction: i32,
+    /// Minimum depth for null move pruning (above `MAX_DEPTH` disables it)
+    pub nmp_min_depth: i32,
+    /// History pruning threshold per ply (pruned below `-history_prune * depth`)
+    pub history_prune: i32,
+    /// SEE threshold for pruning quiet moves (centipawns lost)
+    pub see_quiet_margin: i32,
+    /// Minimum depth for internal iterative reductions
+    pub iir_min_depth: i32,
+    /// Initial aspiration window half-width
+    pub aspiration_window: i32,
+    /// Principal variation search: null window after the first move (off:
+    /// plain alpha-beta, for comparison)
+    pub pvs: bool,
+    /// Qsearch plies (from its first) that also search quiet checks
+    pub qsearch_checks: i32,
+    /// NNUE output scale in percent
+    pub eval_scale: i32,
+    /// Scale the NNUE output by piece count (`output_scale`)
+    pub output_scaling: bool,
+    /// NNUE output scale in 1/128ths, indexed by non-king piece count
+    /// (`OutputScale0`..`OutputScale30`)
+    pub output_scale: OutputScale,
+    /// Iterations completed regardless of the clock (only `stop` ends them)
+    pub min_depth: i32,
+    /// Deepest iteration started, whatever `go` asks for
+    pub max_depth: i32,
+    /// `Contempt`: centipawns a draw costs the engine (negative: draws are welcome)
+    pub contempt: i32,
+    /// Evaluation function searched with (`Auto`: NNUE if loaded, else HCE)
+    pub eval_kind: EvalKind,
+    /// When the heuristic endgame eval replaces NNUE/HCE
+    pub endgame: EndgameConfig,
+    /// HCE piece-square tables (shared, copied only when changed)
+    pub pst: Arc<PstConfig>,
+    /// Precomputed LMR reductions [depth][move_idx]
+    lmr_table: [[u8; LMR_SIZE]; LMR_SIZE],
+}
+
+impl Default for SearchParamsTunable {
+    fn default() -> Self {
+        let mut params = Self {
+            lmr_base: 0,
+            lmr_divisor: 190,
+            rfp_margin: 90,
+            rfp_max_depth: 7,
+            futility_margin: 90,
+            futility_max_depth: 2,
+            lmp_base: 3,
+            razor_margin: 240,
+            razor_max_depth: 2,
+            probcut_margin: 100,
+            nmp_reduction: 4,
+            nmp_min_depth: 3,
+            history_prune: 3000,
+            see_quiet_margin: 50,
+            iir_min_depth: 4,
+            aspiration_window: INITIAL_WINDOW,
+            pvs: true,
+            qsearch_checks: 1,
+            eval_scale: 100,
+            output_scaling: false,
+            output_scale: OUTPUT_SCALE,
+            min_depth: 1,
+            max_depth: MAX_DEPTH,
+            contempt: 0,
+            eval_kind: EvalKind::Auto,
+            endgame: EndgameConfig::default(),
+            pst: Arc::new(PstConfig::default()),
+            lmr_table: [[0; LMR_SIZE]; LMR_SIZE],
+        };
+        params.init_lmr();
+        params
+    }
+}
+
+impl SearchParamsTunable {
+    /// Recompute the LMR table from `lmr_base` / `lmr_divisor`
+    pub fn init_lmr(&mut self) {
+        let base = self.lmr_base as f32 / 100.0;
+        let divisor = (self.lmr_divisor.max(1)) as f32 / 100.0;
+        for (d, row) in self.lmr_table.iter_mut().enumerate().skip(1) {
+            for (m, r) in row.iter_mut().enumerate().skip(1) {
+                let reduction = base + (d as f32).ln() * (m as f32).ln() / divisor;
+                *r = reduction.clamp(0.0, MAX_PLY as f32).min(u8::MAX as f32) as u8;
+            }
+        }
+    }
+
+    /// LMR reduction for `depth` and 1-based move number `move_num`
+    #[inline]
+    pub fn lmr(&self, depth: i32, move_num: usize) -> i32 {
+        let d = (depth.max(0) as usize).min(LMR_SIZE - 1);
+        let m = move_num.min(LMR_SIZE - 1);
+        self.lmr_table[d][m] as i32
+    }
+
+    /// Set a parameter by (case-insensitive) UCI option name.
+    ///
+    /// Returns false if the name is not a tunable parameter.
+    pub fn set(&mut self, name: &str, value: i32) -> bool {
+        match name.to_lowercase().as_str() {
+            "lmrbase" => {
+                self.lmr_base = value;
+                self.init_lmr();
+            }
+            "lmrdivisor" => {
+                self.lmr_divisor = value.max(1);
+                self.init_lmr();
+            }
+            "rfpmargin" => self.rfp_margin = value,
+            "rfpmaxdepth" => self.rfp_max_depth = value,
+            "futilitymargin" => self.futility_margin = value,
+            "futilitymaxdepth" => self.futility_max_depth = value,
+            "lmpbase" => self.lmp_base = value,
+            "razormargin" => self.razor_margin = value,
+            "razormaxdepth" => self.razor_max_depth = value,
+            "probcutmargin" => self.probcut_margin = value,
+            "nmpreduction" => self.nmp_reduction = value.max(1),
+            "nmpmindepth" => self.nmp_min_depth = value.max(1),
+            "historyprune" => self.history_prune = value,
+            "seequietmargin" => self.see_quiet_margin = value,
+            "iirmindepth" => self.iir_min_depth = value,
+            "aspirationwindow" => self.aspiration_window = value.max(1),
+            "pvs" => self.pvs = value != 0,
+            "qsearchchecks" => self.qsearch_checks = value.max(0),
+            "evalscale" => self.eval_scale = value.clamp(10, 400),
+            "outputscaling" => self.output_scaling = value != 0,
+            key if key.starts_with("outputscale") => {
+                let bucket = key["outputscale".len()..].parse::<usize>().ok();
+                let Some(factor) = bucket.and_then(|i| self.output_scale.get_mut(i)) else {
+                    return false;
+                };
+                *factor = value.clamp(0, 256);
+            }
+            "mindepth" => self.min_depth = value.clamp(1, MAX_DEPTH),
+            "maxdepth" => self.max_depth = value.clamp(1, MAX_DEPTH),
+            "contempt" => self.contempt = value.clamp(-MAX_CONTEMPT, MAX_CONTEMPT),
+            _ if name.to_lowercase().starts_with("pst") => return Arc::make_mut(&mut self.pst).set(name, value),
+            _ => return self.endgame.set(name, value),
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_lmr_matches_formula() {
+        let params = SearchParamsTunable::default();
+        let expected = ((10f32).ln() * (20f32).ln() / 1.9) as i32;
+        assert_eq!(params.lmr(10, 20), expected);
+        assert_eq!(params.lmr(0, 5), 0);
+    }
+
+    #[test]
+    fn test_override_changes_lmr() {
+        let mut params = SearchParamsTunable::default();
+        let before = params.lmr(12, 30);
+
+        assert!(params.set("LMRBase", 100));
+        assert_eq!(params.lmr(12, 30), before + 1);
+
+        assert!(params.set("LMRDivisor", 95));
+        assert!(params.lmr(12, 30) > before + 1);
+
+        assert!(params.set("RazorMargin", 300));
+        assert_eq!(params.razor_margin, 300);
+        assert!(!params.set("NotAParam", 1));
+
+        assert!(params.set("QsearchChecks", -1));
+        assert_eq!(params.qsearch_checks, 0);
+
+        assert!(params.set("EvalScale", 150));
+        assert_eq!(params.eval_scale, 150);
+        assert!(params.set("OutputScaling", 1));
+        assert!(params.output_scaling);
+        assert!(params.set("OutputScale3", 1000));
+        assert_eq!(params.output_scale[3], 256);
+        assert!(!params.set("OutputScale31", 64));
+
+        // Depth guards stay within 1..=MAX_DEPTH
+        assert!(params.set("MinDepth", 0));
+        assert_eq!(params.min_depth, 1);
+        assert!(params.set("MaxDepth", 1000));
+        assert_eq!(params.max_depth, MAX_DEPTH);
+
+        // Endgame thresholds go through the same interface
+        assert!(params.set("EndgamePieces", 4));
+        assert_eq!(params.endgame.piece_threshold, 4);
+        assert!(params.set("PstScaleMG", 110));
+        assert!(!params.set("PstScaleXX", 110));
+    }
+}
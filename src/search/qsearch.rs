@@ -3,15 +3,31 @@
 //! When the main search reaches depth 0, we continue searching captures
 //! to ensure we don't stop in the middle of a tactical sequence.
 //!
-//! Implements delta pruning to skip hopeless captures.
+//! Implements delta pruning to skip hopeless captures. With a pawn on the
+//! seventh the big-delta margin allows for a new queen, and quiet queen
+//! promotions are searched alongside the captures.
+//!
+//! The first `QsearchChecks` plies also search quiet checks (after the
+//! captures), so mates one quiet move past the horizon are seen; deeper plies
+//! are captures-only, since check sequences there can explode.
+//!
+//! When in check there is no stand-pat: all evasions are searched, and having
+//! none is checkmate.
+//!
+//! Results go through the transposition table. Plies that search quiet checks
+//! are stored at depth 0 (so the main search's depth-0 probe can use them),
+//! captures-only plies at depth -1.
 //!
 //! Uses compile-time node type specialization via the `NodeType` trait.
 
 use super::{Searcher, ordering};
 use super::negamax::{SearchResult, PV};
+use super::tt::{BoundType, TtProbe, TtStore};
 use super::node_types::NodeType;
 use super::see::{is_good_capture, is_good_capture_with_victim};
-use crate::types::{Board, Score, Ply, Piece};
+use super::checks::{generate_checks, CheckList};
+use crate::types::{Board, BoardExt, Color, Depth, MoveFlag, Score, Ply, Piece, MAX_PLY, is_insufficient_material, next_halfmove_clock};
+use movegen::Rank;
 use crate::eval::SearchEvaluator;
 use smallvec::smallvec;
 
@@ -29,6 +45,9 @@ const PIECE_VALUES: [i32; 6] = [
 /// Using Queen value as the maximum possible gain from a single capture
 const DELTA_MARGIN: i32 = 600;
 
+/// Extra big-delta margin while a promotion is pending: the pawn becomes a queen
+const PROMOTION_MARGIN: i32 = PIECE_VALUES[4] - PIECE_VALUES[0];
+
 /// Safety margin for individual move delta pruning
 const DELTA_SAFETY: i32 = 100;
 
@@ -36,51 +55,138 @@ const DELTA_SAFETY: i32 = 100;
 /// After this depth, only continue if in check
 const MAX_QSEARCH_DEPTH: i32 = 8;
 
+/// TT depth of a qsearch node that also searches quiet checks
+const DEPTH_QS_CHECKS: Depth = Depth::QS;
+
+/// TT depth of a captures-only qsearch node
+const DEPTH_QS_NO_CHECKS: Depth = Depth::new(-1);
+
 /// Get the value of a piece for delta pruning
 #[inline]
 fn piece_value(piece: Piece) -> i32 {
     PIECE_VALUES[piece.index()]
 }
 
-/// Quiescence search - search captures only to avoid horizon effect.
+/// Does the side to move have a pawn one step from promoting?
+#[inline]
+fn promotion_pending(board: &Board) -> bool {
+    let us = board.turn();
+    let seventh = if us == Color::White { Rank::R7 } else { Rank::R2 };
+    (board.piece_bb(Piece::Pawn) & board.color_bb(us))
+        .into_iter()
+        .any(|sq| sq.rank() == seventh)
+}
+
+/// Quiescence search - search captures only to avoid horizon effect,
+/// using the searcher's shared transposition table (tests only: the search
+/// itself goes through `quiescence_with_tt` with the table it was handed).
 ///
 /// Uses compile-time node type specialization via the `NodeType` trait.
 /// `qply` tracks depth within qsearch (starts at 0).
+#[cfg(test)]
 pub fn quiescence<NT: NodeType>(
     searcher: &mut Searcher,
     evaluator: &mut SearchEvaluator,
     board: &Board,
     ply: Ply,
     qply: i32,
+    alpha: Score,
+    beta: Score,
+) -> SearchResult {
+    let shared = searcher.shared.clone();
+    quiescence_with_tt::<NT, _>(searcher, &shared.tt, evaluator, board, ply, qply, alpha, beta)
+}
+
+/// `quiescence` against any table implementing `TtProbe` + `TtStore`
+/// (the one the main search was handed).
+pub fn quiescence_with_tt<NT: NodeType, T: TtProbe + TtStore>(
+    searcher: &mut Searcher,
+    tt: &T,
+    evaluator: &mut SearchEvaluator,
+    board: &Board,
+    ply: Ply,
+    qply: i32,
     mut alpha: Score,
     beta: Score,
 ) -> SearchResult {
     searcher.inc_nodes();
     searcher.inc_qnodes();
-    searcher.update_seldepth(ply);
 
-    // Stand-pat evaluation using incremental evaluator
-    #[cfg(debug_assertions)]
-    searcher.inc_eval_calls();
-    #[cfg(debug_assertions)]
-    let t_eval = std::time::Instant::now();
-    let stand_pat = evaluator.evaluate(board);
-    #[cfg(debug_assertions)]
-    searcher.add_eval_time(t_eval.elapsed().as_nanos() as u64);
+    // Evasions are searched to any depth: cap the recursion at MAX_PLY
+    if ply.raw() >= MAX_PLY {
+        return SearchResult {
+            best_move: None,
+            score: evaluator.evaluate(board),
+            pv: smallvec![],
+            stats: searcher.stats().clone(),
+        };
+    }
 
-    // Beta cutoff: position is already too good
-    if stand_pat >= beta {
+    searcher.update_seldepth(ply);
+
+    // A capture that leaves no mating material ends the game
+    if is_insufficient_material(board) {
         return SearchResult {
             best_move: None,
-            score: beta,
+            score: searcher.contempt_draw(board),
             pv: smallvec![],
             stats: searcher.stats().clone(),
         };
     }
 
-    // === Delta Pruning (Big Delta) ===
-    // If even capturing a queen wouldn't bring us close to alpha, give up
     let in_check = board.in_check();
+    let halfmove = searcher.halfmove_at(ply);
+    evaluator.set_halfmove_clock(halfmove);
+
+    // === TT Probe ===
+    // Off the PV only, so the reported line isn't cut short
+    let hash = board.hash();
+    let tt_depth = if qply < searcher.params.qsearch_checks { DEPTH_QS_CHECKS } else { DEPTH_QS_NO_CHECKS };
+    if let Some(entry) = tt.probe(hash) {
+        searcher.inc_tt_hits();
+        let tt_score = entry.score().from_tt(ply.raw());
+        let usable = match entry.bound() {
+            BoundType::Exact => true,
+            BoundType::LowerBound => tt_score >= beta,
+            BoundType::UpperBound => tt_score <= alpha,
+            BoundType::None => false,
+        };
+        if !NT::PV && usable && entry.depth() >= tt_depth {
+            return SearchResult {
+                best_move: None,
+                score: tt_score,
+                pv: smallvec![],
+                stats: searcher.stats().clone(),
+            };
+        }
+    }
+    let orig_alpha = alpha;
+
+    // Stand-pat evaluation using incremental evaluator.
+    // In check we can't stand pat: the floor is being mated here.
+    let stand_pat = if in_check {
+        Score::mated_in(ply.raw())
+    } else {
+        #[cfg(debug_assertions)]
+        searcher.inc_eval_calls();
+        #[cfg(all(debug_assertions, feature = "std"))]
+        let t_eval = std::time::Instant::now();
+        let eval = evaluator.evaluate(board);
+        #[cfg(all(debug_assertions, feature = "std"))]
+        searcher.add_eval_time(t_eval.elapsed().as_nanos() as u64);
+        eval
+    };
+
+    // Beta cutoff: position is already too good (fail-soft)
+    if stand_pat >= beta {
+        tt.store(hash, None, stand_pat.to_tt(ply.raw()), tt_depth, BoundType::LowerBound);
+        return SearchResult {
+            best_move: None,
+            score: stand_pat,
+            pv: smallvec![],
+            stats: searcher.stats().clone(),
+        };
+    }
 
     // === Qsearch Depth Limit ===
     // Beyond MAX_QSEARCH_DEPTH, only continue if in check
@@ -93,7 +199,12 @@ pub fn quiescence<NT: NodeType>(
         };
     }
 
-    if !in_check && stand_pat.raw() + DELTA_MARGIN < alpha.raw() {
+    // === Delta Pruning (Big Delta) ===
+    // If even capturing a queen wouldn't bring us close to alpha, give up.
+    // A pawn on the seventh can add a queen on top of that.
+    let promoting = !in_check && promotion_pending(board);
+    let margin = if promoting { DELTA_MARGIN + PROMOTION_MARGIN } else { DELTA_MARGIN };
+    if !in_check && stand_pat.raw() + margin < alpha.raw() {
         return SearchResult {
             best_move: None,
             score: alpha,
@@ -106,62 +217,93 @@ pub fn quiescence<NT: NodeType>(
         alpha = stand_pat;
     }
 
-    // Generate only captures
-    #[cfg(debug_assertions)]
+    // Generate only captures (all evasions when in check; everything when a
+    // promotion is pending, filtered to captures and queen pushes below)
+    #[cfg(all(debug_assertions, feature = "std"))]
     let t_gen = std::time::Instant::now();
-    let mut moves = board.generate_captures();
-    #[cfg(debug_assertions)]
+    let mut moves = if in_check || promoting {
+        board.generate_moves()
+    } else {
+        board.generate_captures()
+    };
+    // Quiet checks in the first plies, searched after the captures
+    // (queen promotions among them are already in `moves`)
+    let quiet_checks = if !in_check && qply < searcher.params.qsearch_checks {
+        generate_checks(board)
+    } else {
+        CheckList::new()
+    };
+    #[cfg(all(debug_assertions, feature = "std"))]
     searcher.add_gen_time(t_gen.elapsed().as_nanos() as u64);
 
-    if moves.is_empty() {
+    if moves.is_empty() && quiet_checks.is_empty() {
+        // No evasions: checkmate. Otherwise nothing to capture: the static eval stands.
         return SearchResult {
             best_move: None,
-            score: alpha,
+            score: stand_pat,
             pv: smallvec![],
             stats: searcher.stats().clone(),
         };
     }
 
-    #[cfg(debug_assertions)]
+    #[cfg(all(debug_assertions, feature = "std"))]
     let t_order = std::time::Instant::now();
     ordering::order_captures(board, moves.as_slice_mut());
-    #[cfg(debug_assertions)]
+    #[cfg(all(debug_assertions, feature = "std"))]
     searcher.add_order_time(t_order.elapsed().as_nanos() as u64);
 
+    let tactical = moves.len();
+
     let mut best_score = stand_pat;
     let mut pv: PV = smallvec![];
 
-    for (_, m) in moves.iter().enumerate() {
+    for (i, m) in moves.iter().chain(quiet_checks.iter().copied()).enumerate() {
         if searcher.should_stop() {
             break;
         }
 
+        let quiet_check = i >= tactical;
+        let quiet_promotion = !m.is_capture() && m.is_promotion();
+        if quiet_check && promoting && m.flag() == MoveFlag::PromoQueen {
+            continue;
+        }
+        if !quiet_check && promoting && !m.is_capture() && m.flag() != MoveFlag::PromoQueen {
+            continue;
+        }
+
         // Get captured piece value for delta pruning
-        let captured = board.piece_at(m.to()).map(|(p, _)| p);
+        let captured = board.piece_on(m.to());
         let captured_value = captured.map(piece_value).unwrap_or(0);
 
         // === Delta Pruning (Per-Move) ===
         // If this capture + safety margin can't raise alpha, skip it
-        // Skip this check for promotions (they gain material)
-        if !in_check && !m.is_promotion() {
+        // Skip this check for promotions (they gain material) and checks
+        // (they can mate)
+        if !in_check && !m.is_promotion() && !quiet_check {
             if stand_pat.raw() + captured_value + DELTA_SAFETY < alpha.raw() {
                 continue;
             }
         }
 
         // === SEE Pruning ===
-        // Skip captures that lose material according to SEE
-        if !in_check && !is_good_capture_with_victim(board, m, captured) {
+        // Skip captures that lose material according to SEE. A quiet
+        // promotion is left to the reply: the recapture is searched there.
+        if !in_check && !quiet_promotion && !is_good_capture_with_victim(board, m, captured) {
             continue;
         }
 
         let new_board = board.make_move_new(m);
+        searcher.set_halfmove_at(ply.next(), next_halfmove_clock(board, m, halfmove));
+        searcher.set_path_move(ply, Some(m));
         
         // Clone evaluator for next depth and update incrementally
+        // (king evasions/captures need a full refresh)
         let mut child_evaluator = evaluator.clone();
-        child_evaluator.update_move(board, m); // board is position BEFORE move
+        if !child_evaluator.update_move(board, m) { // board is position BEFORE move
+            child_evaluator.refresh(&new_board);
+        }
 
-        let result = quiescence::<NT::Next>(searcher, &mut child_evaluator, &new_board, ply.next(), qply + 1, -beta, -alpha);
+        let result = quiescence_with_tt::<NT::Next, T>(searcher, tt, &mut child_evaluator, &new_board, ply.next(), qply + 1, -beta, -alpha);
         let score = -result.score;
 
         if score > best_score {
@@ -180,6 +322,18 @@ pub fn quiescence<NT: NodeType>(
         }
     }
 
+    // === TT Store ===
+    if !searcher.should_stop() {
+        let bound = if best_score >= beta {
+            BoundType::LowerBound
+        } else if best_score > orig_alpha {
+            BoundType::Exact
+        } else {
+            BoundType::UpperBound
+        };
+        tt.store(hash, pv.first().copied(), best_score.to_tt(ply.raw()), tt_depth, bound);
+    }
+
     SearchResult {
         best_move: None,
         score: best_score,
@@ -187,3 +341,155 @@ pub fn quiescence<NT: NodeType>(
         stats: searcher.stats().clone(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::EvalKind;
+    use crate::search::{OffPV, TranspositionTable};
+
+    #[test]
+    fn test_qsearch_keeps_every_capture() {
+        // Knights on the dark squares of the first four ranks, black knights on
+        // every light square: each white move takes an undefended knight, well
+        // over 64 of them, and none gives check
+        let board =
+            Board::from_fen("k1n1n1n1/1n1n1n1n/n1n1n1n1/1n1n1n1n/nNnNnNnN/NnNnNnNn/nNnNnNnN/NnNnNnNK w - - 0 1").unwrap();
+        let captures = board.generate_captures();
+        assert!(captures.len() > 64);
+        assert_eq!(captures.len(), board.generate_moves().len());
+
+        // One ply above the qsearch limit every reply stands pat, and a material
+        // eval gives each capture the same gain, so nothing is delta pruned:
+        // one qnode for the root and one per capture
+        let mut searcher = Searcher::new();
+        let mut evaluator = SearchEvaluator::with_kind(EvalKind::Material, None, None, &board);
+        quiescence::<OffPV>(
+            &mut searcher,
+            &mut evaluator,
+            &board,
+            Ply::new(1),
+            MAX_QSEARCH_DEPTH - 1,
+            Score::neg_infinity(),
+            Score::infinity(),
+        );
+        assert_eq!(searcher.take_stats().qnodes, 1 + captures.len() as u64);
+    }
+
+    #[test]
+    fn test_qsearch_detects_mate_in_check() {
+        // Black is up material but in check with no evasions: mate, not eval
+        let board = Board::from_fen("R5k1/5ppp/8/8/8/pp6/8/nn4K1 b - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        let mut evaluator = SearchEvaluator::new(None, None, &board);
+        let result = quiescence::<OffPV>(
+            &mut searcher,
+            &mut evaluator,
+            &board,
+            Ply::new(3),
+            0,
+            Score::neg_infinity(),
+            Score::infinity(),
+        );
+        assert_eq!(result.score, Score::mated_in(3));
+    }
+
+    #[test]
+    fn test_qsearch_searches_promotion_far_below_alpha() {
+        // Two rooks down: only b8=Q gets White near alpha, which sits beyond
+        // the plain big-delta margin
+        let board = Board::from_fen("8/1P6/8/4k3/8/6K1/2rr4/8 w - - 0 1").unwrap();
+        let mut evaluator = SearchEvaluator::new(None, None, &board);
+        let stand_pat = evaluator.evaluate(&board);
+        let alpha = Score::cp(stand_pat.raw() + 650);
+
+        let mut searcher = Searcher::new();
+        let result =
+            quiescence::<OffPV>(&mut searcher, &mut evaluator, &board, Ply::new(1), 0, alpha, Score::infinity());
+        assert!(result.score > alpha, "{} <= {}", result.score, alpha);
+        assert_eq!(result.pv.first().map(|m| m.to_uci()).as_deref(), Some("b7b8q"));
+    }
+
+    #[test]
+    fn test_qsearch_checks_option() {
+        // No captures; Ra8 is a quiet mate
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let qsearch_at = |checks: i32, qply: i32| {
+            let mut searcher = Searcher::new();
+            searcher.params.set("QsearchChecks", checks);
+            let mut evaluator = SearchEvaluator::new(None, None, &board);
+            quiescence::<OffPV>(
+                &mut searcher,
+                &mut evaluator,
+                &board,
+                Ply::new(4),
+                qply,
+                Score::neg_infinity(),
+                Score::infinity(),
+            )
+        };
+
+        // 0: captures only, the mate is not seen
+        let result = qsearch_at(0, 0);
+        assert!(!result.score.is_mate());
+        assert!(result.pv.is_empty());
+
+        // 2: checks in qplies 0 and 1, not from qply 2 on
+        for qply in 0..2 {
+            let result = qsearch_at(2, qply);
+            assert_eq!(result.score, Score::mate_in(5), "qply {qply}");
+            assert_eq!(result.pv.first().map(|m| m.to_uci()).as_deref(), Some("a1a8"));
+        }
+        assert!(!qsearch_at(2, 2).score.is_mate());
+    }
+
+    #[test]
+    fn test_qsearch_reuses_tt() {
+        let board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        let qsearch = |searcher: &mut Searcher| {
+            let mut evaluator = SearchEvaluator::new(None, None, &board);
+            let result = quiescence::<OffPV>(
+                searcher,
+                &mut evaluator,
+                &board,
+                Ply::new(2),
+                0,
+                Score::neg_infinity(),
+                Score::infinity(),
+            );
+            (result.score, searcher.take_stats())
+        };
+
+        let (first, stats) = qsearch(&mut searcher);
+        assert!(stats.qnodes > 1);
+        let entry = searcher.shared.tt.probe(board.hash()).expect("qsearch stored");
+        assert_eq!(entry.depth(), Depth::QS);
+        assert_eq!(entry.bound(), BoundType::Exact);
+
+        // Same position again: answered by the stored entry
+        let (second, stats) = qsearch(&mut searcher);
+        assert_eq!(second, first);
+        assert_eq!(stats.qnodes, 1);
+        assert_eq!(stats.tt_hits, 1);
+
+        // An injected table sees the whole tree, not just the entry node
+        let tt = TranspositionTable::new(1);
+        let mut evaluator = SearchEvaluator::new(None, None, &board);
+        let result = quiescence_with_tt::<OffPV, _>(
+            &mut Searcher::new(),
+            &tt,
+            &mut evaluator,
+            &board,
+            Ply::new(2),
+            0,
+            Score::neg_infinity(),
+            Score::infinity(),
+        );
+        assert_eq!(result.score, first);
+        assert!(tt.probe(board.hash()).is_some());
+        let captures = board.generate_captures();
+        assert!(captures.iter().any(|m| tt.probe(board.make_move_new(m).hash()).is_some()));
+    }
+}
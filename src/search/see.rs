@@ -3,7 +3,7 @@
 //! Determines if a capture sequence is winning, losing, or neutral.
 //! Uses fixed-size arrays to avoid allocations.
 
-use crate::types::{Board, Move, Piece, Color, Bitboard};
+use crate::types::{Board, BoardExt, Move, MoveFlag, Piece, Color, Bitboard};
 use movegen::attacks::{pawn_attacks, knight_attacks, king_attacks, bishop_attacks, rook_attacks};
 
 /// Piece values for SEE (using lower values for faster cutoffs)
@@ -58,74 +58,85 @@ fn get_piece_attacks(board: &Board, target: movegen::Square, piece: Piece, side:
     }
 }
 
-/// Static Exchange Evaluation
-/// Returns the material balance after a capture sequence.
-/// Uses fixed-size array to avoid allocations.
-#[inline]
-/// Static Exchange Evaluation with known victim
-/// Returns the material balance after a capture sequence.
-/// `victim` should be the piece at the target square (None for En Passant).
+/// Material won by the move itself: the captured piece (a pawn for en
+/// passant) plus the promotion gain. Also returns the value of the piece
+/// standing on the target square afterwards and the square of an en passant
+/// victim, which leaves the board as well.
 #[inline]
-pub fn see_captured(board: &Board, mv: Move, victim: Option<Piece>) -> i32 {
-    let from = mv.from();
-    let to = mv.to();
-    
-    let attacker = board.piece_at(from).map(|(p, _)| p);
-    
-    let (attacker_piece, mut gain) = match (attacker, victim) {
-        (Some(a), Some(v)) => (a, see_piece_value(v)),
-        (Some(a), None) => {
-            // En passant capture or quiet move?
-            // SEE is typically only called for captures.
-            // If it's a pawn moving to empty square, assume EP if it's a capture.
-            if a == Piece::Pawn {
-                (a, see_piece_value(Piece::Pawn))
-            } else {
-                return 0; // Not a capture
-            }
+fn initial_exchange(board: &Board, mv: Move, victim: Option<Piece>) -> Option<(i32, i32, Option<movegen::Square>)> {
+    let (attacker, _) = board.piece_at(mv.from())?;
+
+    let (mut gain, ep_square) = match victim {
+        Some(v) => (see_piece_value(v), None),
+        None if mv.flag() == MoveFlag::EnPassant => {
+            let to = mv.to().index();
+            let captured = if board.turn() == Color::White { to - 8 } else { to + 8 };
+            (see_piece_value(Piece::Pawn), movegen::Square::from_index(captured))
         }
-        _ => return 0,
+        None => (0, None),
     };
 
-    // Handle promotion
+    let mut on_square = see_piece_value(attacker);
     if let Some(promo) = mv.flag().promotion_piece() {
         gain += see_piece_value(promo) - see_piece_value(Piece::Pawn);
+        on_square = see_piece_value(promo);
+    }
+    Some((gain, on_square, ep_square))
+}
+
+/// Occupancy after the move leaves its square (and an en passant victim is taken)
+#[inline]
+fn occupied_after(board: &Board, mv: Move, ep_square: Option<movegen::Square>) -> Bitboard {
+    let mut occupied = board.occupied() ^ Bitboard::from_square(mv.from());
+    if let Some(sq) = ep_square {
+        occupied = occupied ^ Bitboard::from_square(sq);
     }
+    occupied
+}
+
+/// Static Exchange Evaluation with known victim.
+///
+/// Returns the material balance after the full capture sequence on the target
+/// square, either side being free to stop capturing. `victim` should be the
+/// piece at the target square (None for en passant and quiet moves, which
+/// start from a gain of zero). Uses a fixed-size gain stack.
+#[inline]
+pub fn see_captured(board: &Board, mv: Move, victim: Option<Piece>) -> i32 {
+    let Some((gain, mut last_value, ep_square)) = initial_exchange(board, mv, victim) else {
+        return 0;
+    };
+    let to = mv.to();
 
-    // Fixed-size gains stack (max 32 captures possible)
+    // gains[d]: balance for the side making capture d if the exchange stopped there
     let mut gains: [i32; 32] = [0; 32];
     let mut depth = 0;
-    gains[depth] = gain;
-    depth += 1;
+    gains[0] = gain;
 
-    let mut occupied = board.occupied() ^ Bitboard::from_square(from);
+    let mut occupied = occupied_after(board, mv, ep_square);
     let mut side = !board.turn();
-    let mut last_value = see_piece_value(attacker_piece);
-    
-    // Simulate the exchange
-    loop {
-        if let Some((sq, piece)) = get_lva(board, to, side, occupied) {
-            occupied = occupied ^ Bitboard::from_square(sq);
-            gains[depth] = last_value;
-            last_value = see_piece_value(piece);
-            depth += 1;
-            side = !side;
-            
-            // King capture ends the sequence
-            if piece == Piece::King {
-                break;
-            }
-        } else {
+
+    while depth < gains.len() - 1 {
+        let Some((sq, piece)) = get_lva(board, to, side, occupied) else {
+            break;
+        };
+        depth += 1;
+        gains[depth] = last_value - gains[depth - 1];
+
+        // The king was just taken: the line before was illegal and ends here
+        if last_value == see_piece_value(Piece::King) {
             break;
         }
+        last_value = see_piece_value(piece);
+        occupied = occupied ^ Bitboard::from_square(sq);
+        side = !side;
     }
-    
-    // Negamax-style evaluation from the end
-    while depth > 1 {
+
+    // Each side picks the better of capturing and stopping, from the end
+    while depth > 0 {
+        gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
         depth -= 1;
-        gains[depth - 1] = gains[depth - 1].max(-gains[depth]);
     }
-    
+
     gains[0]
 }
 
@@ -133,14 +144,68 @@ pub fn see_captured(board: &Board, mv: Move, victim: Option<Piece>) -> i32 {
 /// Returns the material balance after a capture sequence.
 #[inline]
 pub fn see(board: &Board, mv: Move) -> i32 {
-    let victim = board.piece_at(mv.to()).map(|(p, _)| p);
+    let victim = board.piece_on(mv.to());
     see_captured(board, mv, victim)
 }
 
+/// Is the SEE of `mv` (with known victim) at least `threshold`.
+///
+/// Same exchange as `see_captured`, but only the sign relative to the threshold
+/// is tracked (Stockfish-style), so the loop stops as soon as one side can no
+/// longer change the answer instead of resolving every capture.
+#[inline]
+pub fn see_ge_captured(board: &Board, mv: Move, victim: Option<Piece>, threshold: i32) -> bool {
+    let Some((gain, on_square, ep_square)) = initial_exchange(board, mv, victim) else {
+        return 0 >= threshold;
+    };
+    let to = mv.to();
+
+    // Even if the moved piece is lost for nothing we are above the threshold
+    let mut swap = gain - threshold;
+    if swap < 0 {
+        return false;
+    }
+    // Even losing the moved piece keeps us at or above it
+    swap = on_square - swap;
+    if swap <= 0 {
+        return true;
+    }
+
+    let mut occupied = occupied_after(board, mv, ep_square);
+    let mut side = board.turn();
+    // 1 while the side that moved first is at or above the threshold
+    let mut res = 1;
+
+    loop {
+        side = !side;
+        let Some((sq, piece)) = get_lva(board, to, side, occupied) else {
+            break;
+        };
+        res ^= 1;
+
+        if piece == Piece::King {
+            // Taking with the king is only possible if nothing recaptures
+            let recapture = get_lva(board, to, !side, occupied).is_some();
+            return if recapture { res == 0 } else { res == 1 };
+        }
+
+        // `swap` is what `side` stands to lose; if even giving up this piece
+        // does not flip the result, it stops here
+        swap = see_piece_value(piece) - swap;
+        if swap < res {
+            break;
+        }
+        occupied = occupied ^ Bitboard::from_square(sq);
+    }
+
+    res == 1
+}
+
 /// Check if SEE is greater than or equal to threshold
 #[inline]
 pub fn see_ge(board: &Board, mv: Move, threshold: i32) -> bool {
-    see(board, mv) >= threshold
+    let victim = board.piece_on(mv.to());
+    see_ge_captured(board, mv, victim, threshold)
 }
 
 /// Check if a capture is winning (SEE >= 0)
@@ -152,5 +217,75 @@ pub fn is_good_capture(board: &Board, mv: Move) -> bool {
 /// Check if a capture is winning (SEE >= 0) with known victim
 #[inline]
 pub fn is_good_capture_with_victim(board: &Board, mv: Move, victim: Option<Piece>) -> bool {
-    see_captured(board, mv, victim) >= 0
+    see_ge_captured(board, mv, victim, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(board: &Board, uci: &str) -> Move {
+        crate::uci::parse_move(board, uci).unwrap()
+    }
+
+    #[test]
+    fn test_see_values() {
+        // Pawn takes a knight defended by a pawn: +300 -100
+        let board = Board::from_fen("4k3/8/2p5/3n4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(see(&board, parse(&board, "e4d5")), 200);
+
+        // Queen takes a pawn defended by a pawn: +100 -900
+        let board = Board::from_fen("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+        assert_eq!(see(&board, parse(&board, "d1d5")), -800);
+
+        // Rook takes an undefended rook
+        let board = Board::from_fen("4k3/8/8/3r4/8/8/8/3RK3 w - - 0 1").unwrap();
+        assert_eq!(see(&board, parse(&board, "d1d5")), 500);
+
+        // Knight steps onto a square attacked by a pawn
+        let board = Board::from_fen("4k3/8/4p3/8/8/2N5/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(see(&board, parse(&board, "c3d5")), -300);
+        assert!(!see_ge(&board, parse(&board, "c3d5"), -50));
+        assert!(see_ge(&board, parse(&board, "c3b5"), 0));
+    }
+
+    #[test]
+    fn test_see_xray() {
+        // The pawn is defended by one rook and attacked by two, one behind the
+        // other: RxP RxR RxR nets the pawn
+        let board = Board::from_fen("4k3/8/3r4/3p4/8/8/3R4/3RK3 w - - 0 1").unwrap();
+        assert_eq!(see(&board, parse(&board, "d2d5")), 100);
+    }
+
+    #[test]
+    fn test_threshold_matches_full_see() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "2r2rk1/pp1bqppp/2n1pn2/3p4/3P4/2NBPN2/PPQ2PPP/2R2RK1 b - - 0 12",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2",
+            "1k6/1r6/8/1q6/8/1R6/1Q6/1K6 w - - 0 1",
+        ];
+        let mut checked = 0;
+        for fen in fens {
+            let board = Board::from_fen(fen).unwrap();
+            for m in board.generate_moves().iter() {
+                let value = see(&board, m);
+                for threshold in (-1000..=1000).step_by(50) {
+                    assert_eq!(
+                        see_ge(&board, m, threshold),
+                        value >= threshold,
+                        "{} in {}: see {} threshold {}", m, fen, value, threshold
+                    );
+                    checked += 1;
+                }
+                assert!(see_ge(&board, m, value));
+                assert!(!see_ge(&board, m, value + 1));
+            }
+        }
+        assert!(checked > 1000);
+    }
 }
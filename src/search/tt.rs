@@ -5,11 +5,25 @@
 //!
 //! # Design
 //! - 8-byte entries packed into AtomicU64 for lock-free access
-//! - Depth-preferred replacement with age-based eviction
+//! - Clusters of 4 entries (32 bytes, aligned so one never straddles a
+//!   cache line); a hash may live in any entry of its cluster
+//! - Replacement inside a cluster: empty, then oldest generation, then
+//!   shallowest entry
 //! - Lock-free for Lazy SMP multi-threading support
+//!
+//! `probe` and `store` take `&self`, so searchers share one table through an
+//! `Arc`. An entry is a single `AtomicU64`, written and read whole: a reader
+//! sees either the old or the new entry, never half of each, so the
+//! `data ^ key` check two-word entries need to reject torn reads has nothing
+//! to catch here. Two threads racing on one cluster can still both pick the
+//! same slot; one entry is lost, which costs a re-search, not correctness.
+//!
+//! The search only sees the table through the `TtProbe` / `TtStore` traits,
+//! so tests can substitute a table that records its traffic.
 
 use crate::types::{Move, Score, Depth, Hash};
-use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, AtomicU8, Ordering};
 
 /// Type of bound stored in TT entry
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -160,10 +174,75 @@ fn decode_move(encoded: u16) -> Option<Move> {
     Some(Move::from_bits(encoded))
 }
 
+/// Entries per cluster
+pub const CLUSTER_SIZE: usize = 4;
+
+/// Generations are stored in 6 bits
+const GENERATION_MASK: u8 = 0x3F;
+
+/// Slot of `cluster` to write an entry for `hash` searched to `depth` into,
+/// or `None` to keep what is there.
+///
+/// An entry for the same position is only overwritten by one at least as deep
+/// or from a newer search. Otherwise the victim is an empty slot if there is
+/// one, else the entry from the oldest search, else the shallowest entry.
+pub fn replacement_slot(cluster: &[TTEntry; CLUSTER_SIZE], hash: Hash, depth: Depth, generation: u8) -> Option<usize> {
+    let generation = generation & GENERATION_MASK;
+    if let Some(i) = cluster.iter().position(|e| !e.is_empty() && e.matches(hash)) {
+        let existing = cluster[i];
+        let replace = existing.generation() != generation || depth >= existing.depth();
+        return replace.then_some(i);
+    }
+
+    // Lowest (empty, age, depth) priority is evicted: empty first, then the
+    // largest age, then the lowest depth
+    (0..CLUSTER_SIZE).min_by_key(|&i| {
+        let e = cluster[i];
+        let age = generation.wrapping_sub(e.generation()) & GENERATION_MASK;
+        (!e.is_empty(), core::cmp::Reverse(age), e.depth)
+    })
+}
+
+/// Read side of a transposition table, as used by the search
+pub trait TtProbe {
+    /// Entry stored for `hash`, if any
+    fn probe(&self, hash: Hash) -> Option<TTEntry>;
+
+    /// Hint that `hash` is about to be probed
+    #[inline]
+    fn prefetch(&self, _hash: Hash) {}
+}
+
+/// Write side of a transposition table, as used by the search.
+///
+/// `score` is already adjusted for storage (`Score::to_tt`).
+pub trait TtStore {
+    fn store(&self, hash: Hash, best_move: Option<Move>, score: Score, depth: Depth, bound: BoundType);
+}
+
+/// `CLUSTER_SIZE` entries sharing one cache line
+#[repr(align(32))]
+struct Cluster([AtomicU64; CLUSTER_SIZE]);
+
+impl Cluster {
+    fn new() -> Self {
+        Self([AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)])
+    }
+
+    #[inline]
+    fn load(&self) -> [TTEntry; CLUSTER_SIZE] {
+        let mut entries = [TTEntry::default(); CLUSTER_SIZE];
+        for (entry, slot) in entries.iter_mut().zip(&self.0) {
+            *entry = TTEntry::from_u64(slot.load(Ordering::Relaxed));
+        }
+        entries
+    }
+}
+
 /// Lock-free Transposition Table using AtomicU64
 pub struct TranspositionTable {
-    /// Table entries as atomic u64 values
-    entries: Vec<AtomicU64>,
+    /// Table entries as atomic u64 values, in clusters
+    clusters: Vec<Cluster>,
     /// Current generation (incremented each new search)
     generation: AtomicU8,
     /// Size in MB (for reporting)
@@ -184,12 +263,12 @@ impl TranspositionTable {
         let num_entries = num_entries.next_power_of_two() / 2;
         let num_entries = num_entries.max(1024); // Minimum 1024 entries
 
-        let entries = (0..num_entries)
-            .map(|_| AtomicU64::new(0))
+        let clusters = (0..num_entries / CLUSTER_SIZE)
+            .map(|_| Cluster::new())
             .collect();
 
         Self {
-            entries,
+            clusters,
             generation: AtomicU8::new(0),
             size_mb,
         }
@@ -198,13 +277,13 @@ impl TranspositionTable {
     /// Get the number of entries
     #[inline]
     pub fn len(&self) -> usize {
-        self.entries.len()
+        self.clusters.len() * CLUSTER_SIZE
     }
 
     /// Check if table is empty
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.entries.is_empty()
+        self.clusters.is_empty()
     }
 
     /// Get size in MB
@@ -224,32 +303,25 @@ impl TranspositionTable {
         self.generation.fetch_add(1, Ordering::Relaxed);
     }
 
-    /// Get index for a hash
+    /// Index of the cluster for a hash
     #[inline]
     fn index(&self, hash: Hash) -> usize {
         // Fast modulo for power-of-2 size
-        (hash as usize) & (self.entries.len() - 1)
+        (hash as usize) & (self.clusters.len() - 1)
     }
 
-    /// Probe the TT for an entry (lock-free)
+    /// Probe the TT for an entry (lock-free): any entry of the cluster
     #[inline]
     pub fn probe(&self, hash: Hash) -> Option<TTEntry> {
-        let raw = self.entries[self.index(hash)].load(Ordering::Relaxed);
-        if raw == 0 {
-            return None;
-        }
-        
-        let entry = TTEntry::from_u64(raw);
-        if entry.matches(hash) && !entry.is_empty() {
-            Some(entry)
-        } else {
-            None
-        }
+        self.clusters[self.index(hash)]
+            .load()
+            .into_iter()
+            .find(|entry| entry.matches(hash) && !entry.is_empty())
     }
 
     /// Store an entry in the TT (lock-free)
     ///
-    /// Uses depth-preferred replacement with age consideration
+    /// The slot within the cluster is chosen by `replacement_slot`.
     /// Takes &self - uses atomic operations for thread-safety
     pub fn store(
         &self,
@@ -259,44 +331,34 @@ impl TranspositionTable {
         depth: Depth,
         bound: BoundType,
     ) {
-        let idx = self.index(hash);
-        let existing_raw = self.entries[idx].load(Ordering::Relaxed);
-        let existing = TTEntry::from_u64(existing_raw);
+        let cluster = &self.clusters[self.index(hash)];
         let gen = self.generation();
-
-        // Replacement strategy:
-        // 1. Always replace empty entries
-        // 2. Always replace entries from older generations
-        // 3. Replace if new depth >= existing depth
-        let should_replace = existing.is_empty()
-            || existing.generation() != gen
-            || depth.raw() >= existing.depth.into();
-
-        if should_replace {
+        if let Some(i) = replacement_slot(&cluster.load(), hash, depth, gen) {
             let new_entry = TTEntry::new(hash, best_move, score, depth, bound, gen);
-            self.entries[idx].store(new_entry.to_u64(), Ordering::Relaxed);
+            cluster.0[i].store(new_entry.to_u64(), Ordering::Relaxed);
         }
     }
 
     /// Clear the table
     pub fn clear(&self) {
-        for entry in &self.entries {
-            entry.store(0, Ordering::Relaxed);
+        for cluster in &self.clusters {
+            for entry in &cluster.0 {
+                entry.store(0, Ordering::Relaxed);
+            }
         }
         self.generation.store(0, Ordering::Relaxed);
     }
 
     /// Get hashfull in permill (for UCI info)
     pub fn hashfull(&self) -> u32 {
-        let gen = self.generation();
-        // Sample first 1000 entries
-        let sample_size = self.entries.len().min(1000);
-        let used = self.entries[..sample_size]
+        let gen = self.generation() & GENERATION_MASK;
+        // Sample the first 1000 entries
+        let sample_clusters = self.clusters.len().min(1000 / CLUSTER_SIZE);
+        let sample_size = sample_clusters * CLUSTER_SIZE;
+        let used = self.clusters[..sample_clusters]
             .iter()
-            .filter(|e| {
-                let entry = TTEntry::from_u64(e.load(Ordering::Relaxed));
-                !entry.is_empty() && entry.generation() == gen
-            })
+            .flat_map(|cluster| cluster.load())
+            .filter(|entry| !entry.is_empty() && entry.generation() == gen)
             .count();
         ((used * 1000) / sample_size) as u32
     }
@@ -306,16 +368,16 @@ impl TranspositionTable {
     #[inline]
     pub fn prefetch(&self, hash: Hash) {
         let idx = self.index(hash);
-        let ptr = self.entries.as_ptr().wrapping_add(idx) as *const i8;
+        let ptr = self.clusters.as_ptr().wrapping_add(idx) as *const i8;
         
         #[cfg(target_arch = "x86_64")]
         unsafe {
-            std::arch::x86_64::_mm_prefetch(ptr, std::arch::x86_64::_MM_HINT_T0);
+            core::arch::x86_64::_mm_prefetch(ptr, core::arch::x86_64::_MM_HINT_T0);
         }
         
         #[cfg(target_arch = "x86")]
         unsafe {
-            std::arch::x86::_mm_prefetch(ptr, std::arch::x86::_MM_HINT_T0);
+            core::arch::x86::_mm_prefetch(ptr, core::arch::x86::_MM_HINT_T0);
         }
         
         // No-op on other architectures
@@ -324,6 +386,25 @@ impl TranspositionTable {
     }
 }
 
+impl TtProbe for TranspositionTable {
+    #[inline]
+    fn probe(&self, hash: Hash) -> Option<TTEntry> {
+        TranspositionTable::probe(self, hash)
+    }
+
+    #[inline]
+    fn prefetch(&self, hash: Hash) {
+        TranspositionTable::prefetch(self, hash)
+    }
+}
+
+impl TtStore for TranspositionTable {
+    #[inline]
+    fn store(&self, hash: Hash, best_move: Option<Move>, score: Score, depth: Depth, bound: BoundType) {
+        TranspositionTable::store(self, hash, best_move, score, depth, bound)
+    }
+}
+
 impl Default for TranspositionTable {
     fn default() -> Self {
         Self::new(16) // 16 MB default
@@ -365,6 +446,135 @@ mod tests {
         assert_eq!(mv.to(), decoded.to());
     }
     
+    #[test]
+    fn test_promotion_encoding() {
+        use crate::types::Piece;
+
+        let tt = TranspositionTable::new(1);
+        let promotions = [
+            (MoveFlag::PromoKnight, Square::E8, Piece::Knight),
+            (MoveFlag::PromoBishop, Square::E8, Piece::Bishop),
+            (MoveFlag::PromoRook, Square::E8, Piece::Rook),
+            (MoveFlag::PromoQueen, Square::E8, Piece::Queen),
+            (MoveFlag::PromoCaptureKnight, Square::D8, Piece::Knight),
+            (MoveFlag::PromoCaptureBishop, Square::D8, Piece::Bishop),
+            (MoveFlag::PromoCaptureRook, Square::D8, Piece::Rook),
+            (MoveFlag::PromoCaptureQueen, Square::D8, Piece::Queen),
+        ];
+        for (i, (flag, to, piece)) in promotions.into_iter().enumerate() {
+            let mv = Move::new(Square::E7, to, flag);
+            let decoded = decode_move(encode_move(Some(mv))).unwrap();
+            assert_eq!(decoded, mv);
+            assert_eq!(decoded.flag().promotion_piece(), Some(piece));
+            assert_eq!(decoded.is_capture(), to == Square::D8);
+
+            // And through a stored entry
+            let hash = 0x1000 + i as Hash;
+            tt.store(hash, Some(mv), Score::cp(0), Depth::new(3), BoundType::Exact);
+            assert_eq!(tt.probe(hash).unwrap().best_move(), Some(mv));
+        }
+    }
+
+    #[test]
+    fn test_cluster_keeps_deep_entry() {
+        let tt = TranspositionTable::new(1);
+        // Same low bits (same cluster), different keys
+        let hash = |i: u64| 0x0000_1234_5678_9ABC | ((i + 1) << 48);
+
+        tt.store(hash(0), None, Score::cp(10), Depth::new(12), BoundType::Exact);
+        for i in 1..4 {
+            tt.store(hash(i), None, Score::cp(0), Depth::new(1), BoundType::UpperBound);
+        }
+        for i in 0..4 {
+            assert!(tt.probe(hash(i)).is_some(), "entry {i}");
+        }
+        assert_eq!(tt.probe(hash(0)).unwrap().depth().raw(), 12);
+
+        // A fifth position evicts a shallow entry, never the deep one
+        tt.store(hash(4), None, Score::cp(0), Depth::new(2), BoundType::LowerBound);
+        assert!(tt.probe(hash(4)).is_some());
+        assert_eq!(tt.probe(hash(0)).unwrap().depth().raw(), 12);
+        assert_eq!((1..4).filter(|&i| tt.probe(hash(i)).is_some()).count(), 2);
+
+        // A shallower result for a stored position keeps the deeper one...
+        tt.store(hash(0), None, Score::cp(50), Depth::new(3), BoundType::Exact);
+        assert_eq!(tt.probe(hash(0)).unwrap().score().raw(), 10);
+
+        // ...until the next search, whose entries push old ones out first
+        tt.new_search();
+        tt.store(hash(0), None, Score::cp(50), Depth::new(3), BoundType::Exact);
+        assert_eq!(tt.probe(hash(0)).unwrap().score().raw(), 50);
+        tt.store(hash(5), None, Score::cp(0), Depth::new(1), BoundType::Exact);
+        assert_eq!(tt.probe(hash(0)).unwrap().score().raw(), 50);
+    }
+
+    #[test]
+    fn test_replacement_priority() {
+        let hash = |i: u64| (i + 1) << 48;
+        let entry = |i: u64, depth: i32, generation: u8| {
+            TTEntry::new(hash(i), None, Score::cp(0), Depth::new(depth), BoundType::Exact, generation)
+        };
+        let new = hash(9);
+
+        // Empty slot first
+        let cluster = [entry(0, 1, 5), TTEntry::default(), entry(1, 1, 5), entry(2, 1, 5)];
+        assert_eq!(replacement_slot(&cluster, new, Depth::new(1), 5), Some(1));
+        // Then the oldest generation, however deep
+        let cluster = [entry(0, 1, 5), entry(1, 20, 3), entry(2, 9, 4), entry(3, 1, 5)];
+        assert_eq!(replacement_slot(&cluster, new, Depth::new(1), 5), Some(1));
+        // Then the shallowest
+        let cluster = [entry(0, 6, 5), entry(1, 4, 5), entry(2, 2, 5), entry(3, 8, 5)];
+        assert_eq!(replacement_slot(&cluster, new, Depth::new(1), 5), Some(2));
+        // Ages wrap with the 6-bit generation
+        let cluster = [entry(0, 1, 63), entry(1, 1, 0), entry(2, 1, 1), entry(3, 1, 1)];
+        assert_eq!(replacement_slot(&cluster, new, Depth::new(1), 65), Some(0));
+    }
+
+    #[test]
+    fn test_concurrent_store_probe() {
+        use std::sync::Arc;
+        use std::thread;
+
+        // Every field is derived from the key, so any mix of two writes shows up
+        fn expected(hash: Hash) -> (i32, i32) {
+            let key = (hash >> 48) as i32;
+            (key % 2000 - 1000, key % 60 + 1)
+        }
+
+        let tt = Arc::new(TranspositionTable::new(1));
+        let handles: Vec<_> = (0..4u64)
+            .map(|t| {
+                let tt = Arc::clone(&tt);
+                thread::spawn(move || {
+                    let mut x = 0x9E37_79B9_7F4A_7C15u64 ^ (t + 1);
+                    let mut hits = 0;
+                    for _ in 0..50_000 {
+                        // xorshift64: few clusters (low bits), many keys
+                        x ^= x << 13;
+                        x ^= x >> 7;
+                        x ^= x << 17;
+                        let hash = x & 0xFFFF_0000_0000_00FF;
+                        let (score, depth) = expected(hash);
+                        tt.store(hash, None, Score::cp(score), Depth::new(depth), BoundType::Exact);
+
+                        let probe = x.rotate_left(24) & 0xFFFF_0000_0000_00FF;
+                        if let Some(entry) = tt.probe(probe) {
+                            let (score, depth) = expected(probe);
+                            assert_eq!(entry.score().raw(), score);
+                            assert_eq!(entry.depth().raw(), depth);
+                            assert_eq!(entry.bound(), BoundType::Exact);
+                            hits += 1;
+                        }
+                    }
+                    hits
+                })
+            })
+            .collect();
+
+        let hits: u32 = handles.into_iter().map(|h| h.join().unwrap()).sum();
+        assert!(hits > 0);
+    }
+
     #[test]
     fn test_entry_pack_unpack() {
         let entry = TTEntry::new(
@@ -14,7 +14,7 @@ mod depth;
 mod convert;
 
 // Re-export our custom types
-pub use score::{Score, SCORE_INFINITY, SCORE_MATE, SCORE_DRAW, SCORE_NONE};
+pub use score::{Score, ScoreDisplay, ScoreScale, NORMALIZED_PAWN, SCORE_INFINITY, SCORE_MATE, SCORE_DRAW, SCORE_NONE};
 pub use depth::{Depth, Ply, MAX_DEPTH, MAX_PLY};
 pub use convert::{ToNnue, nnue_color_flip};
 
@@ -32,6 +32,8 @@ pub use movegen::{
     MoveFlag,
 };
 
+use alloc::{format, string::{String, ToString}, vec::Vec};
+
 /// Zobrist hash type (used for transposition table)
 pub type Hash = u64;
 
@@ -61,3 +63,284 @@ pub const fn piece_value(piece: Piece) -> Value {
         Piece::King => KING_VALUE,
     }
 }
+
+/// Square lookups on the movegen `Board`.
+///
+/// `Board::piece_at` gives piece and color; `piece_on` is the same lookup
+/// without the color, for code that only needs the piece type (ordering,
+/// SEE, delta pruning). Both read the same board, so they agree on occupancy.
+pub trait BoardExt {
+    /// Piece type on `sq`, if any
+    fn piece_on(&self, sq: Square) -> Option<Piece>;
+
+    /// The legal move from `from` to `to` promoting to `promotion`, if there is one.
+    ///
+    /// The king "capturing" its own rook is read as castling to that side
+    /// (Polyglot books write castling this way). Only moves from the legal
+    /// list are returned, so a castle that is illegal right now is `None`.
+    fn find_move(&self, from: Square, to: Square, promotion: Option<Piece>) -> Option<Move>;
+}
+
+impl BoardExt for Board {
+    #[inline]
+    fn piece_on(&self, sq: Square) -> Option<Piece> {
+        self.piece_at(sq).map(|(p, _)| p)
+    }
+
+    fn find_move(&self, from: Square, to: Square, promotion: Option<Piece>) -> Option<Move> {
+        let us = self.turn();
+        let to = match (self.piece_at(from), self.piece_at(to)) {
+            (Some((Piece::King, c)), Some((Piece::Rook, rc))) if c == us && rc == us && from.rank() == to.rank() => {
+                let kingside = to.file() > from.file();
+                if castling_rook_square(self, us, kingside) != Some(to) {
+                    return None;
+                }
+                let file = if kingside { movegen::File::G } else { movegen::File::C };
+                Square::from_file_rank(file, from.rank())
+            }
+            _ => to,
+        };
+
+        self.generate_moves()
+            .iter()
+            .find(|m| m.from() == from && m.to() == to && m.flag().promotion_piece() == promotion)
+    }
+}
+
+/// Square of the rook `color` castles with on the given side, if it still
+/// has that right.
+///
+/// That is the outermost rook on the king's rank on that side (X-FEN `KQkq`
+/// rights), so Chess960 starting positions are covered: the rook's start file
+/// comes from the position, not from the standard A/H files.
+pub fn castling_rook_square(board: &Board, color: Color, kingside: bool) -> Option<Square> {
+    let rights = board.castling();
+    let allowed = if kingside { rights.has_kingside(color) } else { rights.has_queenside(color) };
+    if !allowed {
+        return None;
+    }
+
+    let king = board.king_square(color);
+    let rooks = (board.piece_bb(Piece::Rook) & board.color_bb(color))
+        .into_iter()
+        .filter(|sq| sq.rank() == king.rank() && sq.file() != king.file() && (sq.file() > king.file()) == kingside);
+    if kingside {
+        rooks.max_by_key(|sq| sq.file())
+    } else {
+        rooks.min_by_key(|sq| sq.file())
+    }
+}
+
+/// UCI text of `m` played in `board`.
+///
+/// With `chess960` castling is written as the king taking its own rook
+/// (`e1h1`), as `UCI_Chess960` GUIs expect; otherwise as the king's two
+/// square move (`e1g1`).
+pub fn move_to_uci(board: &Board, m: Move, chess960: bool) -> String {
+    let kingside = match m.flag() {
+        MoveFlag::KingCastle => true,
+        MoveFlag::QueenCastle => false,
+        _ => return m.to_uci(),
+    };
+    match castling_rook_square(board, board.turn(), kingside).filter(|_| chess960) {
+        Some(rook) => format!("{}{}", m.from(), rook),
+        None => m.to_uci(),
+    }
+}
+
+/// `line` played from `board`, as UCI text (see `move_to_uci`)
+pub fn line_to_uci(board: &Board, line: &[Move], chess960: bool) -> String {
+    let mut board = *board;
+    let mut text = Vec::with_capacity(line.len());
+    for &m in line {
+        text.push(move_to_uci(&board, m, chess960));
+        board = board.make_move_new(m);
+    }
+    text.join(" ")
+}
+
+/// Dark squares (a1, c1, ..., h8)
+pub const DARK_SQUARES: u64 = 0xAA55_AA55_AA55_AA55;
+
+/// Neither side has the material to ever deliver mate: bare kings, a single
+/// knight, or bishops that all stand on squares of one color (KBvK, KBvKB
+/// with same-colored bishops).
+///
+/// Any pawn, rook or queen on the board means the game is still alive.
+pub fn is_insufficient_material(board: &Board) -> bool {
+    let alive = board.piece_bb(Piece::Pawn) | board.piece_bb(Piece::Rook) | board.piece_bb(Piece::Queen);
+    if alive.bits() != 0 {
+        return false;
+    }
+
+    let knights = board.piece_bb(Piece::Knight).count();
+    let bishops = board.piece_bb(Piece::Bishop).bits();
+    match knights {
+        0 => bishops & DARK_SQUARES == 0 || bishops & DARK_SQUARES == bishops,
+        1 => bishops == 0,
+        _ => false,
+    }
+}
+
+/// Halfmove clock after playing `m` on `board`: captures and pawn moves reset it
+#[inline]
+pub fn next_halfmove_clock(board: &Board, m: Move, clock: u32) -> u32 {
+    let pawn_move = matches!(board.piece_at(m.from()), Some((Piece::Pawn, _)));
+    if m.is_capture() || pawn_move {
+        0
+    } else {
+        clock + 1
+    }
+}
+
+/// `fen` with colors swapped and ranks flipped: the same position seen from
+/// the other side. Castling rights and the en passant square follow the pieces.
+pub fn mirror_fen(fen: &str) -> String {
+    let swap = |c: char| if c.is_ascii_uppercase() { c.to_ascii_lowercase() } else { c.to_ascii_uppercase() };
+    let mut fields = fen.split_whitespace();
+
+    let placement: Vec<String> = fields
+        .next()
+        .unwrap_or("")
+        .split('/')
+        .rev()
+        .map(|rank| rank.chars().map(swap).collect())
+        .collect();
+    let turn = match fields.next() {
+        Some("b") => "w",
+        _ => "b",
+    };
+    let castling = match fields.next() {
+        None | Some("-") => "-".to_string(),
+        Some(rights) => {
+            let mut swapped: Vec<char> = rights.chars().map(swap).collect();
+            // Keep the usual order: white's rights first
+            swapped.sort_by_key(|c| c.is_ascii_lowercase());
+            swapped.into_iter().collect()
+        }
+    };
+    let ep = match fields.next() {
+        Some(sq) if sq.len() == 2 => {
+            let mut chars = sq.chars();
+            let file = chars.next().unwrap_or('a');
+            let rank = match chars.next() {
+                Some('3') => '6',
+                Some('6') => '3',
+                Some(r) => r,
+                None => '-',
+            };
+            format!("{}{}", file, rank)
+        }
+        _ => "-".to_string(),
+    };
+
+    let mut mirrored = format!("{} {} {} {}", placement.join("/"), turn, castling, ep);
+    for clock in fields {
+        mirrored.push(' ');
+        mirrored.push_str(clock);
+    }
+    mirrored
+}
+
+/// `board` with colors swapped and ranks flipped (see `mirror_fen`).
+///
+/// A symmetric evaluation scores both from the side to move alike.
+pub fn mirror_board(board: &Board) -> Board {
+    Board::from_fen(&mirror_fen(&board.to_fen())).expect("mirror of a legal position is legal")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insufficient_material() {
+        let dead = [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",     // KvK
+            "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1",   // KNvK
+            "4k3/8/8/8/8/8/8/2B1K3 b - - 0 1",   // KBvK
+            "1n2k3/8/8/8/8/8/8/4K3 w - - 0 1",   // KvKN
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",  // KBvKB, both on dark squares
+        ];
+        for fen in dead {
+            assert!(is_insufficient_material(&Board::from_fen(fen).unwrap()), "{}", fen);
+        }
+
+        let alive = [
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",   // KPvK
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",    // KRvK
+            "4k3/8/8/8/8/8/8/3QK3 w - - 0 1",    // KQvK
+            "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1", // KBvKB, opposite colors
+            "4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1",  // KNNvK
+            "4kb2/8/8/8/8/8/8/1N2K3 w - - 0 1",  // KNvKB
+            "4k3/p7/8/8/8/8/8/2B1K3 w - - 0 1",  // KBvKP
+        ];
+        for fen in alive {
+            assert!(!is_insufficient_material(&Board::from_fen(fen).unwrap()), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_castling_rook_square() {
+        // King on f1 between rooks on b1 and g1, an extra rook on a1
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/RR3KR1 w K - 0 1").unwrap();
+        assert_eq!(castling_rook_square(&board, Color::White, true), Some(Square::G1));
+        // No queenside right
+        assert_eq!(castling_rook_square(&board, Color::White, false), None);
+
+        // The outermost rook is the castling one
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/RR3KR1 w Q - 0 1").unwrap();
+        assert_eq!(castling_rook_square(&board, Color::White, false), Some(Square::A1));
+    }
+
+    #[test]
+    fn test_mirror_fen() {
+        assert_eq!(
+            mirror_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            "rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1"
+        );
+        assert_eq!(mirror_fen("4k3/8/8/8/8/8/8/R3K3 w Qk - 5 40"), "r3k3/8/8/8/8/8/8/4K3 b Kq - 5 40");
+        // Mirroring twice is the identity
+        let fen = "r3k2r/1pp2ppp/p1n5/4P3/3q4/2N5/PPP2PPP/R2QK2R w Kq - 0 12";
+        assert_eq!(mirror_fen(&mirror_fen(fen)), fen);
+    }
+
+    #[test]
+    fn test_null_move_restores_hash() {
+        let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        let hash = board.hash();
+
+        let null = board.make_null_move();
+        assert_ne!(null.turn(), board.turn());
+        assert_ne!(null.hash(), hash);
+
+        // Copy-make: the original board is the unmade position
+        assert_eq!(board.hash(), hash);
+        // Passing twice returns to the same position
+        assert_eq!(null.make_null_move().hash(), hash);
+    }
+
+    #[test]
+    fn test_piece_on_agrees_with_piece_at() {
+        let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        for idx in 0..64 {
+            let sq = Square::from_index(idx).unwrap();
+            let full = board.piece_at(sq);
+            assert_eq!(board.piece_on(sq), full.map(|(p, _)| p));
+            assert_eq!(full.is_some(), (board.occupied() & Bitboard::from_square(sq)).any());
+            if let Some((piece, color)) = full {
+                assert!((board.piece_bb(piece) & board.color_bb(color) & Bitboard::from_square(sq)).any());
+            }
+        }
+    }
+
+    #[test]
+    fn test_null_move_clears_en_passant() {
+        let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        assert!(board.ep_square().is_some());
+
+        let null = board.make_null_move();
+        assert!(null.ep_square().is_none());
+        assert_eq!(null.turn(), Color::White);
+    }
+}
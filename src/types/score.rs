@@ -3,8 +3,8 @@
 //! Handles regular centipawn scores, mate scores, draws, and special values.
 //! Optimized for alpha-beta search with proper mate score handling.
 
-use std::fmt;
-use std::ops::{Add, Sub, Neg};
+use core::fmt;
+use core::ops::{Add, Sub, Neg};
 
 /// Special score values
 /// Special score values
@@ -13,6 +13,10 @@ pub const SCORE_INFINITY: i32 = 32000;
 pub const SCORE_MATE: i32 = 31000;
 pub const SCORE_DRAW: i32 = 0;
 
+/// Internal units printed as 100 cp in normalized output. The NNUE scale runs
+/// above material centipawns; retune this with the network.
+pub const NORMALIZED_PAWN: i32 = 150;
+
 // Mate score bounds for detection
 const SCORE_MATE_IN_MAX: i32 = SCORE_MATE - 1000;
 const SCORE_MATED_IN_MAX: i32 = -SCORE_MATE + 1000;
@@ -23,6 +27,13 @@ const SCORE_MATED_IN_MAX: i32 = -SCORE_MATE + 1000;
 /// Mate in N is encoded as `SCORE_MATE - N`, mated in N as `-SCORE_MATE + N`.
 ///
 /// Underlying storage is `i16` for memory efficiency.
+///
+/// `Ord` compares the raw value, which the encoding makes mate-aware: every
+/// mate is above every centipawn score, a mate in fewer plies is greater
+/// (`mate_in(3) > mate_in(5)`), and being mated in fewer plies is smaller
+/// (`mated_in(3) < mated_in(5)`). So `score > alpha` prefers the shorter mate
+/// and the longer defence without special cases. `none()` sorts below
+/// `neg_infinity()` and must not take part in comparisons.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[repr(transparent)]
 pub struct Score(pub i16);
@@ -76,6 +87,18 @@ impl Score {
         self.0 as i32
     }
 
+    /// The better of two scores (same order as `Ord`: shorter mates win)
+    #[inline]
+    pub const fn max(self, other: Self) -> Self {
+        if self.0 >= other.0 { self } else { other }
+    }
+
+    /// The worse of two scores (same order as `Ord`: being mated sooner is worse)
+    #[inline]
+    pub const fn min(self, other: Self) -> Self {
+        if self.0 <= other.0 { self } else { other }
+    }
+
     /// Check if this is a mate score (winning)
     #[inline]
     pub const fn is_mate(self) -> bool {
@@ -106,6 +129,21 @@ impl Score {
         }
     }
 
+    /// Signed distance to mate in full moves, as reported by UCI `score mate`.
+    ///
+    /// Positive when the side to move mates (an odd number of plies, so mate in
+    /// N moves is ply 2N-1), negative when it gets mated (ply 2N).
+    #[inline]
+    pub const fn mate_moves(self) -> Option<i32> {
+        if self.is_mate() {
+            Some((SCORE_MATE - self.0 as i32 + 1) / 2)
+        } else if self.is_mated() {
+            Some(-(self.0 as i32 + SCORE_MATE) / 2)
+        } else {
+            None
+        }
+    }
+
     /// Adjust a mate score when storing in TT (relative to current ply)
     ///
     /// Mate scores need ply adjustment because the TT stores position-relative scores:
@@ -137,12 +175,38 @@ impl Score {
 
     #[inline]
     pub const fn add_const(self, rhs: i32) -> Self {
-        Score(self.0 + rhs as i16)
+        self.add_saturating(rhs)
     }
 
     #[inline]
     pub const fn sub_const(self, rhs: i32) -> Self {
-        Score(self.0 - rhs as i16)
+        self.sub_saturating(rhs)
+    }
+
+    /// Add a centipawn margin, clamped to ±infinity instead of wrapping.
+    ///
+    /// Use this when adding pruning margins to bounds that may be infinite.
+    #[inline]
+    pub const fn add_saturating(self, margin: i32) -> Self {
+        Self::saturate(self.0 as i32 + margin)
+    }
+
+    /// Subtract a centipawn margin, clamped to ±infinity instead of wrapping.
+    #[inline]
+    pub const fn sub_saturating(self, margin: i32) -> Self {
+        Self::saturate(self.0 as i32 - margin)
+    }
+
+    /// Clamp an i32 result into [-SCORE_INFINITY, SCORE_INFINITY]
+    #[inline]
+    const fn saturate(v: i32) -> Self {
+        if v > SCORE_INFINITY {
+            Score(SCORE_INFINITY as i16)
+        } else if v < -SCORE_INFINITY {
+            Score(-SCORE_INFINITY as i16)
+        } else {
+            Score(v as i16)
+        }
     }
 }
 
@@ -150,7 +214,7 @@ impl Add for Score {
     type Output = Self;
     #[inline]
     fn add(self, rhs: Self) -> Self {
-        Score(self.0 + rhs.0)
+        self.add_saturating(rhs.0 as i32)
     }
 }
 
@@ -158,7 +222,7 @@ impl Sub for Score {
     type Output = Self;
     #[inline]
     fn sub(self, rhs: Self) -> Self {
-        Score(self.0 - rhs.0)
+        self.sub_saturating(rhs.0 as i32)
     }
 }
 
@@ -177,20 +241,50 @@ impl From<i32> for Score {
     }
 }
 
-impl fmt::Display for Score {
+/// Centipawn representation for printed scores
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreScale {
+    /// Raw search values (diagnostics: what the search actually compares)
+    Internal,
+    /// Internal values rescaled so 100 cp is `NORMALIZED_PAWN` (UCI output)
+    Normalized,
+}
+
+/// `Score` formatted as `cp <n>` / `mate <n>` on a chosen scale (see `Score::display`)
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreDisplay(Score, ScoreScale);
+
+impl Score {
+    /// Centipawns on the normalized scale (meaningless for mate scores)
+    #[inline]
+    pub const fn normalized_cp(self) -> i32 {
+        self.0 as i32 * 100 / NORMALIZED_PAWN
+    }
+
+    /// Format on `scale`; mate scores print the same on both.
+    /// `Display` prints the internal scale.
+    pub fn display(self, scale: ScoreScale) -> ScoreDisplay {
+        ScoreDisplay(self, scale)
+    }
+}
+
+impl fmt::Display for ScoreDisplay {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.is_mate() {
-            let moves = (SCORE_MATE - self.0 as i32 + 1) / 2;
-            write!(f, "mate {}", moves)
-        } else if self.is_mated() {
-            let moves = (self.0 as i32 + SCORE_MATE + 1) / 2;
-            write!(f, "mate -{}", moves)
-        } else {
-            write!(f, "cp {}", self.0)
+        let ScoreDisplay(score, scale) = *self;
+        match (score.mate_moves(), scale) {
+            (Some(moves), _) => write!(f, "mate {}", moves),
+            (None, ScoreScale::Internal) => write!(f, "cp {}", score.0),
+            (None, ScoreScale::Normalized) => write!(f, "cp {}", score.normalized_cp()),
         }
     }
 }
 
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display(ScoreScale::Internal).fmt(f)
+    }
+}
+
 impl fmt::Debug for Score {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "Score({})", self)
@@ -214,6 +308,55 @@ mod tests {
         assert_eq!(mated_in_2.mate_distance(), Some(3));
     }
 
+    #[test]
+    fn test_mate_ordering() {
+        assert!(Score::mate_in(3) > Score::mate_in(5));
+        assert!(Score::mated_in(3) < Score::mated_in(5));
+        assert!(Score::mate_in(99) > Score::cp(3000));
+        assert!(Score::mated_in(99) < Score::cp(-3000));
+
+        assert_eq!(Score::mate_in(5).max(Score::mate_in(3)), Score::mate_in(3));
+        assert_eq!(Score::mated_in(5).min(Score::mated_in(3)), Score::mated_in(3));
+        assert_eq!(Score::cp(20).max(Score::mated_in(2)), Score::cp(20));
+        assert_eq!(Score::cp(20).min(Score::mate_in(2)), Score::cp(20));
+        // Same answer as the `Ord` methods
+        assert_eq!(Score::mate_in(7).max(Score::cp(5)), Ord::max(Score::mate_in(7), Score::cp(5)));
+    }
+
+    #[test]
+    fn test_mate_display() {
+        assert_eq!(Score::mate_in(1).to_string(), "mate 1");
+        assert_eq!(Score::mate_in(3).to_string(), "mate 2");
+        assert_eq!(Score::mated_in(2).to_string(), "mate -1");
+        assert_eq!(Score::mated_in(4).to_string(), "mate -2");
+        // Already checkmated: no moves left, and no "-0"
+        assert_eq!(Score::mated_in(0).to_string(), "mate 0");
+        assert_eq!(Score::cp(-35).to_string(), "cp -35");
+    }
+
+    #[test]
+    fn test_display_scales() {
+        let score = Score::cp(NORMALIZED_PAWN * 2);
+        assert_eq!(score.display(ScoreScale::Internal).to_string(), format!("cp {}", NORMALIZED_PAWN * 2));
+        assert_eq!(score.display(ScoreScale::Normalized).to_string(), "cp 200");
+        assert_eq!(score.to_string(), score.display(ScoreScale::Internal).to_string());
+        assert_eq!(Score::cp(-NORMALIZED_PAWN).display(ScoreScale::Normalized).to_string(), "cp -100");
+
+        // Mates are not rescaled
+        assert_eq!(Score::mate_in(3).display(ScoreScale::Normalized).to_string(), "mate 2");
+        assert_eq!(Score::mated_in(2).display(ScoreScale::Internal).to_string(), "mate -1");
+    }
+
+    #[test]
+    fn test_saturating_arithmetic() {
+        assert_eq!(Score::infinity().add_const(100), Score::infinity());
+        assert_eq!(Score::neg_infinity().sub_const(100), Score::neg_infinity());
+        assert_eq!(Score::infinity() + Score::cp(500), Score::infinity());
+        assert_eq!(Score::neg_infinity() - Score::infinity(), Score::neg_infinity());
+        assert_eq!(Score::cp(100).add_saturating(50), Score::cp(150));
+        assert_eq!(Score::cp(100).sub_saturating(150), Score::cp(-50));
+    }
+
     #[test]
     fn test_tt_adjustment() {
         let mate = Score::mate_in(5);
@@ -1,10 +1,10 @@
 //! UCI command handler and main loop.
 
 use super::parser::{parse_command, UciCommand};
-use super::{parse_move, format_move, SearchParams, ENGINE_NAME, ENGINE_AUTHOR};
-use crate::types::{Board, Move, Score};
-use crate::search::{Searcher, SearchLimits};
-use crate::eval::nnue;
+use super::{parse_move_with, format_move, fen_clocks, is_legal, SearchParams, ENGINE_NAME, ENGINE_AUTHOR};
+use crate::types::{is_insufficient_material, line_to_uci, move_to_uci, Board, Color, Depth, Move, Score, ScoreScale, MAX_DEPTH, next_halfmove_clock};
+use crate::search::{max_threads, Searcher, SearchLimits, MAX_CONTEMPT};
+use crate::eval::{endgame, hce, nnue, SearchEvaluator};
 use crate::book::PolyglotBook;
 use std::io::{self, BufRead, Write};
 
@@ -20,12 +20,26 @@ pub struct UciHandler {
     use_own_book: bool,
     /// Path to opening book file
     book_path: String,
+    /// Book moves are only played before this game ply
+    book_depth: u32,
+    /// Plies played since the `position` start (length of its move list)
+    game_ply: u32,
+    /// Book moves played this game
+    book_hits: u32,
     /// Debug mode enabled
     debug: bool,
     /// Should the engine quit
     quit: bool,
     /// Move overhead in milliseconds (safety buffer for time control)
     move_overhead: u64,
+    /// Root lines to search and report (`MultiPV`)
+    multi_pv: usize,
+    /// `UCI_Chess960`: castling is sent and read as king-takes-rook
+    chess960: bool,
+    /// Plies since the last capture or pawn move (fifty-move rule)
+    halfmove_clock: u32,
+    /// Fullmove number of the current position
+    fullmove_number: u32,
 }
 
 impl Default for UciHandler {
@@ -60,9 +74,16 @@ impl UciHandler {
             book: None, // No automatic book loading
             use_own_book: false, // Disabled by default (standard UCI behavior)
             book_path: String::new(), // No default path
+            book_depth: 255,
+            game_ply: 0,
+            book_hits: 0,
             debug: false,
             quit: false,
             move_overhead: 10, // Default 10ms
+            multi_pv: 1,
+            chess960: false,
+            halfmove_clock: 0,
+            fullmove_number: 1,
         }
     }
 
@@ -99,14 +120,16 @@ impl UciHandler {
             UciCommand::Debug(on) => self.cmd_debug(on),
             UciCommand::IsReady => self.cmd_isready(),
             UciCommand::SetOption { name, value } => self.cmd_setoption(&name, value.as_deref()),
-            UciCommand::Register => {} // Ignore registration
+            UciCommand::Register => self.cmd_register(),
             UciCommand::UciNewGame => self.cmd_ucinewgame(),
             UciCommand::Position { fen, moves } => self.cmd_position(fen.as_deref(), &moves),
             UciCommand::Go(params) => self.cmd_go(params),
+            UciCommand::Perft(depth) => self.cmd_perft(depth),
             UciCommand::Stop => self.cmd_stop(),
             UciCommand::PonderHit => self.cmd_ponderhit(),
             UciCommand::Quit => self.cmd_quit(),
             UciCommand::Display => self.cmd_display(),
+            UciCommand::Eval => self.cmd_eval(),
             UciCommand::Unknown(s) => {
                 if self.debug {
                     eprintln!("Unknown command: {}", s);
@@ -129,22 +152,40 @@ impl UciHandler {
         
         // Send options
         self.send("option name Hash type spin default 16 min 1 max 16384");
-        self.send("option name Threads type spin default 1 min 1 max 64");
+        self.send(&format!("option name Threads type spin default 1 min 1 max {}", max_threads()));
         self.send("option name MoveOverhead type spin default 10 min 0 max 5000");
+        self.send("option name MultiPV type spin default 1 min 1 max 256");
+        self.send(&format!("option name Contempt type spin default 0 min -{} max {}", MAX_CONTEMPT, MAX_CONTEMPT));
         self.send("option name OwnBook type check default false");
         self.send("option name BookPath type string default <empty>");
+        self.send("option name BookDepth type spin default 255 min 0 max 1000");
+        self.send("option name EvalFileEndgame type string default <empty>");
+        self.send(&format!("option name MinDepth type spin default 1 min 1 max {}", MAX_DEPTH));
+        self.send(&format!("option name MaxDepth type spin default {} min 1 max {}", MAX_DEPTH, MAX_DEPTH));
+        self.send("option name UCI_ShowCurrLine type check default false");
+        self.send("option name UCI_Chess960 type check default false");
         
         self.send("uciok");
     }
 
     fn cmd_debug(&mut self, on: bool) {
         self.debug = on;
+        // Debug mode also prints search profiling as info strings
+        self.searcher.profiling = on;
     }
 
     fn cmd_isready(&self) {
         self.send("readyok");
     }
 
+    /// `register later` / `register name .. code ..`: the engine is free, so
+    /// any registration is accepted and nothing changes
+    fn cmd_register(&self) {
+        if self.debug {
+            self.send("info string registration not required");
+        }
+    }
+
     fn cmd_setoption(&mut self, name: &str, value: Option<&str>) {
         match name.to_lowercase().as_str() {
             "hash" => {
@@ -171,6 +212,21 @@ impl UciHandler {
                     }
                 }
             }
+            "multipv" => {
+                if let Some(v) = value {
+                    if let Ok(n) = v.parse::<usize>() {
+                        self.multi_pv = n.clamp(1, 256);
+                    }
+                }
+            }
+            "uci_chess960" => {
+                self.chess960 = value.is_some_and(|v| v.eq_ignore_ascii_case("true"));
+                self.searcher.chess960 = self.chess960;
+            }
+            "uci_showcurrline" => {
+                let on = value.is_some_and(|v| v.eq_ignore_ascii_case("true"));
+                self.searcher.currline = if on { Some(|line: &str| println!("{}", line)) } else { None };
+            }
             "ownbook" => {
                 if let Some(v) = value {
                     self.use_own_book = v.to_lowercase() == "true";
@@ -223,8 +279,36 @@ impl UciHandler {
                     }
                 }
             }
+            "bookdepth" => {
+                if let Some(v) = value {
+                    if let Ok(plies) = v.parse::<u32>() {
+                        self.book_depth = plies.min(1000);
+                    }
+                }
+            }
+            "evalfileendgame" => {
+                match value {
+                    Some(path) if !path.is_empty() && path != "<empty>" => {
+                        match nnue::load_model(path) {
+                            Ok(model) => {
+                                println!("info string Endgame NNUE loaded: {}", path);
+                                self.searcher.set_nnue_endgame(Some(model));
+                            }
+                            Err(e) => {
+                                println!("info string Failed to load endgame NNUE {}: {:?}", path, e);
+                                self.searcher.set_nnue_endgame(None);
+                            }
+                        }
+                    }
+                    _ => self.searcher.set_nnue_endgame(None),
+                }
+            }
             _ => {
-                if self.debug {
+                // Search parameters: MinDepth/MaxDepth and hidden tuning options
+                let tuned = value
+                    .and_then(|v| v.parse::<i32>().ok())
+                    .is_some_and(|v| self.searcher.params.set(name, v));
+                if !tuned && self.debug {
                     eprintln!("Unknown option: {}", name);
                 }
             }
@@ -232,14 +316,13 @@ impl UciHandler {
     }
 
     fn cmd_ucinewgame(&mut self) {
-        // Preserve NNUE model before resetting
-        let nnue_model = self.searcher.nnue.take();
-        
         self.board = Board::default();
-        self.searcher = Searcher::new();
-        
-        // Restore NNUE model
-        self.searcher.nnue = nnue_model;
+        self.halfmove_clock = 0;
+        self.fullmove_number = 1;
+        self.game_ply = 0;
+        self.book_hits = 0;
+        // Clears the tables in place: NNUE, Hash, Threads and parameters stay
+        self.searcher.new_game();
     }
 
     fn cmd_position(&mut self, fen: Option<&str>, moves: &[String]) {
@@ -248,70 +331,116 @@ impl UciHandler {
             Some(f) => Board::from_fen(f).unwrap_or_default(),
             None => Board::default(),
         };
+        (self.halfmove_clock, self.fullmove_number) = fen.map(fen_clocks).unwrap_or((0, 1));
+        self.game_ply = 0;
 
-        // Track position hashes for repetition detection
-        let mut history: Vec<u64> = Vec::with_capacity(moves.len() + 1);
-        history.push(self.board.hash());
+        // Hashes of the positions before the current one, for repetition detection
+        let mut history: Vec<u64> = Vec::with_capacity(moves.len());
 
         // Apply moves
         for move_str in moves {
-            if let Some(m) = parse_move(&self.board, move_str) {
-                self.board = self.board.make_move_new(m);
+            if let Some(m) = parse_move_with(&self.board, move_str, self.chess960) {
                 history.push(self.board.hash());
+                self.halfmove_clock = next_halfmove_clock(&self.board, m, self.halfmove_clock);
+                if self.board.turn() == Color::Black {
+                    self.fullmove_number += 1;
+                }
+                self.board = self.board.make_move_new(m);
+                self.game_ply += 1;
             } else if self.debug {
                 eprintln!("Invalid move: {}", move_str);
             }
         }
         
+        // Incrementally updated hash must match a from-scratch one (the TT and
+        // repetition detection rely on it)
+        debug_assert_eq!(
+            self.board.hash(),
+            Board::from_fen(&self.board.to_fen()).map(|b| b.hash()).unwrap_or(self.board.hash())
+        );
+
         // Store history in searcher for repetition detection
         self.searcher.set_position_with_history(self.board, history);
+        self.searcher.set_halfmove_clock(self.halfmove_clock);
     }
 
     fn cmd_go(&mut self, params: SearchParams) {
         // Try opening book first (unless infinite or analysis mode)
-        if self.use_own_book && !params.infinite && params.searchmoves.is_empty() {
-            if let Some(ref book) = self.book {
-                if let Some(book_move) = book.probe_move(&self.board) {
-                    self.send(&format!("info string book move"));
-                    self.send(&format!("bestmove {}", format_move(book_move)));
-                    return;
-                }
-            }
+        if let Some(book_move) = self.book_move(&params) {
+            self.book_hits += 1;
+            let book_move = move_to_uci(&self.board, book_move, self.chess960);
+            self.send(&format!("info string book move {} (book hits this game: {})", book_move, self.book_hits));
+            self.send(&format!("bestmove {}", book_move));
+            return;
         }
 
         // Set up search limits with move overhead
         let limits = SearchLimits::from_params(&params)
-            .with_move_overhead(self.move_overhead);
+            .with_move_overhead(self.move_overhead)
+            .with_multi_pv(self.multi_pv);
+        let limits = self.dead_draw_limits(limits, &params);
         
-        // Set position and run search
-        self.searcher.set_position(self.board);
+        // The position (with its history and halfmove clock) was set by `position`
         let result = self.searcher.search(limits);
 
         // Send info
         let stats = result.stats;
-        let pv_str: String = result.pv.iter()
-            .map(|m| format_move(*m))
-            .collect::<Vec<_>>()
-            .join(" ");
+        let pv_str = line_to_uci(&self.board, &result.pv, self.chess960);
 
         self.send(&format!(
-            "info depth {} seldepth {} score {} nodes {} nps {} time {} pv {}",
+            "info depth {} seldepth {} score {} nodes {} nps {} tbhits {} time {} pv {}",
             stats.depth.raw(),
             stats.seldepth.raw(),
-            result.score,
+            result.score.display(ScoreScale::Normalized),
             stats.nodes,
             stats.nps(),
+            stats.tbhits,
             stats.time_ms,
             pv_str
         ));
 
         // Send best move
         match result.best_move {
-            Some(m) => self.send(&format!("bestmove {}", format_move(m))),
+            Some(m) => self.send(&format!("bestmove {}", move_to_uci(&self.board, m, self.chess960))),
             None => self.send("bestmove 0000"),
         }
     }
 
+    /// Limits for a position neither side can win any more: any legal move
+    /// keeps the draw, so a one-ply search stands in for spending the clock
+    /// (and risking a flag) on it. Infinite searches are left alone.
+    fn dead_draw_limits(&self, mut limits: SearchLimits, params: &SearchParams) -> SearchLimits {
+        if !params.infinite && is_insufficient_material(&self.board) {
+            limits.depth = Some(Depth::new(1));
+        }
+        limits
+    }
+
+    /// Book move to play instead of searching, if any
+    fn book_move(&self, params: &SearchParams) -> Option<Move> {
+        if !self.use_own_book || params.infinite || !params.searchmoves.is_empty() {
+            return None;
+        }
+        // Past the book depth: search, even if the book has the position
+        if self.game_ply >= self.book_depth {
+            return None;
+        }
+        // Verify before playing: a hash collision must not make us
+        // send an illegal move. Search instead.
+        let book = self.book.as_ref()?;
+        book.probe_move(&self.board).filter(|&m| is_legal(&self.board, m))
+    }
+
+    fn cmd_perft(&self, depth: u32) {
+        let divide = super::perft_divide(&self.board, depth);
+        for (m, nodes) in &divide {
+            self.send(&format!("{}: {}", move_to_uci(&self.board, *m, self.chess960), nodes));
+        }
+        let total: u64 = divide.iter().map(|&(_, nodes)| nodes).sum();
+        self.send("");
+        self.send(&format!("Nodes searched: {}", total));
+    }
+
     fn cmd_stop(&mut self) {
         self.searcher.stop();
     }
@@ -324,14 +453,74 @@ impl UciHandler {
         self.quit = true;
     }
 
+    fn cmd_eval(&self) {
+        let eval = self.eval_breakdown();
+        let nnue = eval.nnue.map_or_else(|| "none loaded".to_string(), |score| score.raw().to_string());
+        self.send("Static evaluation (centipawns, White's point of view)");
+        self.send(&format!("NNUE:     {}", nnue));
+        self.send(&format!("HCE:      {}", eval.hce.raw()));
+        self.send(&format!("Endgame:  {}", if eval.endgame { "active" } else { "inactive" }));
+        self.send(&format!("Material: {}", eval.material));
+        self.send(&format!("Phase:    {} / 256", eval.phase));
+        self.send(&format!("Total:    {}", eval.total.raw()));
+    }
+
+    /// Evaluate the current position the way the search would, and its parts
+    fn eval_breakdown(&self) -> EvalBreakdown {
+        let board = &self.board;
+        let params = &self.searcher.params;
+        let white = |score: Score| if board.turn() == Color::White { score } else { -score };
+
+        let mut evaluator = SearchEvaluator::with_kind(
+            params.eval_kind,
+            self.searcher.nnue.as_ref(),
+            self.searcher.nnue_endgame.as_ref(),
+            board,
+        )
+        .with_endgame(params.endgame)
+        .with_pst(&params.pst)
+        .with_scale(params.eval_scale)
+        .with_output_scale(params.output_scaling.then_some(&params.output_scale));
+        evaluator.set_halfmove_clock(self.halfmove_clock);
+        let total = evaluator.evaluate(board);
+
+        EvalBreakdown {
+            total: white(total),
+            nnue: self.searcher.nnue.as_ref().map(|model| white(nnue::evaluate_scratch(model, board))),
+            hce: white(hce::evaluate_with(board, &params.pst)),
+            endgame: endgame::should_use_endgame(board, &params.endgame),
+            material: hce::material_balance(board),
+            phase: hce::calculate_phase(board),
+        }
+    }
+
     fn cmd_display(&self) {
         // Non-standard debug command to display the board
         eprintln!("{:?}", self.board);
         eprintln!("FEN: {}", self.board.to_fen());
         eprintln!("Side to move: {:?}", self.board.turn());
+        eprintln!("Halfmove clock: {} Fullmove: {}", self.halfmove_clock, self.fullmove_number);
     }
 }
 
+/// Static evaluation of a position, split up for the `eval` command.
+///
+/// Scores are centipawns from White's point of view.
+struct EvalBreakdown {
+    /// What the search would use (eval kind, endgame switch, scaling applied)
+    total: Score,
+    /// Main network on its own, if one is loaded
+    nnue: Option<Score>,
+    /// Hand-crafted eval
+    hce: Score,
+    /// The heuristic endgame eval replaces the others here
+    endgame: bool,
+    /// Material balance
+    material: i32,
+    /// Game phase as used by the HCE (0 = opening, 256 = endgame)
+    phase: i32,
+}
+
 /// Info message builder for search output
 #[allow(dead_code)]
 pub struct InfoBuilder {
@@ -355,7 +544,7 @@ impl InfoBuilder {
     }
 
     pub fn score(mut self, s: Score) -> Self {
-        self.parts.push(format!("score {}", s));
+        self.parts.push(format!("score {}", s.display(ScoreScale::Normalized)));
         self
     }
 
@@ -397,3 +586,181 @@ impl Default for InfoBuilder {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::{polyglot_hash, BookEntry};
+    use crate::types::{Piece, Ply, Square};
+    use crate::uci::parse_move;
+
+    #[test]
+    fn test_book_depth_limits_book_moves() {
+        let mut handler = UciHandler::new();
+        handler.handle_input("position startpos moves e2e4 e7e5 g1f3 b8c6");
+
+        // Book entry for the position after 4 plies: Bb5 (f1b5)
+        let raw_move = (5 << 6) | (4 << 3) | 1;
+        handler.book = Some(PolyglotBook::from_entries(vec![BookEntry {
+            key: polyglot_hash(&handler.board),
+            raw_move,
+            weight: 1,
+            learn: 0,
+        }]));
+        handler.handle_input("setoption name OwnBook value true");
+
+        let params = SearchParams::fixed_depth(1);
+        let bb5 = parse_move(&handler.board, "f1b5");
+        assert!(bb5.is_some());
+        assert_eq!(handler.book_move(&params), bb5);
+
+        // BookDepth 4: the 5th ply is searched even though the book has it
+        handler.handle_input("setoption name BookDepth value 4");
+        assert_eq!(handler.game_ply, 4);
+        assert!(handler.book_move(&params).is_none());
+
+        handler.handle_input("setoption name BookDepth value 5");
+        assert_eq!(handler.book_move(&params), bb5);
+
+        // Playing it counts as a book hit, until the next game
+        assert_eq!(handler.book_hits, 0);
+        handler.handle_input("go depth 1");
+        assert_eq!(handler.book_hits, 1);
+        handler.handle_input("ucinewgame");
+        assert_eq!(handler.book_hits, 0);
+    }
+
+    #[test]
+    fn test_go_perft_parsed() {
+        assert!(matches!(parse_command("go perft 3"), UciCommand::Perft(3)));
+        assert!(matches!(parse_command("go perft"), UciCommand::Perft(1)));
+        assert!(matches!(parse_command("go depth 3"), UciCommand::Go(_)));
+
+        // Runs from the current position without starting a search
+        let mut handler = UciHandler::new();
+        handler.handle_input("position startpos moves e2e4");
+        handler.handle_input("go perft 2");
+        assert_eq!(crate::uci::perft(&handler.board, 2), 600);
+    }
+
+    #[test]
+    fn test_chess960_castling_king_takes_rook() {
+        // A Chess960 start with the kingside knights gone
+        let fen = "rbbqk2r/pppppppp/8/8/8/8/PPPPPPPP/RBBQK2R w KQkq - 0 1";
+        let mut handler = UciHandler::new();
+        handler.handle_input("setoption name UCI_Chess960 value true");
+        assert!(handler.chess960 && handler.searcher.chess960);
+
+        // King takes rook castles; the king's two-square move does not parse
+        handler.handle_input(&format!("position fen {} moves e1h1", fen));
+        assert_eq!(handler.game_ply, 1);
+        assert_eq!(handler.board.piece_at(Square::G1), Some((Piece::King, Color::White)));
+        assert_eq!(handler.board.piece_at(Square::F1), Some((Piece::Rook, Color::White)));
+        let board = Board::from_fen(fen).unwrap();
+        assert!(parse_move_with(&board, "e1g1", true).is_none());
+
+        // Written back the same way, and the standard way with the option off
+        let castle = parse_move_with(&board, "e1h1", true).unwrap();
+        assert_eq!(move_to_uci(&board, castle, true), "e1h1");
+        assert_eq!(move_to_uci(&board, castle, false), "e1g1");
+        assert_eq!(parse_move(&board, "e1g1"), Some(castle));
+    }
+
+    #[test]
+    fn test_eval_command_start_position() {
+        assert!(matches!(parse_command("eval"), UciCommand::Eval));
+
+        let mut handler = UciHandler::new();
+        handler.handle_input("position startpos");
+        handler.handle_input("eval");
+        let eval = handler.eval_breakdown();
+        assert!(eval.total.raw().abs() <= 50, "{}", eval.total);
+        assert!(eval.hce.raw().abs() <= 50);
+        assert_eq!(eval.material, 0);
+        assert_eq!(eval.phase, 0);
+        assert!(!eval.endgame);
+
+        // White's point of view either way round
+        handler.handle_input("position fen 4k3/8/8/8/8/8/8/R3K3 b - - 0 1");
+        let eval = handler.eval_breakdown();
+        assert!(eval.total.raw() > 300);
+        assert_eq!(eval.material, 500);
+    }
+
+    #[test]
+    fn test_dead_draw_moves_at_once() {
+        let mut handler = UciHandler::new();
+        let timed = SearchParams { wtime: Some(60_000), btime: Some(60_000), ..Default::default() };
+
+        handler.handle_input("position fen 4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1");
+        let limits = handler.dead_draw_limits(SearchLimits::from_params(&timed), &timed);
+        assert_eq!(limits.depth, Some(Depth::new(1)));
+
+        // Analysis keeps going
+        let infinite = SearchParams { infinite: true, ..Default::default() };
+        let limits = handler.dead_draw_limits(SearchLimits::from_params(&infinite), &infinite);
+        assert_eq!(limits.depth, None);
+
+        handler.handle_input("position fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        let limits = handler.dead_draw_limits(SearchLimits::from_params(&timed), &timed);
+        assert_eq!(limits.depth, None);
+    }
+
+    #[test]
+    fn test_register_is_accepted() {
+        assert!(matches!(parse_command("register later"), UciCommand::Register));
+        assert!(matches!(parse_command("register name Someone code 1234"), UciCommand::Register));
+
+        let mut handler = UciHandler::new();
+        handler.handle_input("register later");
+        handler.handle_input("register name Someone code 1234");
+
+        // Still answering: the position and search go through as usual
+        handler.handle_input("position startpos moves e2e4");
+        assert_eq!(handler.board.turn(), Color::Black);
+        handler.handle_input("go depth 2");
+        assert!(handler.searcher.shared.tt.hashfull() > 0);
+    }
+
+    #[test]
+    fn test_ucinewgame_keeps_options() {
+        let mut handler = UciHandler::new();
+        handler.handle_input("setoption name Hash value 32");
+        handler.handle_input("setoption name Threads value 2");
+        handler.handle_input("setoption name MultiPV value 3");
+        handler.handle_input("setoption name Contempt value 25");
+        handler.handle_input("position startpos");
+        handler.handle_input("go depth 2");
+        assert!(handler.searcher.shared.tt.hashfull() > 0);
+
+        handler.handle_input("ucinewgame");
+        assert_eq!(handler.searcher.shared.tt.size_mb(), 32);
+        assert_eq!(handler.searcher.threads(), 2.min(max_threads()));
+        assert_eq!(handler.multi_pv, 3);
+        assert_eq!(handler.searcher.params.contempt, 25);
+        assert_eq!(handler.searcher.shared.tt.hashfull(), 0);
+    }
+
+    #[test]
+    fn test_threefold_from_game_history() {
+        // The knights go out and back twice: Ng8 now makes the start position's third occurrence
+        let mut handler = UciHandler::new();
+        handler.handle_input("position startpos moves g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1");
+        let ng8 = parse_move(&handler.board, "f6g8").unwrap();
+        let start = handler.board.make_move_new(ng8).hash();
+        assert_eq!(start, Board::default().hash());
+        assert_eq!(handler.searcher.game_history.len(), 7);
+        assert_eq!(handler.halfmove_clock, 7);
+        assert!(handler.searcher.is_repetition(start, Ply::new(1), 8));
+
+        // Once out and back: Ng8 only repeats it a second time, no draw yet
+        handler.handle_input("position startpos moves g1f3 g8f6 f3g1");
+        assert!(!handler.searcher.is_repetition(start, Ply::new(1), 4));
+
+        // ...but repeating a position from inside the search is
+        let root = handler.board.hash();
+        handler.searcher.set_path_hash(Ply::new(1), start);
+        assert!(handler.searcher.is_repetition(start, Ply::new(3), 6));
+        assert!(handler.searcher.is_repetition(root, Ply::new(4), 7));
+    }
+}
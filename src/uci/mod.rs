@@ -8,7 +8,7 @@ mod handler;
 
 pub use handler::UciHandler;
 
-use crate::types::{Board, Move, Depth, Piece};
+use crate::types::{Board, BoardExt, Move, MoveFlag, Depth, Piece};
 use movegen::Square;
 
 /// UCI engine identification
@@ -68,6 +68,15 @@ impl SearchParams {
 
 /// Parse a move string (e.g., "e2e4", "e7e8q") into a Move for the given board
 pub fn parse_move(board: &Board, move_str: &str) -> Option<Move> {
+    parse_move_with(board, move_str, false)
+}
+
+/// `parse_move`, with `UCI_Chess960` castling rules when `chess960` is set.
+///
+/// Castling is accepted as the king taking its own rook either way. In
+/// Chess960 mode that is the only way to castle: a king move onto the
+/// castling square (`e1g1`) is just a king move.
+pub fn parse_move_with(board: &Board, move_str: &str, chess960: bool) -> Option<Move> {
     let move_str = move_str.trim().to_lowercase();
     if move_str.len() < 4 {
         return None;
@@ -93,25 +102,83 @@ pub fn parse_move(board: &Board, move_str: &str) -> Option<Move> {
         None
     };
 
-    // Find the matching legal move
+    // Only ever a move from the legal list (castling included)
+    let m = board.find_move(from, to, promo_piece)?;
+    let castle = matches!(m.flag(), MoveFlag::KingCastle | MoveFlag::QueenCastle);
+    let takes_rook = matches!(board.piece_at(to), Some((Piece::Rook, c)) if c == board.turn());
+    if chess960 && castle && !takes_rook {
+        return board
+            .generate_moves()
+            .iter()
+            .find(|x| x.from() == from && x.to() == to && !matches!(x.flag(), MoveFlag::KingCastle | MoveFlag::QueenCastle));
+    }
+    Some(m)
+}
+
+/// Is `m` a legal move in `board`
+pub fn is_legal(board: &Board, m: Move) -> bool {
+    board.generate_moves().iter().any(|legal| legal == m)
+}
+
+/// Number of leaf positions `depth` plies below `board` (move generator check)
+pub fn perft(board: &Board, depth: u32) -> u64 {
     let moves = board.generate_moves();
-    for m in moves.iter() {
-        if m.from() == from && m.to() == to {
-            // For promotions, also check the promotion piece
-            if let Some(p) = promo_piece {
-                if m.flag().promotion_piece() == Some(p) {
-                    return Some(m);
-                }
-            } else if m.flag().promotion_piece().is_none() {
-                return Some(m);
-            }
-        }
+    match depth {
+        0 => 1,
+        1 => moves.len() as u64,
+        _ => moves.iter().map(|m| perft(&board.make_move_new(m), depth - 1)).sum(),
     }
+}
 
-    None
+/// `perft` split by root move ("divide"), in move generation order
+pub fn perft_divide(board: &Board, depth: u32) -> Vec<(Move, u64)> {
+    board
+        .generate_moves()
+        .iter()
+        .map(|m| (m, perft(&board.make_move_new(m), depth.saturating_sub(1))))
+        .collect()
+}
+
+/// Halfmove clock and fullmove number of a FEN (its 5th and 6th fields).
+///
+/// GUIs sometimes send only the first four fields; missing or malformed
+/// counters default to 0 and 1.
+pub fn fen_clocks(fen: &str) -> (u32, u32) {
+    let mut fields = fen.split_whitespace().skip(4);
+    let halfmove = fields.next().and_then(|f| f.parse().ok()).unwrap_or(0);
+    let fullmove = fields.next().and_then(|f| f.parse().ok()).unwrap_or(1);
+    (halfmove, fullmove)
 }
 
 /// Format a move to UCI notation (e.g., "e2e4", "e7e8q")
 pub fn format_move(m: Move) -> String {
     m.to_uci()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perft_counts() {
+        let positions: [(&str, &[u64]); 4] = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902]),
+            // Kiwipete
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862]),
+            // En passant and rook checks on the fifth rank
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812]),
+            // Promotions and castling through attacks
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467]),
+        ];
+
+        for (fen, counts) in positions {
+            let board = Board::from_fen(fen).unwrap();
+            for (depth, &expected) in (1..).zip(counts) {
+                assert_eq!(perft(&board, depth), expected, "{fen} depth {depth}");
+            }
+            let divide = perft_divide(&board, 2);
+            assert_eq!(divide.len() as u64, counts[0]);
+            assert_eq!(divide.iter().map(|&(_, n)| n).sum::<u64>(), counts[1]);
+        }
+    }
+}
@@ -14,7 +14,7 @@ pub enum UciCommand {
     IsReady,
     /// "setoption name X value Y"
     SetOption { name: String, value: Option<String> },
-    /// "register" - Registration (we ignore this)
+    /// "register later" / "register name X code Y" - accepted, nothing to register
     Register,
     /// "ucinewgame" - New game starting
     UciNewGame,
@@ -22,6 +22,8 @@ pub enum UciCommand {
     Position { fen: Option<String>, moves: Vec<String> },
     /// "go ..." - Start searching
     Go(SearchParams),
+    /// "go perft <depth>" - Debug: count leaf nodes per root move (non-standard)
+    Perft(u32),
     /// "stop" - Stop searching
     Stop,
     /// "ponderhit" - Opponent played expected move
@@ -30,6 +32,8 @@ pub enum UciCommand {
     Quit,
     /// "d" - Debug: display board (non-standard but common)
     Display,
+    /// "eval" - Debug: static evaluation breakdown (non-standard)
+    Eval,
     /// Unknown command
     Unknown(String),
 }
@@ -55,6 +59,7 @@ pub fn parse_command(input: &str) -> UciCommand {
         Some("ponderhit") => UciCommand::PonderHit,
         Some("quit") => UciCommand::Quit,
         Some("d") => UciCommand::Display,
+        Some("eval") => UciCommand::Eval,
         _ => UciCommand::Unknown(input.to_string()),
     }
 }
@@ -138,6 +143,9 @@ fn parse_go<'a>(parts: &mut impl Iterator<Item = &'a str>) -> UciCommand {
     let mut params = SearchParams::new();
     
     let tokens: Vec<&str> = parts.collect();
+    if tokens.first() == Some(&"perft") {
+        return UciCommand::Perft(tokens.get(1).and_then(|d| d.parse().ok()).unwrap_or(1));
+    }
     let mut i = 0;
 
     while i < tokens.len() {